@@ -0,0 +1,35 @@
+// Benchmarks of the graph construction and the closeness centrality on synthetic datasets of growing size
+// run with `cargo bench` (add `--features rayon` to measure the parallel centrality)
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use part3::graph_utils::{calculate_centrality, construct_graph};
+use part3::input::synthetic_customers;
+
+// dataset sizes benchmarked, and the seed of their customers so every run measures the same graphs
+const SIZES: [usize; 3] = [100, 500, 1000];
+const SEED: u64 = 42;
+
+// Function to benchmark construct_graph (every pair of customers is compared, so it grows quadratically)
+fn bench_construct_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construct_graph");
+    group.sample_size(10); // one run on 1000 customers takes about a second
+    for size in SIZES {
+        let customers = synthetic_customers(size, SEED);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &customers, |b, customers| b.iter(|| construct_graph(customers)));
+    }
+    group.finish();
+}
+
+// Function to benchmark calculate_centrality on a graph built once per size
+fn bench_calculate_centrality(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_centrality");
+    group.sample_size(10);
+    for size in SIZES {
+        let customers = synthetic_customers(size, SEED);
+        let graph = construct_graph(&customers);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &graph, |b, graph| b.iter(|| calculate_centrality(graph)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_construct_graph, bench_calculate_centrality);
+criterion_main!(benches);
@@ -0,0 +1,624 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::{HashMap, HashSet};
+use crate::customer::{ChurnStatus, Customer, CharacteristicSummary, SimilarityConfig, NumericRanges, compute_shared_characteristic_summary, compute_mutual_characteristic_summary, inverse_frequencies, bootstrap_prevalence, PrevalenceInterval, UnknownPolicy, BucketSpec, DEFAULT_PRECISION};
+use crate::graph_utils::{
+    construct_graph_with_progress, construct_graph_within_churn_groups, calculate_centrality_with, calculate_harmonic_centrality, calculate_weighted_centrality, calculate_pagerank, calculate_eigenvector_centrality,
+    identify_high_centrality_nodes_with, keep_top_k_edges, nodes_in_small_components, top_k_by_centrality, top_k_central_nodes, NodeOrder, ThresholdMode, default_attribute_weights, EdgeWeight, NEIGHBOR_THRESHOLD, FLOYD_WARSHALL_MAX_NODES,
+};
+
+// centrality measure used to pick the high centrality nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CentralityAlgorithm {
+    Closeness, // Wasserman-Faust closeness (calculate_centrality), the default
+    PageRank, // calculate_pagerank with damping 0.85 and 100 iterations
+    Eigenvector, // calculate_eigenvector_centrality with at most 100 iterations and tolerance 1e-9
+    WeightedCloseness, // calculate_weighted_centrality: closeness where an edge sharing more characteristics is shorter
+    Harmonic, // calculate_harmonic_centrality: sum of 1 / distance, well-behaved on disconnected graphs
+}
+
+// struct holding every tunable parameter of the analysis pipeline (create it with AnalysisConfig::builder())
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisConfig {
+    pub neighbor_threshold: f64, // minimum weighted number of shared characteristics for two customers to be connected
+    pub attribute_weights: HashMap<String, f64>, // weight of each attribute toward the neighbor threshold
+    pub threshold_factor: f64, // nodes with centrality above threshold_factor * mean centrality (see threshold_mode) are high centrality nodes
+    pub threshold_mode: ThresholdMode, // statistic the threshold factor multiplies (the mean by default, or the median or a percentile)
+    pub top_n: usize, // number of shared characteristics kept per high centrality node
+    pub record_limit: usize, // maximum number of customers analyzed
+    pub algorithm: CentralityAlgorithm,
+    pub similarity: SimilarityConfig, // bucket edges of the numeric fields compared by group
+    pub show_progress: bool, // print the progress of the graph construction to stderr
+    pub floyd_warshall_max_nodes: usize, // closeness uses Floyd-Warshall instead of repeated Dijkstra on graphs up to this size
+    pub min_component_size: Option<usize>, // leave the customers of smaller connected components out of the centrality (none if None)
+    pub max_neighbors: Option<usize>, // keep only each customer's k most similar neighbors (see keep_top_k_edges); all of them if None
+    pub most_central: Option<usize>, // select exactly this many most central nodes instead of thresholding with threshold_factor
+    pub top_nodes: Option<usize>, // profile only this many of the high centrality nodes (the most central ones); all of them if None
+    pub bootstrap_resamples: Option<usize>, // bootstrap the prevalence of each shared characteristic with this many resamples (not at all if None)
+    pub bootstrap_seed: u64, // seed of the bootstrap resampling
+    // compute the centrality once over the whole graph and split the high centrality nodes by churn status,
+    // instead of computing it within each group's subgraph
+    pub combined_graph: bool,
+    pub within_churn_groups: bool, // only connect customers with the same churn status (no edges across the groups)
+    pub node_order: NodeOrder, // order of the high centrality nodes in the results (node index by default)
+    pub precision: usize, // number of decimal places of the printed percentages
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            neighbor_threshold: NEIGHBOR_THRESHOLD as f64,
+            attribute_weights: default_attribute_weights(),
+            threshold_factor: 1.1,
+            threshold_mode: ThresholdMode::Mean,
+            top_n: 4,
+            record_limit: 1000,
+            algorithm: CentralityAlgorithm::Closeness,
+            similarity: SimilarityConfig::default(),
+            show_progress: false,
+            floyd_warshall_max_nodes: FLOYD_WARSHALL_MAX_NODES,
+            min_component_size: None,
+            max_neighbors: None,
+            most_central: None,
+            top_nodes: None,
+            bootstrap_resamples: None,
+            bootstrap_seed: 0,
+            combined_graph: false,
+            within_churn_groups: false,
+            node_order: NodeOrder::Index,
+            precision: DEFAULT_PRECISION,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    // Function to start building a config from the defaults
+    pub fn builder() -> AnalysisConfigBuilder {
+        AnalysisConfigBuilder { config: AnalysisConfig::default() }
+    }
+}
+
+// builder for AnalysisConfig; every setter overrides one default
+#[derive(Debug, Clone)]
+pub struct AnalysisConfigBuilder {
+    config: AnalysisConfig,
+}
+
+impl AnalysisConfigBuilder {
+    pub fn neighbor_threshold(mut self, neighbor_threshold: f64) -> Self {
+        self.config.neighbor_threshold = neighbor_threshold;
+        self
+    }
+
+    // sets the weight of a single attribute (see graph_utils::ATTRIBUTE_NAMES), keeping the others
+    pub fn attribute_weight(mut self, attribute: &str, weight: f64) -> Self {
+        self.config.attribute_weights.insert(attribute.to_string(), weight);
+        self
+    }
+
+    pub fn threshold_factor(mut self, threshold_factor: f64) -> Self {
+        self.config.threshold_factor = threshold_factor;
+        self
+    }
+
+    pub fn threshold_mode(mut self, threshold_mode: ThresholdMode) -> Self {
+        self.config.threshold_mode = threshold_mode;
+        self
+    }
+
+    pub fn top_n(mut self, top_n: usize) -> Self {
+        self.config.top_n = top_n;
+        self
+    }
+
+    pub fn record_limit(mut self, record_limit: usize) -> Self {
+        self.config.record_limit = record_limit;
+        self
+    }
+
+    // leaves the customers of connected components with fewer than min_component_size customers out of the centrality
+    pub fn min_component_size(mut self, min_component_size: usize) -> Self {
+        self.config.min_component_size = Some(min_component_size);
+        self
+    }
+
+    // turns the graph into a k-nearest-neighbor graph, so no customer has more than max_neighbors neighbors
+    pub fn max_neighbors(mut self, max_neighbors: usize) -> Self {
+        self.config.max_neighbors = Some(max_neighbors);
+        self
+    }
+
+    // selects the k most central nodes of each group as its high centrality nodes, instead of thresholding
+    pub fn most_central(mut self, k: usize) -> Self {
+        self.config.most_central = Some(k);
+        self
+    }
+
+    // keeps only the top_nodes most central of the high centrality nodes
+    pub fn top_nodes(mut self, top_nodes: usize) -> Self {
+        self.config.top_nodes = Some(top_nodes);
+        self
+    }
+
+    // adds confidence intervals on the prevalence of each shared characteristic, from `resamples` bootstrap resamples
+    pub fn bootstrap(mut self, resamples: usize, seed: u64) -> Self {
+        self.config.bootstrap_resamples = Some(resamples);
+        self.config.bootstrap_seed = seed;
+        self
+    }
+
+    // computes the centrality on the whole graph once; the groups keep their nodes' share of it
+    pub fn combined_graph(mut self) -> Self {
+        self.config.combined_graph = true;
+        self
+    }
+
+    // builds the graph within each churn group only, leaving out the edges between churned and existing customers
+    pub fn within_churn_groups(mut self) -> Self {
+        self.config.within_churn_groups = true;
+        self
+    }
+
+    pub fn node_order(mut self, node_order: NodeOrder) -> Self {
+        self.config.node_order = node_order;
+        self
+    }
+
+    // prints the percentages with this many decimal places
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.config.precision = precision;
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: CentralityAlgorithm) -> Self {
+        self.config.algorithm = algorithm;
+        self
+    }
+
+    pub fn similarity(mut self, similarity: SimilarityConfig) -> Self {
+        self.config.similarity = similarity;
+        self
+    }
+
+    // leaves an attribute (see graph_utils::ATTRIBUTE_NAMES) out of the comparison, for both the edges and the reported traits
+    pub fn exclude_attribute(mut self, attribute: &str) -> Self {
+        self.config.similarity.excluded_attributes.insert(attribute.to_string());
+        self
+    }
+
+    // compares the count fields within this tolerance after min-max normalizing them over the analyzed customers
+    pub fn similarity_tolerance(mut self, tolerance: f64) -> Self {
+        self.config.similarity.similarity_tolerance = Some(tolerance);
+        self
+    }
+
+    // compares the income ranges ordinally: ranges at most max_distance brackets apart are similar
+    pub fn income_ordinal(mut self, max_distance: usize) -> Self {
+        self.config.similarity.income_max_distance = Some(max_distance);
+        self
+    }
+
+    // ranks the shared characteristics of each node by count x inverse frequency, so distinctive traits come before ubiquitous ones
+    pub fn weighted_ranking(mut self) -> Self {
+        self.config.similarity.weighted_ranking = true;
+        self
+    }
+
+    // compares the education levels ordinally: levels at most max_distance apart are similar
+    pub fn education_ordinal(mut self, max_distance: usize) -> Self {
+        self.config.similarity.education_max_distance = Some(max_distance);
+        self
+    }
+
+    // compares the months with the bank by the tenure bands with the given edges instead of TENURE_BAND_EDGES
+    pub fn tenure_bands(mut self, edges: &[f64]) -> Self {
+        self.config.similarity.mon_w_bank = BucketSpec::new(edges);
+        self
+    }
+
+    // puts the months inactive into buckets with the given edges (e.g. ACTIVITY_BUCKET_EDGES) instead of comparing the counts
+    pub fn mon_inactive_buckets(mut self, edges: &[f64]) -> Self {
+        self.config.similarity.mon_inactive_buckets = Some(BucketSpec::new(edges));
+        self
+    }
+
+    // puts the contacts from the bank into buckets with the given edges instead of comparing the counts
+    pub fn num_contact_buckets(mut self, edges: &[f64]) -> Self {
+        self.config.similarity.num_contact_buckets = Some(BucketSpec::new(edges));
+        self
+    }
+
+    // sets how "Unknown" categories compare (see UnknownPolicy)
+    pub fn unknown_policy(mut self, unknown_policy: UnknownPolicy) -> Self {
+        self.config.similarity.unknown_policy = unknown_policy;
+        self
+    }
+
+    pub fn show_progress(mut self, show_progress: bool) -> Self {
+        self.config.show_progress = show_progress;
+        self
+    }
+
+    pub fn floyd_warshall_max_nodes(mut self, floyd_warshall_max_nodes: usize) -> Self {
+        self.config.floyd_warshall_max_nodes = floyd_warshall_max_nodes;
+        self
+    }
+
+    pub fn build(self) -> AnalysisConfig {
+        self.config
+    }
+}
+
+// struct holding the results for one group of customers (churned or not churned)
+#[derive(Debug, Clone)]
+pub struct GroupAnalysis {
+    pub customers: Vec<Customer>, // the group's customers, in node order
+    pub centrality: HashMap<NodeIndex, f64>,
+    pub high_centrality_nodes: Vec<NodeIndex>, // in the config's node_order
+    pub summary: CharacteristicSummary, // shared characteristics of the high centrality nodes
+    pub mutual_summary: CharacteristicSummary, // characteristics the high centrality nodes share with each other
+    pub prevalence_intervals: Vec<PrevalenceInterval>, // bootstrapped intervals of the summary's characteristics (empty unless bootstrap_resamples is set)
+}
+
+// struct holding the results of the whole pipeline
+#[derive(Debug, Clone)]
+pub struct AnalysisResult<'a> {
+    pub graph: Graph<&'a Customer, u32, Undirected>,
+    pub churn: GroupAnalysis,
+    pub not_churn: GroupAnalysis,
+    pub dropped_nodes: Vec<NodeIndex>, // nodes of the components below min_component_size, left out of both groups (sorted)
+}
+
+// smallest number of customers the analysis can run on (closeness needs at least one other customer to be close to)
+pub const MIN_CUSTOMERS: usize = 2;
+
+// error describing why the analysis couldn't run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    NotEnoughCustomers(usize), // fewer than MIN_CUSTOMERS customers to analyze (e.g. an empty or header-only CSV file)
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AnalysisError::NotEnoughCustomers(count) => write!(f,
+                "need at least {} customers to build a graph, found {} (is the input empty, header-only or all rows invalid?)", MIN_CUSTOMERS, count),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+// Function to run the whole analysis: build the graph, split customers by churn status,
+// compute each group's centrality, pick the high centrality nodes and summarize their shared characteristics
+// returns an error instead of a meaningless result when there are fewer than MIN_CUSTOMERS customers
+pub fn run_analysis<'a>(customers: &'a [Customer], config: &AnalysisConfig) -> Result<AnalysisResult<'a>, AnalysisError> {
+    let customers = &customers[..customers.len().min(config.record_limit)];
+    if customers.len() < MIN_CUSTOMERS {
+        return Err(AnalysisError::NotEnoughCustomers(customers.len()));
+    }
+    run_analysis_on_graph(customers, build_graph(customers, config), config)
+}
+
+// Function to build the graph of the customers the way run_analysis does (before the max_neighbors pruning),
+// e.g. to save it with graph_utils::save_graph and skip building it on the next run
+pub fn build_graph<'a>(customers: &'a [Customer], config: &AnalysisConfig) -> Graph<&'a Customer, u32, Undirected> {
+    let config = &with_dataset_passes(customers, config);
+    let progress = |done, total| {
+        if config.show_progress {
+            eprintln!("Building graph: {}% ({} of {} customers)", done * 100 / total, done, total);
+        }
+    };
+    if config.within_churn_groups {
+        construct_graph_within_churn_groups(customers, &config.attribute_weights, config.neighbor_threshold, &config.similarity, progress)
+    } else {
+        construct_graph_with_progress(customers, &config.attribute_weights, config.neighbor_threshold, &config.similarity, progress)
+    }
+}
+
+// Function to run the analysis like run_analysis on a graph already built from the customers (by build_graph, or reloaded
+// with graph_utils::load_graph); the nodes must be the customers, in order
+pub fn run_analysis_on_graph<'a>(customers: &'a [Customer], mut graph: Graph<&'a Customer, u32, Undirected>, config: &AnalysisConfig) -> Result<AnalysisResult<'a>, AnalysisError> {
+    let customers = &customers[..customers.len().min(config.record_limit)];
+    if customers.len() < MIN_CUSTOMERS {
+        return Err(AnalysisError::NotEnoughCustomers(customers.len()));
+    }
+    let config = &with_dataset_passes(customers, config);
+    if let Some(max_neighbors) = config.max_neighbors { // sparser graph on permissive thresholds
+        graph = keep_top_k_edges(&graph, max_neighbors);
+    }
+
+    // Splitting customers into two groups: churned customers and customers who haven't churned (churn=stop using card)
+    // customers with an unknown churn status are in neither group
+    // customers in tiny components are noise: they stay in the graph (so the node indices don't change) but not in the groups
+    let dropped_nodes = config.min_component_size.map_or_else(Vec::new, |min_size| nodes_in_small_components(&graph, min_size));
+    let dropped: HashSet<NodeIndex> = dropped_nodes.iter().copied().collect();
+    // in the combined mode the centrality and the threshold come from all the (kept) customers, whatever their churn status
+    let combined = config.combined_graph.then(|| {
+        let nodes: Vec<NodeIndex> = graph.node_indices().filter(|node| !dropped.contains(node)).collect();
+        let centrality = group_centrality(&graph, &nodes, config);
+        let high_centrality_nodes = select_high_centrality_nodes(&centrality, config);
+        CombinedCentrality { centrality, high_centrality_nodes }
+    });
+    let churn = analyze_group(&graph, customers, ChurnStatus::Attrited, &dropped, combined.as_ref(), config);
+    let not_churn = analyze_group(&graph, customers, ChurnStatus::Existing, &dropped, combined.as_ref(), config);
+    Ok(AnalysisResult { graph, churn, not_churn, dropped_nodes })
+}
+
+// Function to fill in the parts of the similarity config that are computed over the whole dataset
+fn with_dataset_passes(customers: &[Customer], config: &AnalysisConfig) -> AnalysisConfig {
+    // with a similarity tolerance the count fields are normalized, which needs their ranges over the dataset first
+    let mut config = config.clone();
+    if config.similarity.similarity_tolerance.is_some() && config.similarity.ranges.is_none() {
+        config.similarity.ranges = NumericRanges::from_customers(customers);
+    }
+    // likewise the weighted ranking of the shared characteristics needs how rare each characteristic is in the dataset
+    if config.similarity.weighted_ranking && config.similarity.inverse_frequencies.is_none() {
+        config.similarity.inverse_frequencies = Some(inverse_frequencies(customers, &config.similarity));
+    }
+    config
+}
+
+// Function to fingerprint everything build_graph depends on (the customers, the neighbor threshold, the attribute weights,
+// whether it stays within the churn groups and the similarity config), so a saved graph is only reused for the same input and config. The hash comes from the
+// standard library's DefaultHasher, which may change between Rust versions; a changed hash only means rebuilding the graph
+pub fn graph_fingerprint(customers: &[Customer], config: &AnalysisConfig) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    serde_json::to_string(customers).unwrap_or_default().hash(&mut hasher);
+    config.neighbor_threshold.to_bits().hash(&mut hasher);
+    config.within_churn_groups.hash(&mut hasher);
+    let mut weights: Vec<(&String, u64)> = config.attribute_weights.iter().map(|(name, weight)| (name, weight.to_bits())).collect();
+    weights.sort();
+    weights.hash(&mut hasher);
+    // the hash maps and sets are hashed in a sorted order; the inverse frequencies only rank the traits, they don't change the graph
+    let mut excluded: Vec<&String> = config.similarity.excluded_attributes.iter().collect();
+    excluded.sort();
+    excluded.hash(&mut hasher);
+    let similarity = SimilarityConfig { excluded_attributes: HashSet::new(), inverse_frequencies: None, ..config.similarity.clone() };
+    format!("{:?}", similarity).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Function to pick the high centrality nodes: the most_central nodes if set, else those above the threshold
+fn select_high_centrality_nodes(centrality: &HashMap<NodeIndex, f64>, config: &AnalysisConfig) -> Vec<NodeIndex> {
+    match config.most_central {
+        Some(k) => top_k_central_nodes(centrality, k),
+        None => identify_high_centrality_nodes_with(centrality, config.threshold_factor, config.threshold_mode),
+    }
+}
+
+// Function to run the centrality and summary steps for the group of customers with the given churn status
+// the centrality and the high centrality nodes are keyed by the nodes of the full graph, and the summary counts
+// the characteristics shared with all neighbors (of both groups)
+fn analyze_group<E: Sync + Clone + EdgeWeight>(
+    graph: &Graph<&Customer, E, Undirected>,
+    all_customers: &[Customer],
+    status: ChurnStatus,
+    dropped: &HashSet<NodeIndex>, // nodes left out of every group (see AnalysisConfig::min_component_size)
+    combined: Option<&CombinedCentrality>, // centrality of the whole graph in the combined mode; None computes the group's own
+    config: &AnalysisConfig,
+) -> GroupAnalysis {
+    let group_nodes: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&node| graph[node].churn_status == status && !dropped.contains(&node))
+        .collect();
+    let customers: Vec<Customer> = group_nodes.iter().map(|&node| graph[node].clone()).collect();
+    let (centrality, mut high_centrality_nodes) = match combined {
+        Some(combined) => { // the group's share of the whole graph's centrality and high centrality nodes
+            let centrality: HashMap<NodeIndex, f64> = group_nodes.iter()
+                .filter_map(|node| combined.centrality.get(node).map(|&value| (*node, value)))
+                .collect();
+            let high_centrality_nodes = combined.high_centrality_nodes.iter().copied().filter(|node| centrality.contains_key(node)).collect();
+            (centrality, high_centrality_nodes)
+        }
+        None => {
+            let centrality = group_centrality(graph, &group_nodes, config);
+            let high_centrality_nodes = select_high_centrality_nodes(&centrality, config);
+            (centrality, high_centrality_nodes)
+        }
+    };
+    if let Some(top_nodes) = config.top_nodes { // focus the summary on the most central nodes
+        high_centrality_nodes = top_k_by_centrality(&high_centrality_nodes, &centrality, top_nodes);
+    }
+    let high_centrality_nodes = config.node_order.sort(&high_centrality_nodes, &centrality);
+    let summary = compute_shared_characteristic_summary(&high_centrality_nodes, all_customers, graph, config.top_n, &config.similarity);
+    let mutual_summary = compute_mutual_characteristic_summary(&high_centrality_nodes, all_customers, graph, &config.similarity);
+    let prevalence_intervals = config.bootstrap_resamples.map_or_else(Vec::new, |resamples| {
+        bootstrap_prevalence(&high_centrality_nodes, all_customers, graph, config.top_n, &config.similarity, resamples, config.bootstrap_seed)
+    });
+    GroupAnalysis { customers, centrality, high_centrality_nodes, summary, mutual_summary, prevalence_intervals }
+}
+
+// struct holding the centrality of the whole graph and its high centrality nodes (see AnalysisConfig::combined_graph)
+struct CombinedCentrality {
+    centrality: HashMap<NodeIndex, f64>,
+    high_centrality_nodes: Vec<NodeIndex>,
+}
+
+// Function to compute a group's centrality with the chosen algorithm, within the subgraph of the group's nodes
+// the subgraph keeps the order of group_nodes (its node i is group_nodes[i]),
+// and the values are keyed back by the nodes of the full graph
+fn group_centrality<E: Sync + Clone + EdgeWeight>(
+    graph: &Graph<&Customer, E, Undirected>,
+    group_nodes: &[NodeIndex], // nodes of the group in the full graph, in ascending order
+    config: &AnalysisConfig,
+) -> HashMap<NodeIndex, f64> {
+    let members: HashSet<NodeIndex> = group_nodes.iter().copied().collect();
+    let subgraph = graph.filter_map(|node, &customer| members.contains(&node).then_some(customer), |_, weight| Some(weight.clone()));
+    let centrality = match config.algorithm {
+        CentralityAlgorithm::Closeness => calculate_centrality_with(&subgraph, config.floyd_warshall_max_nodes),
+        CentralityAlgorithm::WeightedCloseness => calculate_weighted_centrality(&subgraph),
+        CentralityAlgorithm::PageRank => calculate_pagerank(&subgraph, 0.85, 100),
+        CentralityAlgorithm::Eigenvector => calculate_eigenvector_centrality(&subgraph, 100, 1e-9),
+        CentralityAlgorithm::Harmonic => calculate_harmonic_centrality(&subgraph),
+    };
+    centrality.into_iter().map(|(node, value)| (group_nodes[node.index()], value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::tests::{create_sample_customer1, create_sample_customer2, create_sample_customer3};
+    use crate::graph_utils::{calculate_centrality, identify_high_centrality_nodes, SavedGraph};
+
+    // test that the builder keeps the defaults and applies the overrides
+    #[test]
+    fn test_builder_defaults() {
+        assert_eq!(AnalysisConfig::builder().build(), AnalysisConfig::default());
+        assert_eq!(AnalysisConfig::default().top_n, 4);
+        let config = AnalysisConfig::builder().threshold_factor(1.5).top_n(6).record_limit(10).algorithm(CentralityAlgorithm::PageRank).attribute_weight("Card Type", 3.0).build();
+        assert_eq!(config.threshold_factor, 1.5);
+        assert_eq!(config.top_n, 6);
+        assert_eq!(config.record_limit, 10);
+        assert_eq!(config.algorithm, CentralityAlgorithm::PageRank);
+        assert_eq!(config.attribute_weights["Card Type"], 3.0);
+        assert_eq!(config.attribute_weights["Age"], 1.0);
+        assert_eq!(config.neighbor_threshold, 2.0);
+    }
+
+    // test that with top_nodes(3) exactly the three most central high centrality nodes are profiled
+    #[test]
+    fn test_top_nodes_limit() {
+        // a chain 0 - 1 - ... - 6: the numeric fields are all equal (8 shared traits), and each customer shares two categorical
+        // traits with the customer before it and the other two with the customer after it, so only neighbors in the chain reach 10
+        let customers: Vec<Customer> = (0..7).map(|i| {
+            let mut customer = create_sample_customer1();
+            customer.one_hot_encoding.education_level = format!("Education {}", i / 2);
+            customer.one_hot_encoding.income_range = format!("Income {}", i / 2);
+            customer.one_hot_encoding.marital_status = format!("Status {}", (i + 1) / 2);
+            customer.one_hot_encoding.card_type = format!("Card {}", (i + 1) / 2);
+            customer
+        }).collect();
+        let config = AnalysisConfig::builder().neighbor_threshold(10.0).threshold_factor(0.5).build();
+        let all = run_analysis(&customers, &config).unwrap();
+        assert_eq!(all.graph.edge_count(), 6);
+        assert!(all.not_churn.high_centrality_nodes.len() > 3);
+
+        let top = run_analysis(&customers, &AnalysisConfig { top_nodes: Some(3), node_order: NodeOrder::Centrality, ..config.clone() }).unwrap();
+        // the middle of the chain is the most central, then its two neighbors (tied, lowest index first)
+        assert_eq!(top.not_churn.high_centrality_nodes, [3, 2, 4].map(NodeIndex::new));
+        assert_eq!(top.not_churn.summary.node_count, 3);
+        let by_index = run_analysis(&customers, &AnalysisConfig { top_nodes: Some(3), ..config }).unwrap();
+        assert_eq!(by_index.not_churn.high_centrality_nodes, [2, 3, 4].map(NodeIndex::new)); // the same nodes, in index order
+    }
+
+    // test that with a minimum component size only the chain of 7 customers is analyzed, not the pair and the isolated customer
+    #[test]
+    fn test_min_component_size() {
+        let mut customers: Vec<Customer> = (0..7).map(|i| { // chain 0 - 1 - ... - 6 like in test_top_nodes_limit
+            let mut customer = create_sample_customer1();
+            customer.one_hot_encoding.education_level = format!("Education {}", i / 2);
+            customer.one_hot_encoding.income_range = format!("Income {}", i / 2);
+            customer.one_hot_encoding.marital_status = format!("Status {}", (i + 1) / 2);
+            customer.one_hot_encoding.card_type = format!("Card {}", (i + 1) / 2);
+            customer
+        }).collect();
+        for label in ["Pair", "Pair", "Alone"] { // identical pair (12 shared traits), and a customer only sharing the 8 numeric traits
+            let mut customer = create_sample_customer1();
+            customer.one_hot_encoding.education_level = label.to_string();
+            customer.one_hot_encoding.income_range = label.to_string();
+            customer.one_hot_encoding.marital_status = label.to_string();
+            customer.one_hot_encoding.card_type = label.to_string();
+            customers.push(customer);
+        }
+        let config = AnalysisConfig::builder().neighbor_threshold(10.0).build();
+        let everything = run_analysis(&customers, &config).unwrap();
+        assert!(everything.dropped_nodes.is_empty());
+        assert_eq!(everything.not_churn.customers.len(), 10);
+
+        let result = run_analysis(&customers, &AnalysisConfig { min_component_size: Some(3), ..config }).unwrap();
+        assert_eq!(result.dropped_nodes, [7, 8, 9].map(NodeIndex::new));
+        assert_eq!(result.graph.node_count(), 10); // the graph itself is unchanged
+        assert_eq!(result.not_churn.customers.len(), 7);
+        assert!(result.not_churn.centrality.keys().all(|node| node.index() < 7));
+    }
+
+    // test that the combined mode thresholds the centrality of the whole graph and splits its high centrality nodes by churn status
+    #[test]
+    fn test_combined_graph() {
+        let customers: Vec<Customer> = (0..7).map(|i| { // chain 0 - 1 - ... - 6 like in test_top_nodes_limit, alternating churn status
+            let mut customer = create_sample_customer1();
+            customer.one_hot_encoding.education_level = format!("Education {}", i / 2);
+            customer.one_hot_encoding.income_range = format!("Income {}", i / 2);
+            customer.one_hot_encoding.marital_status = format!("Status {}", (i + 1) / 2);
+            customer.one_hot_encoding.card_type = format!("Card {}", (i + 1) / 2);
+            customer.churn_status = if i % 2 == 1 { ChurnStatus::Attrited } else { ChurnStatus::Existing };
+            customer
+        }).collect();
+        let config = AnalysisConfig::builder().neighbor_threshold(10.0).combined_graph().build();
+        let result = run_analysis(&customers, &config).unwrap();
+        // the middle of the chain (2, 3 and 4) is above 1.1 x the mean closeness of the whole chain
+        let full_centrality = calculate_centrality(&result.graph);
+        let mut expected = identify_high_centrality_nodes(&full_centrality, config.threshold_factor);
+        expected.sort();
+        assert_eq!(expected, [2, 3, 4].map(NodeIndex::new));
+
+        let sorted = |nodes: &[NodeIndex]| { let mut nodes = nodes.to_vec(); nodes.sort(); nodes };
+        assert_eq!(sorted(&result.churn.high_centrality_nodes), vec![NodeIndex::new(3)]);
+        assert_eq!(sorted(&result.not_churn.high_centrality_nodes), vec![NodeIndex::new(2), NodeIndex::new(4)]);
+        for (node, value) in result.churn.centrality.iter().chain(&result.not_churn.centrality) {
+            assert_eq!(*value, full_centrality[node]);
+        }
+        assert_eq!(result.churn.centrality.len() + result.not_churn.centrality.len(), 7);
+    }
+
+    // test that the analysis of a reloaded graph matches the analysis that built it, and that the fingerprint follows the config
+    #[test]
+    fn test_run_analysis_on_saved_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let config = AnalysisConfig::default();
+        let built = run_analysis(&customers, &config).unwrap();
+        let saved = SavedGraph::from_graph(&build_graph(&customers, &config), graph_fingerprint(&customers, &config));
+        let reloaded = run_analysis_on_graph(&saved.customers, saved.graph(), &config).unwrap();
+        assert_eq!(reloaded.churn.high_centrality_nodes, built.churn.high_centrality_nodes);
+        assert_eq!(reloaded.not_churn.summary, built.not_churn.summary);
+
+        assert_eq!(graph_fingerprint(&customers, &config), graph_fingerprint(&saved.customers, &config.clone()));
+        assert_ne!(graph_fingerprint(&customers, &config), graph_fingerprint(&customers, &AnalysisConfig::builder().neighbor_threshold(3.0).build()));
+        assert_ne!(graph_fingerprint(&customers, &config), graph_fingerprint(&customers[1..], &config));
+    }
+
+    // test that an empty input or a single customer gives an error instead of an empty or meaningless result
+    #[test]
+    fn test_not_enough_customers() {
+        let config = AnalysisConfig::default();
+        assert_eq!(run_analysis(&[], &config).unwrap_err(), AnalysisError::NotEnoughCustomers(0));
+        assert_eq!(run_analysis(&[create_sample_customer1()], &config).unwrap_err(), AnalysisError::NotEnoughCustomers(1));
+        // the record limit is applied first
+        let customers = vec![create_sample_customer1(), create_sample_customer2()];
+        assert!(run_analysis(&customers, &config).is_ok());
+        assert!(run_analysis(&customers, &AnalysisConfig::builder().record_limit(1).build()).is_err());
+        assert!(AnalysisError::NotEnoughCustomers(0).to_string().contains("found 0"));
+    }
+
+    // test that a non-default neighbor threshold and record limit change the computed edge set
+    #[test]
+    fn test_config_propagates_into_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let edges = |result: &AnalysisResult| {
+            let mut edges: Vec<(usize, usize)> = result.graph.edge_indices()
+                .map(|edge| result.graph.edge_endpoints(edge).unwrap())
+                .map(|(a, b)| (a.index(), b.index()))
+                .collect();
+            edges.sort();
+            edges
+        };
+
+        let default_result = run_analysis(&customers, &AnalysisConfig::default()).unwrap();
+        assert_eq!(edges(&default_result), vec![(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(default_result.churn.customers.len(), 2);
+        assert_eq!(default_result.not_churn.customers.len(), 2);
+
+        // only customer 1 and its twin share at least 7 characteristics
+        let strict_result = run_analysis(&customers, &AnalysisConfig::builder().neighbor_threshold(7.0).build()).unwrap();
+        assert_eq!(edges(&strict_result), vec![(0, 2)]);
+
+        let limited_result = run_analysis(&customers, &AnalysisConfig::builder().record_limit(2).build()).unwrap();
+        assert_eq!(limited_result.graph.node_count(), 2);
+        assert_eq!(edges(&limited_result), vec![(0, 1)]);
+    }
+}
@@ -0,0 +1,395 @@
+// Command line options of the churn analysis binary
+use std::path::PathBuf;
+use crate::customer::{CustomerFilter, RareThreshold, UnknownPolicy};
+use crate::graph_utils::ThresholdMode;
+
+// output format of the final analysis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text, // human-readable text (default)
+    Json, // a single JSON document (see report::AnalysisReport)
+    JsonLines, // one JSON object per high centrality customer and line, streamed (see report::write_profiles_jsonl)
+    Tree, // a JSON document with every high centrality customer, its neighbors and the traits shared with each (see report::TreeReport)
+}
+
+// struct holding the parsed command line options
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    pub format: OutputFormat,
+    pub output: Option<PathBuf>, // file the report is written to (stdout if None); the parse reports stay on stderr
+    pub graph_cache: Option<PathBuf>, // file the graph is saved to, and reloaded from when the input and config are unchanged
+    pub inputs: Vec<PathBuf>, // CSV files to analyze, merged in order (.csv, or .gz with the gzip feature)
+    pub dedup_key: Option<String>, // column identifying a customer, to keep only its first row across the inputs
+    pub drop_duplicates: bool, // drop customers equal on every field to an earlier one (they are only reported otherwise)
+    pub filters: Vec<CustomerFilter>, // conditions (ANDed) a customer must meet to be analyzed, e.g. card_type=Gold
+    pub strict_categories: bool, // report rows with an unrecognized category instead of mapping it to "Unknown"
+    pub side_by_side: bool, // one table comparing the churn groups instead of a summary per group
+    pub distinguishing: bool, // also report the attributes on which the high centrality customers differ from their neighbors
+    pub dry_run: bool, // only read the input and print a data-quality report, without building the graph
+    pub interactive: bool, // after the report, read customer indices from stdin and print their neighbors and centrality
+    pub strict_ranges: bool, // report rows with an out of range utilization ratio instead of clamping it
+    pub top_n: Option<usize>, // number of shared characteristics kept per high centrality node (config default if None)
+    pub weighted_traits: bool, // rank the shared characteristics by count x rarity instead of count
+    pub sort_by_centrality: bool, // report the high centrality nodes by descending centrality instead of by index
+    pub combined_graph: bool, // compute the centrality once on the whole graph and split the high centrality nodes by churn status
+    pub within_groups: bool, // only connect customers of the same churn group
+    pub tenure_bands: Option<Vec<f64>>, // edges of the months-with-the-bank bands, ascending (customer::TENURE_BAND_EDGES if None)
+    pub bucket_activity: bool, // compare the months inactive and the contacts by bucket (see ACTIVITY_BUCKET_EDGES) instead of exactly
+    pub min_component_size: Option<usize>, // leave the customers of smaller connected components out of the analysis (none if None)
+    pub max_neighbors: Option<usize>, // keep only each customer's k most similar neighbors (all of them if None)
+    pub precision: Option<usize>, // decimal places of the printed percentages (one if None)
+    pub most_central: Option<usize>, // take the K most central customers of each group instead of thresholding
+    pub top_nodes: Option<usize>, // profile only this many of the most central high centrality nodes (all of them if None)
+    pub progress: bool, // report the progress of the graph construction
+    pub collapse_below: Option<RareThreshold>, // collapse the characteristics below this count or share into "Other (rare)" in the printed summaries
+    pub quiet: bool, // print only the counts of each group's summary, not the breakdown by category
+    pub debug: bool, // print the adjacency list of the graph (only sensible for small runs)
+    pub threshold_factor: Option<f64>, // nodes above threshold_factor * mean centrality are high centrality nodes (config default if None)
+    pub threshold_mode: Option<ThresholdMode>, // statistic the threshold factor multiplies (config default, the mean, if None)
+    pub delimiter: Option<u8>, // CSV field delimiter (detected from the header line if None)
+    pub sample: Option<usize>, // analyze a random sample of this many customers instead of the first rows
+    pub seed: u64, // seed of the random sample and of the bootstrap, so the same seed gives the same results
+    pub unknown_policy: Option<UnknownPolicy>, // how "Unknown" categories and missing numbers compare (config default, matching only "Unknown", if None)
+    pub bootstrap: Option<usize>, // number of bootstrap resamples for the prevalence intervals (no intervals if None)
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions { format: OutputFormat::Text, output: None, graph_cache: None, inputs: vec![PathBuf::from("BankChurners.csv")], dedup_key: None, drop_duplicates: false, filters: Vec::new(), strict_categories: false, strict_ranges: false, dry_run: false, interactive: false, side_by_side: false, distinguishing: false, top_n: None, min_component_size: None, max_neighbors: None, precision: None, most_central: None, top_nodes: None, weighted_traits: false, sort_by_centrality: false, combined_graph: false, within_groups: false, tenure_bands: None, bucket_activity: false, progress: false, collapse_below: None, quiet: false, debug: false, threshold_factor: None, threshold_mode: None, delimiter: None, sample: None, seed: 0, unknown_policy: None, bootstrap: None }
+    }
+}
+
+// Function to parse the command line arguments (without the program name)
+// returns a message describing the problem if an option is unknown or has an invalid value
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter();
+    let mut inputs = Vec::new(); // replace the default input only if --input is given
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = args.next().ok_or("--format needs a value (text, json, jsonl or tree)")?;
+                options.format = match value.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    "jsonl" => OutputFormat::JsonLines,
+                    "tree" => OutputFormat::Tree,
+                    _ => return Err(format!("unknown format '{}', expected text, json, jsonl or tree", value)),
+                };
+            }
+            "--output" => {
+                options.output = Some(PathBuf::from(args.next().ok_or("--output needs a path")?));
+            }
+            "--input" => { // may be repeated to merge several files
+                let value = args.next().ok_or("--input needs a path")?;
+                inputs.push(PathBuf::from(value));
+            }
+            "--drop-duplicates" => options.drop_duplicates = true,
+            "--dedup-key" => {
+                options.dedup_key = Some(args.next().ok_or("--dedup-key needs a column name (e.g. CLIENTNUM)")?);
+            }
+            "--top-n" => {
+                let value = args.next().ok_or("--top-n needs a number")?;
+                options.top_n = Some(value.parse().map_err(|_| format!("invalid --top-n value '{}'", value))?);
+            }
+            "--precision" => {
+                let value = args.next().ok_or("--precision needs a number of decimal places")?;
+                options.precision = Some(value.parse().map_err(|_| format!("invalid --precision value '{}'", value))?);
+            }
+            "--most-central" => {
+                let value = args.next().ok_or("--most-central needs a number of customers")?;
+                options.most_central = Some(value.parse().map_err(|_| format!("invalid --most-central value '{}'", value))?);
+            }
+            "--top-nodes" => {
+                let value = args.next().ok_or("--top-nodes needs a number")?;
+                options.top_nodes = Some(value.parse().map_err(|_| format!("invalid --top-nodes value '{}'", value))?);
+            }
+            "--min-component-size" => {
+                let value = args.next().ok_or("--min-component-size needs a number of customers")?;
+                options.min_component_size = Some(value.parse().map_err(|_| format!("invalid --min-component-size value '{}'", value))?);
+            }
+            "--max-neighbors" => {
+                let value = args.next().ok_or("--max-neighbors needs a number")?;
+                options.max_neighbors = Some(value.parse().map_err(|_| format!("invalid --max-neighbors value '{}'", value))?);
+            }
+            "--bootstrap" => {
+                let value = args.next().ok_or("--bootstrap needs a number of resamples")?;
+                options.bootstrap = Some(value.parse().map_err(|_| format!("invalid --bootstrap value '{}'", value))?);
+            }
+            "--progress" => options.progress = true,
+            "--weighted-traits" => options.weighted_traits = true,
+            "--combined-graph" => options.combined_graph = true,
+            "--within-groups" => options.within_groups = true,
+            "--bucket-activity" => options.bucket_activity = true,
+            "--sort-by-centrality" => options.sort_by_centrality = true,
+            "--debug" => options.debug = true,
+            "--quiet" => options.quiet = true,
+            "--strict-categories" => options.strict_categories = true,
+            "--strict-ranges" => options.strict_ranges = true,
+            "--interactive" => options.interactive = true,
+            "--side-by-side" => options.side_by_side = true,
+            "--dry-run" => options.dry_run = true,
+            "--distinguishing" => options.distinguishing = true,
+            "--filter" => { // may be repeated, a customer must meet every filter
+                let value = args.next().ok_or("--filter needs a condition (e.g. card_type=Gold)")?;
+                options.filters.push(CustomerFilter::parse(&value)?);
+            }
+            "--sample" => {
+                let value = args.next().ok_or("--sample needs a number of customers")?;
+                options.sample = Some(value.parse().map_err(|_| format!("invalid --sample value '{}'", value))?);
+            }
+            "--seed" => {
+                let value = args.next().ok_or("--seed needs a number")?;
+                options.seed = value.parse().map_err(|_| format!("invalid --seed value '{}'", value))?;
+            }
+            "--threshold-mode" => {
+                let value = args.next().ok_or("--threshold-mode needs a mode (mean, median or a percentile like p75)")?;
+                options.threshold_mode = Some(match value.as_str() {
+                    "mean" => ThresholdMode::Mean,
+                    "median" => ThresholdMode::Median,
+                    _ => match value.strip_prefix('p').and_then(|percent| percent.parse::<f64>().ok()) {
+                        Some(percent) if (0.0..=100.0).contains(&percent) => ThresholdMode::Percentile(percent),
+                        _ => return Err(format!("invalid --threshold-mode '{}', expected mean, median or a percentile like p75", value)),
+                    },
+                });
+            }
+            "--unknown" => {
+                let value = args.next().ok_or("--unknown needs a policy (match, never or always)")?;
+                options.unknown_policy = Some(match value.as_str() {
+                    "match" => UnknownPolicy::Match,
+                    "never" => UnknownPolicy::NeverMatch,
+                    "always" => UnknownPolicy::AlwaysMatch,
+                    _ => return Err(format!("invalid --unknown policy '{}', expected match, never or always", value)),
+                });
+            }
+            "--collapse-below" => { // a count (e.g. 5) or a share of the category (e.g. 2%)
+                let value = args.next().ok_or("--collapse-below needs a count or a percentage (e.g. 5 or 2%)")?;
+                let invalid = || format!("invalid --collapse-below value '{}', expected a count or a percentage like 2%", value);
+                options.collapse_below = Some(match value.strip_suffix('%') {
+                    Some(percent) => RareThreshold::Percentage(percent.parse().map_err(|_| invalid())?),
+                    None => RareThreshold::Count(value.parse().map_err(|_| invalid())?),
+                });
+            }
+            "--tenure-bands" => { // e.g. 12,24,36 for <12, 12-24, 24-36 and >36 months
+                let value = args.next().ok_or("--tenure-bands needs comma-separated band edges in months (e.g. 12,24,36)")?;
+                let invalid = || format!("invalid --tenure-bands value '{}', expected ascending numbers like 12,24,36", value);
+                let edges: Vec<f64> = value.split(',').map(|edge| edge.trim().parse::<f64>()).collect::<Result<_, _>>().map_err(|_| invalid())?;
+                if !edges.windows(2).all(|pair| pair[0] < pair[1]) {
+                    return Err(invalid());
+                }
+                options.tenure_bands = Some(edges);
+            }
+            "--graph-cache" => options.graph_cache = Some(PathBuf::from(args.next().ok_or("--graph-cache needs a file path")?)),
+            "--delimiter" => {
+                let value = args.next().ok_or("--delimiter needs a character (e.g. ';' or tab)")?;
+                options.delimiter = Some(match value.as_str() {
+                    "tab" | "\\t" => b'\t',
+                    _ if value.len() == 1 && value.is_ascii() => value.as_bytes()[0],
+                    _ => return Err(format!("invalid --delimiter '{}', expected a single character or tab", value)),
+                });
+            }
+            "--threshold-factor" => {
+                let value = args.next().ok_or("--threshold-factor needs a number")?;
+                let factor: f64 = value.parse().map_err(|_| format!("invalid --threshold-factor value '{}'", value))?;
+                if !(factor.is_finite() && factor > 0.0) {
+                    return Err(format!("--threshold-factor must be positive, got {}", value));
+                }
+                options.threshold_factor = Some(factor);
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    if !inputs.is_empty() {
+        options.inputs = inputs;
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Function to turn a list of string literals into command line arguments
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // test that the format flag is parsed and text stays the default
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_args(args(&[])).unwrap().format, OutputFormat::Text);
+        assert_eq!(parse_args(args(&["--format", "json"])).unwrap().format, OutputFormat::Json);
+        assert_eq!(parse_args(args(&["--format", "jsonl"])).unwrap().format, OutputFormat::JsonLines);
+        assert_eq!(parse_args(args(&["--format", "tree"])).unwrap().format, OutputFormat::Tree);
+        assert!(parse_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_args(args(&["--format"])).is_err());
+    }
+
+    // test that --output takes a path and stdout stays the default
+    #[test]
+    fn test_parse_output() {
+        assert_eq!(parse_args(args(&[])).unwrap().output, None);
+        assert_eq!(parse_args(args(&["--output", "reports/churn.txt"])).unwrap().output, Some(PathBuf::from("reports/churn.txt")));
+        assert!(parse_args(args(&["--output"])).is_err());
+    }
+
+    // test that the input path defaults to BankChurners.csv and can be overridden, or repeated to merge files (optionally deduplicated)
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(parse_args(args(&[])).unwrap().inputs, [PathBuf::from("BankChurners.csv")]);
+        assert_eq!(parse_args(args(&["--input", "exports/march.csv.gz"])).unwrap().inputs, [PathBuf::from("exports/march.csv.gz")]);
+        let options = parse_args(args(&["--input", "march.csv", "--input", "april.csv", "--dedup-key", "CLIENTNUM"])).unwrap();
+        assert_eq!(options.inputs, [PathBuf::from("march.csv"), PathBuf::from("april.csv")]);
+        assert_eq!(options.dedup_key.as_deref(), Some("CLIENTNUM"));
+        assert!(!options.drop_duplicates);
+        assert!(parse_args(args(&["--drop-duplicates"])).unwrap().drop_duplicates);
+    }
+
+    // test that --top-n, --top-nodes, --most-central, --precision, --max-neighbors, --min-component-size and --bootstrap are parsed as numbers
+    #[test]
+    fn test_parse_top_n() {
+        assert_eq!(parse_args(args(&[])).unwrap().top_n, None);
+        assert_eq!(parse_args(args(&["--top-n", "6"])).unwrap().top_n, Some(6));
+        assert!(parse_args(args(&["--top-n", "six"])).is_err());
+        assert_eq!(parse_args(args(&[])).unwrap().top_nodes, None);
+        assert_eq!(parse_args(args(&["--top-nodes", "3"])).unwrap().top_nodes, Some(3));
+        assert_eq!(parse_args(args(&["--most-central", "10"])).unwrap().most_central, Some(10));
+        assert!(parse_args(args(&["--most-central", "ten"])).is_err());
+        assert_eq!(parse_args(args(&["--precision", "0"])).unwrap().precision, Some(0));
+        assert!(parse_args(args(&["--precision", "-1"])).is_err());
+        assert_eq!(parse_args(args(&[])).unwrap().max_neighbors, None);
+        assert_eq!(parse_args(args(&["--max-neighbors", "10"])).unwrap().max_neighbors, Some(10));
+        assert!(parse_args(args(&["--max-neighbors", "ten"])).is_err());
+        assert_eq!(parse_args(args(&["--min-component-size", "4"])).unwrap().min_component_size, Some(4));
+        assert_eq!(parse_args(args(&["--bootstrap", "200"])).unwrap().bootstrap, Some(200));
+    }
+
+    // test that progress output, the weighted trait ranking, the combined graph mode, the within-groups graph, the centrality order and the activity buckets are off unless their flags are given
+    #[test]
+    fn test_parse_progress() {
+        assert!(!parse_args(args(&[])).unwrap().progress);
+        assert!(parse_args(args(&["--progress", "--top-n", "3"])).unwrap().progress);
+        assert!(!parse_args(args(&[])).unwrap().weighted_traits);
+        assert!(parse_args(args(&["--weighted-traits"])).unwrap().weighted_traits);
+        assert!(!parse_args(args(&[])).unwrap().combined_graph);
+        assert!(parse_args(args(&["--combined-graph"])).unwrap().combined_graph);
+        assert!(!parse_args(args(&[])).unwrap().within_groups);
+        assert!(parse_args(args(&["--within-groups"])).unwrap().within_groups);
+        assert!(!parse_args(args(&[])).unwrap().sort_by_centrality);
+        assert!(parse_args(args(&["--sort-by-centrality"])).unwrap().sort_by_centrality);
+        assert!(!parse_args(args(&[])).unwrap().bucket_activity);
+        assert!(parse_args(args(&["--bucket-activity"])).unwrap().bucket_activity);
+    }
+
+    // test that the adjacency list is only printed with --debug, and categories and ranges are lenient unless --strict-categories / --strict-ranges is given
+    #[test]
+    fn test_parse_debug() {
+        assert!(!parse_args(args(&[])).unwrap().debug);
+        assert!(parse_args(args(&["--debug"])).unwrap().debug);
+        assert!(!parse_args(args(&[])).unwrap().strict_categories);
+        assert!(parse_args(args(&["--strict-categories"])).unwrap().strict_categories);
+        assert!(!parse_args(args(&[])).unwrap().strict_ranges);
+        assert!(parse_args(args(&["--strict-ranges"])).unwrap().strict_ranges);
+    }
+
+    // test that the detailed summary is the default and --quiet turns it off, and --interactive, --side-by-side, --distinguishing and --dry-run are off unless given
+    #[test]
+    fn test_parse_quiet() {
+        assert!(!parse_args(args(&[])).unwrap().quiet);
+        assert!(parse_args(args(&["--quiet", "--format", "text"])).unwrap().quiet);
+        assert!(!parse_args(args(&[])).unwrap().interactive);
+        assert!(parse_args(args(&["--interactive"])).unwrap().interactive);
+        assert!(!parse_args(args(&[])).unwrap().side_by_side);
+        assert!(parse_args(args(&["--side-by-side"])).unwrap().side_by_side);
+        assert!(!parse_args(args(&[])).unwrap().distinguishing);
+        assert!(parse_args(args(&["--distinguishing"])).unwrap().distinguishing);
+        assert!(!parse_args(args(&[])).unwrap().dry_run);
+        assert!(parse_args(args(&["--dry-run"])).unwrap().dry_run);
+    }
+
+    // test that --filter can be repeated and an unknown field is rejected
+    #[test]
+    fn test_parse_filters() {
+        assert!(parse_args(args(&[])).unwrap().filters.is_empty());
+        let options = parse_args(args(&["--filter", "card_type=Gold", "--filter", "income_range=$120K +"])).unwrap();
+        assert_eq!(options.filters, [CustomerFilter::parse("card_type=Gold").unwrap(), CustomerFilter::parse("income_range=$120K +").unwrap()]);
+        assert!(parse_args(args(&["--filter", "colour=Gold"])).is_err());
+    }
+
+    // test that --sample and --seed are parsed, with the first rows (no sample) and seed 0 by default
+    #[test]
+    fn test_parse_sample() {
+        let defaults = parse_args(args(&[])).unwrap();
+        assert_eq!((defaults.sample, defaults.seed), (None, 0));
+        let options = parse_args(args(&["--sample", "500", "--seed", "42"])).unwrap();
+        assert_eq!((options.sample, options.seed), (Some(500), 42));
+        assert!(parse_args(args(&["--seed", "-1"])).is_err());
+    }
+
+    // test that --delimiter takes a single character or tab
+    #[test]
+    fn test_parse_delimiter() {
+        assert_eq!(parse_args(args(&[])).unwrap().delimiter, None);
+        assert_eq!(parse_args(args(&["--delimiter", ";"])).unwrap().delimiter, Some(b';'));
+        assert_eq!(parse_args(args(&["--delimiter", "tab"])).unwrap().delimiter, Some(b'\t'));
+        assert_eq!(parse_args(args(&["--delimiter", "\\t"])).unwrap().delimiter, Some(b'\t'));
+        assert!(parse_args(args(&["--delimiter", ";;"])).is_err());
+        assert!(parse_args(args(&["--delimiter"])).is_err());
+    }
+
+    // test that --threshold-factor only accepts positive numbers
+    #[test]
+    fn test_parse_threshold_factor() {
+        assert_eq!(parse_args(args(&[])).unwrap().threshold_factor, None);
+        assert_eq!(parse_args(args(&["--threshold-factor", "1.5"])).unwrap().threshold_factor, Some(1.5));
+        assert!(parse_args(args(&["--threshold-factor", "0"])).is_err());
+        assert!(parse_args(args(&["--threshold-factor", "-1"])).is_err());
+        assert!(parse_args(args(&["--threshold-factor", "NaN"])).is_err());
+        assert!(parse_args(args(&["--threshold-factor"])).is_err());
+    }
+
+    // test that --threshold-mode accepts mean, median and percentiles between 0 and 100
+    #[test]
+    fn test_parse_threshold_mode() {
+        assert_eq!(parse_args(args(&[])).unwrap().threshold_mode, None);
+        assert_eq!(parse_args(args(&["--threshold-mode", "median"])).unwrap().threshold_mode, Some(ThresholdMode::Median));
+        assert_eq!(parse_args(args(&["--threshold-mode", "p75"])).unwrap().threshold_mode, Some(ThresholdMode::Percentile(75.0)));
+        assert!(parse_args(args(&["--threshold-mode", "p120"])).is_err());
+        assert!(parse_args(args(&["--threshold-mode", "mode"])).is_err());
+    }
+
+    // test that --unknown accepts the three policies
+    #[test]
+    fn test_parse_unknown_policy() {
+        assert_eq!(parse_args(args(&[])).unwrap().unknown_policy, None);
+        assert_eq!(parse_args(args(&["--unknown", "never"])).unwrap().unknown_policy, Some(UnknownPolicy::NeverMatch));
+        assert_eq!(parse_args(args(&["--unknown", "always"])).unwrap().unknown_policy, Some(UnknownPolicy::AlwaysMatch));
+        assert!(parse_args(args(&["--unknown", "sometimes"])).is_err());
+    }
+
+    // test that --collapse-below accepts a count or a percentage
+    #[test]
+    fn test_parse_collapse_below() {
+        assert_eq!(parse_args(args(&[])).unwrap().collapse_below, None);
+        assert_eq!(parse_args(args(&["--collapse-below", "5"])).unwrap().collapse_below, Some(RareThreshold::Count(5)));
+        assert_eq!(parse_args(args(&["--collapse-below", "2.5%"])).unwrap().collapse_below, Some(RareThreshold::Percentage(2.5)));
+        assert!(parse_args(args(&["--collapse-below", "few"])).is_err());
+    }
+
+    // test that --tenure-bands takes ascending comma-separated edges
+    #[test]
+    fn test_parse_tenure_bands() {
+        assert_eq!(parse_args(args(&[])).unwrap().tenure_bands, None);
+        assert_eq!(parse_args(args(&["--tenure-bands", "6, 12,24"])).unwrap().tenure_bands, Some(vec![6.0, 12.0, 24.0]));
+        assert!(parse_args(args(&["--tenure-bands", "24,12"])).is_err());
+        assert!(parse_args(args(&["--tenure-bands", "12,two"])).is_err());
+    }
+
+    // test that --graph-cache takes a path and is off by default
+    #[test]
+    fn test_parse_graph_cache() {
+        assert_eq!(parse_args(args(&[])).unwrap().graph_cache, None);
+        assert_eq!(parse_args(args(&["--graph-cache", "graph.json"])).unwrap().graph_cache, Some(PathBuf::from("graph.json")));
+        assert!(parse_args(args(&["--graph-cache"])).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 use petgraph::graph::{Graph, NodeIndex};
 use petgraph::Undirected;
-use crate::graph_utils::determine_neighbor;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::input::ColumnMap;
+use crate::graph_utils::{customer_for_node, node_customer_indices, percentile, NodeOrder, ATTRIBUTE_NAMES};
 
 // create a struct for catergorical variables' one-hot encoding 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct OneHotEncoding {
     pub education_level: String,
     pub marital_status: String,
@@ -11,110 +14,594 @@ pub struct OneHotEncoding {
     pub card_type: String,
 }
 
+// churn status of a customer, parsed from the Attrition_Flag column (see ChurnStatus::from_label)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChurnStatus {
+    Existing, // still using the card (not churn), "Existing Customer" in BankChurners.csv
+    Attrited, // stopped using the card (churn), "Attrited Customer" in BankChurners.csv
+    Unknown, // any other label; such customers are left out of both groups of the analysis
+}
+
+impl ChurnStatus {
+    // Function to parse an Attrition_Flag label; case and surrounding spaces are ignored,
+    // and "Existing"/"Attrited"/"Churned" are accepted as well as the BankChurners labels
+    pub fn from_label(label: &str) -> ChurnStatus {
+        match label.trim().to_lowercase().as_str() {
+            "existing customer" | "existing" => ChurnStatus::Existing,
+            "attrited customer" | "attrited" | "churned" => ChurnStatus::Attrited,
+            _ => ChurnStatus::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for ChurnStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChurnStatus::Existing => write!(f, "Existing Customer"),
+            ChurnStatus::Attrited => write!(f, "Attrited Customer"),
+            ChurnStatus::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
 // define a customer struct with 11 attributes (attributes = categories of characteristics we want to analyze) 
 // ex: age is a category, age groups customers belong to are characteristics
 #[allow(dead_code)]
 #[derive(PartialEq)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Customer {
-    pub churn_status: String, // whether the customer is still using the card (not churn) or not (churn)
-    pub age: i32, //age of customer
+    pub churn_status: ChurnStatus, // whether the customer is still using the card (not churn) or not (churn)
+    // the numeric fields are None when the value is missing from the data (an empty field), see SimilarityConfig::shared_value
+    pub age: Option<i32>, //age of customer
     pub one_hot_encoding: OneHotEncoding, // see struct OneHotEncoding
-    pub mon_w_bank: i32, // number of months the customer has been using services/purchasing products from the bank
-    pub num_product_purchased: i32, // number of products the customer purchased from the bank
-    pub mon_inactive: i32, // number of months the customer's card is inactive
-    pub num_contact: i32, // number of times the banks contacted the customer
-    pub transactions_amount: i32, // dollar amount of card transactions 
-    pub num_transctions: i32, // number of card transactions in the pat 12 months
-    pub avg_card_utilize: f64, // Average Card Utilization Ratio (divide your balance by your credit limit)
+    pub mon_w_bank: Option<i32>, // number of months the customer has been using services/purchasing products from the bank
+    pub num_product_purchased: Option<i32>, // number of products the customer purchased from the bank
+    pub mon_inactive: Option<i32>, // number of months the customer's card is inactive
+    pub num_contact: Option<i32>, // number of times the banks contacted the customer
+    pub transactions_amount: Option<i32>, // dollar amount of card transactions (whole dollars, cents are dropped when reading)
+    pub num_transctions: Option<i32>, // number of card transactions in the pat 12 months
+    pub avg_card_utilize: Option<f64>, // Average Card Utilization Ratio (divide your balance by your credit limit)
+}
+
+// one-line profile of a customer for logs and messages, e.g.
+// "Attrited Customer, age 62, $120K +, Gold card, 48 months with the bank, 1 products, $900 in 60 transactions, utilization 0.05"
+// missing numbers are shown as "?"
+impl std::fmt::Display for Customer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let known = |value: Option<i32>| value.map_or("?".to_string(), |value| value.to_string());
+        write!(f, "{}, age {}, {}, {} card, {} months with the bank, {} products, ${} in {} transactions, utilization {}",
+            self.churn_status, known(self.age), self.one_hot_encoding.income_range, self.one_hot_encoding.card_type, known(self.mon_w_bank),
+            known(self.num_product_purchased), known(self.transactions_amount), known(self.num_transctions),
+            self.avg_card_utilize.map_or("?".to_string(), |ratio| format!("{:.2}", ratio)))
+    }
+}
+
+impl Customer {
+    // Function to list the columns whose value is missing for this customer (its None fields), in column order
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let fields = [
+            (self.age.is_none(), "Customer_Age"),
+            (self.mon_w_bank.is_none(), "Months_on_book"),
+            (self.num_product_purchased.is_none(), "Total_Relationship_Count"),
+            (self.mon_inactive.is_none(), "Months_Inactive_12_mon"),
+            (self.num_contact.is_none(), "Contacts_Count_12_mon"),
+            (self.transactions_amount.is_none(), "Total_Trans_Amt"),
+            (self.num_transctions.is_none(), "Total_Trans_Ct"),
+            (self.avg_card_utilize.is_none(), "Avg_Utilization_Ratio"),
+        ];
+        fields.into_iter().filter_map(|(missing, name)| missing.then_some(name)).collect()
+    }
+}
+
+// struct holding the aggregated shared characteristics of a set of high centrality nodes (see compute_shared_characteristic_summary)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CharacteristicSummary {
+    pub node_count: usize, // number of high centrality nodes the summary was computed over
+    pub total_count: usize, // sum of the counts of all characteristics across all categories
+    pub categories: Vec<CategorySummary>, // categories sorted by total count (descending), then by name
+    pub invalid_nodes: Vec<usize>, // node indices that were out of bounds of the customers slice and skipped
+}
+
+impl CharacteristicSummary {
+    // Function to find the single most prevalent shared characteristic over all the high centrality nodes (the headline insight)
+    // with its category; ties go to the category listed first. None if nothing was shared
+    pub fn headline(&self) -> Option<(&CategorySummary, &CharacteristicCount)> {
+        let mut headline: Option<(&CategorySummary, &CharacteristicCount)> = None;
+        for category in &self.categories {
+            if let Some(characteristic) = category.characteristics.first() { // characteristics are sorted by count
+                let is_more_prevalent = match headline {
+                    Some((_, best)) => characteristic.count > best.count,
+                    None => true,
+                };
+                if is_more_prevalent {
+                    headline = Some((category, characteristic));
+                }
+            }
+        }
+        headline
+    }
+
+    // Function to collapse the rare characteristics of each category into a single RARE_CHARACTERISTICS entry with their summed
+    // count and percentage, listed after the characteristics that stay explicit; a category with a single rare characteristic
+    // keeps it, since collapsing it wouldn't shorten anything. The category and summary totals are unchanged
+    pub fn collapse_rare(&self, threshold: RareThreshold) -> CharacteristicSummary {
+        let categories = self.categories.iter().map(|category| {
+            let (rare, mut characteristics): (Vec<CharacteristicCount>, Vec<CharacteristicCount>) =
+                category.characteristics.iter().cloned().partition(|characteristic| threshold.is_rare(characteristic));
+            if rare.len() < 2 {
+                return category.clone();
+            }
+            characteristics.push(CharacteristicCount {
+                name: RARE_CHARACTERISTICS.to_string(),
+                count: rare.iter().map(|characteristic| characteristic.count).sum(),
+                percentage: rare.iter().map(|characteristic| characteristic.percentage).sum(),
+            });
+            CategorySummary { characteristics, ..category.clone() }
+        }).collect();
+        CharacteristicSummary { categories, ..self.clone() }
+    }
+}
+
+// entry that the rare characteristics of a category are collapsed into (see CharacteristicSummary::collapse_rare)
+pub const RARE_CHARACTERISTICS: &str = "Other (rare)";
+
+// what makes a characteristic rare enough to be collapsed (see CharacteristicSummary::collapse_rare)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RareThreshold {
+    Count(usize), // shared fewer than this many times
+    Percentage(f64), // less than this share (0-100) of its category
+}
+
+impl RareThreshold {
+    pub fn is_rare(&self, characteristic: &CharacteristicCount) -> bool {
+        match *self {
+            RareThreshold::Count(min_count) => characteristic.count < min_count,
+            RareThreshold::Percentage(min_percentage) => characteristic.percentage < min_percentage,
+        }
+    }
+}
+
+// struct for one category (e.g. "Card Type") in the summary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategorySummary {
+    pub name: String,
+    pub total_count: usize, // total count of the characteristics in this category
+    pub percentage: f64, // share of this category in the summary's total count (0-100, not rounded)
+    pub characteristics: Vec<CharacteristicCount>, // characteristics sorted by count (descending), then by name
+}
+
+// struct for one characteristic (e.g. "Blue") within a category
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CharacteristicCount {
+    pub name: String,
+    pub count: usize, // number of times the characteristic was shared between a high centrality node and its neighbors
+    pub percentage: f64, // share of this characteristic within its category (0-100, not rounded)
+}
+
+// error describing why a CSV record couldn't be turned into a Customer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    MissingField(&'static str), // the column is empty
+    ShortRow { fields: usize, expected: usize, column: &'static str }, // the row ends before the last column read; column is the first one cut off
+    UnparseableField { field: &'static str, value: String }, // the column has a value that isn't a valid number
+    UnknownCategory { field: &'static str, value: String }, // the column has an unrecognized category (only with strict categories)
+    OutOfRange { field: &'static str, value: String }, // the column has a number outside its valid range (only with strict ranges)
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RecordError::MissingField(field) => write!(f, "missing field {}", field),
+            RecordError::ShortRow { fields, expected, column } =>
+                write!(f, "row has {} fields, expected at least {} (missing column {})", fields, expected, column),
+            RecordError::UnparseableField { field, value } => write!(f, "unparseable field {}: '{}'", field, value),
+            RecordError::UnknownCategory { field, value } => write!(f, "unknown category in {}: '{}'", field, value),
+            RecordError::OutOfRange { field, value } => write!(f, "out of range value in {}: '{}'", field, value),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl Customer {
+    // Function to create a customer from a CSV record, using the column map to find each field
+    // returns an error for the first field that can't be parsed, or for an empty churn status or category;
+    // an empty numeric field is kept as None (missing data) rather than read as 0
+    // dollar fields (currently only Total_Trans_Amt) may be formatted like "$15,000.00", see parse_dollars below
+    // unrecognized categories become "Unknown" (see map_category), and a utilization ratio outside [0, 1] is clamped into it
+    pub fn from_record(record: &csv::StringRecord, columns: &ColumnMap) -> Result<Customer, RecordError> {
+        Customer::from_record_with(record, columns, false)
+    }
+
+    // Function to create a customer like from_record; with strict_categories an unrecognized category is an error
+    // (see map_category_strict) instead of becoming "Unknown"
+    pub fn from_record_with(record: &csv::StringRecord, columns: &ColumnMap, strict_categories: bool) -> Result<Customer, RecordError> {
+        Ok(Customer::from_record_checked(record, columns, strict_categories, false)?.0)
+    }
+
+    // Function to create a customer like from_record_with, also returning the fields whose value was clamped into their valid range
+    // (only Avg_Utilization_Ratio, which must be within [0, 1]); with strict_ranges such a value is an error instead
+    pub fn from_record_checked(record: &csv::StringRecord, columns: &ColumnMap, strict_categories: bool, strict_ranges: bool) -> Result<(Customer, Vec<&'static str>), RecordError> {
+        // a row with fewer fields than the last column read (e.g. cut off, or misaligned by a stray separator) is rejected as a whole
+        let expected = columns.columns().iter().map(|&(index, _)| index + 1).max().unwrap_or(0);
+        if record.len() < expected {
+            let column = columns.columns().into_iter()
+                .filter(|&(index, _)| index >= record.len())
+                .min_by_key(|&(index, _)| index)
+                .map_or("", |(_, name)| name);
+            return Err(RecordError::ShortRow { fields: record.len(), expected, column });
+        }
+        // Closure to get a non-empty field of the record
+        let field = |index: usize, name: &'static str| {
+            record.get(index).map(str::trim).filter(|value| !value.is_empty()).ok_or(RecordError::MissingField(name))
+        };
+        // Closure to get a numeric field of the record, None if it's empty
+        let optional_field = |index: usize| record.get(index).map(str::trim).filter(|value| !value.is_empty());
+        // Helper to parse a numeric field of the record
+        fn parse<T: std::str::FromStr>(value: &str, name: &'static str) -> Result<T, RecordError> {
+            value.parse().map_err(|_| RecordError::UnparseableField { field: name, value: value.to_string() })
+        }
+        // Helper to parse a whole-dollar field: a leading "$" and the thousands separators are stripped,
+        // and the cents are truncated rather than rounded, so "$1,234.56" becomes 1234
+        fn parse_dollars(value: &str, name: &'static str) -> Result<i32, RecordError> {
+            let digits = value.strip_prefix('$').unwrap_or(value).replace(',', "");
+            let (dollars, cents) = digits.split_once('.').unwrap_or((&digits, ""));
+            if !cents.chars().all(|c| c.is_ascii_digit()) {
+                return Err(RecordError::UnparseableField { field: name, value: value.to_string() });
+            }
+            parse(dollars, name).map_err(|_| RecordError::UnparseableField { field: name, value: value.to_string() })
+        }
+        // Closure to map a categorical field, leniently or strictly
+        let category = |value: &str, name: &'static str| match map_category_strict(value) {
+            Some(category) => Ok(category),
+            None if strict_categories => Err(RecordError::UnknownCategory { field: name, value: value.to_string() }),
+            None => Ok(map_category(value)),
+        };
+        let mut customer = Customer {
+            // extract values from record
+            churn_status: ChurnStatus::from_label(field(columns.churn_status, "Attrition_Flag")?),
+            age: optional_field(columns.age).map(|value| parse(value, "Customer_Age")).transpose()?,
+            one_hot_encoding: OneHotEncoding {
+                education_level: category(field(columns.education_level, "Education_Level")?, "Education_Level")?,
+                marital_status: category(field(columns.marital_status, "Marital_Status")?, "Marital_Status")?,
+                income_range: category(field(columns.income_range, "Income_Category")?, "Income_Category")?,
+                card_type: category(field(columns.card_type, "Card_Category")?, "Card_Category")?,
+            },
+            mon_w_bank: optional_field(columns.mon_w_bank).map(|value| parse(value, "Months_on_book")).transpose()?,
+            num_product_purchased: optional_field(columns.num_product_purchased).map(|value| parse(value, "Total_Relationship_Count")).transpose()?,
+            mon_inactive: optional_field(columns.mon_inactive).map(|value| parse(value, "Months_Inactive_12_mon")).transpose()?,
+            num_contact: optional_field(columns.num_contact).map(|value| parse(value, "Contacts_Count_12_mon")).transpose()?,
+            transactions_amount: optional_field(columns.transactions_amount).map(|value| parse_dollars(value, "Total_Trans_Amt")).transpose()?,
+            num_transctions: optional_field(columns.num_transctions).map(|value| parse(value, "Total_Trans_Ct")).transpose()?,
+            avg_card_utilize: optional_field(columns.avg_card_utilize).map(|value| parse(value, "Avg_Utilization_Ratio")).transpose()?,
+        };
+        // a utilization ratio is a balance divided by a credit limit, so anything outside [0, 1] (e.g. from a mis-mapped column) is bad data
+        let mut clamped_fields = Vec::new();
+        if let Some(ratio) = customer.avg_card_utilize.filter(|ratio| !(0.0..=1.0).contains(ratio) && !ratio.is_nan()) { // NaN matches no bucket anyway
+            if strict_ranges {
+                return Err(RecordError::OutOfRange { field: "Avg_Utilization_Ratio", value: ratio.to_string() });
+            }
+            customer.avg_card_utilize = Some(ratio.clamp(0.0, 1.0));
+            clamped_fields.push("Avg_Utilization_Ratio");
+        }
+        Ok((customer, clamped_fields))
+    }
 }
 
-// Function to print the top 4 shared characteristics between high centrality nodes and their neighbors
-pub fn print_top_shared_characteristics( 
+// Function to print the top N shared characteristics of each high centrality node, the most central first,
+// followed by the summary of the shared characteristics between high centrality nodes and their neighbors
+pub fn print_top_shared_characteristics<E>( 
     high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing high centrality nodes
+    centrality: &HashMap<NodeIndex, f64>, // centrality of the nodes, to report them in descending order
     customers: &[Customer],//Slice of Customer representing all customers
-    graph: &Graph<&Customer, (), Undirected>, // Reference to the undirected graph of customers (constructed in graph_utils and passed in in main)
-) -> Result<(), Box<dyn std::error::Error>> {
-    if high_centrality_nodes.is_empty() { // print statement in case there is no high centrality nodes
-        println!("No high centrality nodes.");
-        return Ok(());
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers (constructed in graph_utils and passed in in main)
+    top_n: usize, // number of shared characteristics kept per high centrality node
+) -> std::io::Result<()> {
+    let nodes = NodeOrder::Centrality.sort(high_centrality_nodes, centrality);
+    write_node_characteristics(&nodes, centrality, customers, graph, top_n, &SimilarityConfig::default(), &mut std::io::stdout())?;
+    let summary = compute_shared_characteristic_summary(high_centrality_nodes, customers, graph, top_n, &SimilarityConfig::default());
+    print_summary(&summary)
+}
+
+// Function to write one line per node with its centrality and its top N shared characteristics, in the order of the nodes given
+pub fn write_node_characteristics<E>(
+    nodes: &[NodeIndex], // nodes to write, already in the order to report them (see NodeOrder)
+    centrality: &HashMap<NodeIndex, f64>, // centrality of the nodes (0 for a node without one)
+    customers: &[Customer],//Slice of Customer representing all customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    top_n: usize, // number of shared characteristics kept per node
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+    writer: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    let node_customers = node_customer_indices(graph, customers);
+    for &node in nodes {
+        let characteristics: Vec<String> = top_shared_characteristics_mapped(graph, node, customers, &node_customers, top_n, similarity).into_iter()
+            .map(|(characteristic, count)| format!("{} ({})", characteristic, count))
+            .collect();
+        writeln!(writer, "  Node {} (centrality {:.4}): {}", node.index(), centrality.get(&node).copied().unwrap_or(0.0), characteristics.join(", "))?;
     }
-    // Create a HashMap to store each category's total counts and separated counts by characteristics in each category
+    Ok(())
+}
+
+// Function to aggregate the top N shared characteristics between high centrality nodes and their neighbors
+// by category, with the counts and percentages of each category and of each characteristic within its category
+pub fn compute_shared_characteristic_summary<E>(
+    high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing high centrality nodes
+    customers: &[Customer],//Slice of Customer representing all customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    top_n: usize, // number of shared characteristics kept per high centrality node
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+) -> CharacteristicSummary {
+    // Create a HashMap to store the total counts of each characteristic, sorted into categories at the end (see categorize_characteristic_counts)
     let mut total_characteristic_counts = std::collections::HashMap::<String, usize>::new();
-    let mut separated_counts: std::collections::HashMap<String, std::collections::HashMap<String, usize>> =
-        std::collections::HashMap::new();
+    let mut invalid_nodes = Vec::new();
+    let node_customers = node_customer_indices(graph, customers); // which customer each node is, without assuming node i is customers[i]
     
     // iterate over high centrality ndoes 
     for &node_index in high_centrality_nodes { 
-        if node_index.index() < customers.len() { // Check if the node index is within the bounds of the customers array
+        if node_customers.contains_key(&node_index) { // Check if the node is one of the customers
             let shared_characteristics =
-                find_top_shared_characteristics(graph, node_index, customers); // Find the top 4 shared characteristics between the current node and its neighbors using helper function
+                top_shared_characteristics_mapped(graph, node_index, customers, &node_customers, top_n, similarity); // Find the top N shared characteristics between the current node and its neighbors using helper function
 
             if shared_characteristics.is_empty() {// Continue to the next iteration if there are no shared characteristics
                 continue;
             }
 
             // Update the total counts for each characteristic across nodes 
-            for (characteristic, count) in shared_characteristics.iter().cloned() {
-                *total_characteristic_counts.entry(characteristic.clone()).or_insert(0) += count;
-
-                // sort characteristics into the categories they belong to 
-                // do this by splitting the characteristic names by ":", the string before is category lable, after is characteristic
-                let parts: Vec<&str> = characteristic.splitn(2, ":").collect(); 
-                if parts.len() == 2 {
-                    let key = parts[0].trim().to_string();
-                    let entry_count = separated_counts
-                        .entry(key.clone())
-                        .or_insert_with(|| std::collections::HashMap::new());
-
-                    *entry_count.entry(parts[1].trim().to_string()).or_insert(0) += count;
-                }
+            for (characteristic, count) in shared_characteristics {
+                *total_characteristic_counts.entry(characteristic).or_insert(0) += count;
+            }
+        } else { // keep track of invalid node indices
+            invalid_nodes.push(node_index.index());
+        }
+    }
+
+    let (total_sum, categories) = categorize_characteristic_counts(&total_characteristic_counts);
+    CharacteristicSummary {
+        node_count: high_centrality_nodes.len(),
+        total_count: total_sum,
+        categories,
+        invalid_nodes,
+    }
+}
+
+// Function to aggregate the characteristics the high centrality nodes share with each other (the "influencer" profile of a group),
+// counting the shared characteristics of every pair of high centrality nodes, whether or not the pair is connected in the graph
+pub fn compute_mutual_characteristic_summary<E>(
+    high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing high centrality nodes
+    customers: &[Customer],//Slice of Customer representing all customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+) -> CharacteristicSummary {
+    let mut total_characteristic_counts = std::collections::HashMap::<String, usize>::new();
+    let node_customers = node_customer_indices(graph, customers);
+    // keep the nodes that are customers, and track the invalid node indices like compute_shared_characteristic_summary
+    let mut central_customers = Vec::new();
+    let mut invalid_nodes = Vec::new();
+    for &node_index in high_centrality_nodes {
+        match customer_for_node(&node_customers, customers, node_index) {
+            Some(customer) => central_customers.push(customer),
+            None => invalid_nodes.push(node_index.index()),
+        }
+    }
+
+    // each unordered pair of high centrality customers once
+    for (i, customer_a) in central_customers.iter().enumerate() {
+        for customer_b in &central_customers[i + 1..] {
+            for characteristic in shared_traits_with(customer_a, customer_b, similarity) {
+                *total_characteristic_counts.entry(characteristic).or_insert(0) += 1;
             }
-        } else { // print statement for invalid node index
-            println!("Invalid node index: {}", node_index.index());
         }
     }
 
+    let (total_sum, categories) = categorize_characteristic_counts(&total_characteristic_counts);
+    CharacteristicSummary {
+        node_count: high_centrality_nodes.len(),
+        total_count: total_sum,
+        categories,
+        invalid_nodes,
+    }
+}
+
+// struct holding the bootstrapped confidence interval of one characteristic's prevalence
+// (its share of all the characteristics shared by the high centrality nodes, 0-100 like the summary percentages)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrevalenceInterval {
+    pub characteristic: String, // full name, e.g. "Card Type: Blue"
+    pub estimate: f64, // prevalence over the high centrality nodes themselves
+    pub lower: f64, // 2.5th percentile of the resampled prevalences
+    pub upper: f64, // 97.5th percentile of the resampled prevalences
+}
+
+// Function to estimate 95% confidence intervals of each characteristic's prevalence by bootstrapping:
+// the high centrality nodes are resampled with replacement `resamples` times, and the summary of each resample
+// gives one prevalence per characteristic (0 if no resampled node shares it); the intervals are the percentiles of those.
+// The same seed gives the same intervals; returns the characteristics sorted by estimate (descending), then by name
+pub fn bootstrap_prevalence<E>(
+    high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing high centrality nodes
+    customers: &[Customer],//Slice of Customer representing all customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    top_n: usize, // number of shared characteristics kept per high centrality node
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+    resamples: usize, // number of bootstrap resamples
+    seed: u64, // seed of the resampling
+) -> Vec<PrevalenceInterval> {
+    use rand::{Rng, SeedableRng};
+    // the top N characteristics of each node only need to be found once, the resamples just add them up differently
+    let node_customers = node_customer_indices(graph, customers);
+    let node_characteristics: Vec<Vec<(String, usize)>> = high_centrality_nodes.iter()
+        .filter(|node_index| node_customers.contains_key(node_index)) // invalid nodes are skipped like in the summary
+        .map(|&node_index| top_shared_characteristics_mapped(graph, node_index, customers, &node_customers, top_n, similarity))
+        .collect();
+    let prevalences = |counts: &HashMap<&str, usize>| -> HashMap<String, f64> {
+        let total: usize = counts.values().sum();
+        counts.iter().map(|(&characteristic, &count)| (characteristic.to_string(), count as f64 / total as f64 * 100.0)).collect()
+    };
+    let count_nodes = |nodes: &[usize]| { // positions in node_characteristics, repeated for nodes drawn more than once
+        let mut counts = HashMap::<&str, usize>::new();
+        for &position in nodes {
+            for (characteristic, count) in &node_characteristics[position] {
+                *counts.entry(characteristic.as_str()).or_insert(0) += count;
+            }
+        }
+        counts
+    };
+
+    let all_nodes: Vec<usize> = (0..node_characteristics.len()).collect();
+    let estimates = prevalences(&count_nodes(&all_nodes));
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut resampled: HashMap<&str, Vec<f64>> = estimates.keys().map(|characteristic| (characteristic.as_str(), Vec::with_capacity(resamples))).collect();
+    for _ in 0..resamples {
+        let sample: Vec<usize> = all_nodes.iter().map(|_| rng.gen_range(0..all_nodes.len())).collect();
+        let sample_prevalences = prevalences(&count_nodes(&sample));
+        for (characteristic, values) in resampled.iter_mut() {
+            values.push(sample_prevalences.get(*characteristic).copied().unwrap_or(0.0));
+        }
+    }
+
+    let mut intervals: Vec<PrevalenceInterval> = resampled.into_iter().map(|(characteristic, values)| PrevalenceInterval {
+        characteristic: characteristic.to_string(),
+        estimate: estimates[characteristic],
+        lower: percentile(&values, 2.5),
+        upper: percentile(&values, 97.5),
+    }).collect();
+    intervals.sort_by(|a, b| b.estimate.total_cmp(&a.estimate).then_with(|| a.characteristic.cmp(&b.characteristic)));
+    intervals
+}
+
+// category of the characteristics whose name has no ":" separator (and so no category label)
+pub const UNCATEGORIZED: &str = "Other";
+
+// Function to sort the total counts of each characteristic into their categories, with the percentages of each category
+// and of each characteristic within its category; returns the sum of all counts (the denominator of the category percentages) and the categories
+// every counted characteristic ends up in exactly one category, so the category totals always add up to the returned sum
+pub fn categorize_characteristic_counts(total_characteristic_counts: &std::collections::HashMap<String, usize>) -> (usize, Vec<CategorySummary>) {
+    let mut separated_counts: std::collections::HashMap<String, std::collections::HashMap<String, usize>> =
+        std::collections::HashMap::new();
+    for (characteristic, &count) in total_characteristic_counts {
+        // sort characteristics into the categories they belong to 
+        // do this by splitting the characteristic names by ":", the string before is category lable, after is characteristic
+        let (key, entry) = match characteristic.split_once(':') {
+            Some((key, entry)) => (key.trim(), entry.trim()),
+            None => (UNCATEGORIZED, characteristic.trim()), // keep characteristics without a label instead of dropping them
+        };
+        *separated_counts.entry(key.to_string()).or_default().entry(entry.to_string()).or_insert(0) += count;
+    }
+
     // Calculate the sum of total counts of shared characteristic across all categories (for percentage calculation later)
-    let total_sum: usize = separated_counts.iter().flat_map(|(_, entry_counts)| entry_counts.values()).sum();
+    let total_sum: usize = total_characteristic_counts.values().sum();
 
-    // Print the total counts for each categories and the characteristics within each category
-    println!("Prevalent characteristic categories and their compositions:");
-    for (key, entry_counts) in separated_counts.iter() { // iterate through each category and their characteristics 
-        let total_count: usize = entry_counts.values().cloned().sum(); // calculate total characteritics count of each category
-        let key_percentage: f64 = (total_count as f64 / total_sum as f64) * 100.00; // calculate the percentage of each category 
-        let rounded_key_percentage = (key_percentage * 10.0).round() / 10.0; // round the percentage 
+    // Calculate the total counts and percentages for each category and the characteristics within each category
+    let mut categories: Vec<CategorySummary> = separated_counts.into_iter().map(|(key, entry_counts)| {
+        let total_count: usize = entry_counts.values().sum(); // calculate total characteritics count of each category
+        let mut characteristics: Vec<CharacteristicCount> = entry_counts.into_iter().map(|(entry, count)| CharacteristicCount {
+            name: entry,
+            count,
+            percentage: (count as f64 / total_count as f64) * 100.00, // percentage of each characteristic within their category
+        }).collect();
+        characteristics.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        CategorySummary {
+            name: key,
+            total_count,
+            percentage: (total_count as f64 / total_sum as f64) * 100.00, // percentage of each category
+            characteristics,
+        }
+    }).collect();
+    categories.sort_by(|a, b| b.total_count.cmp(&a.total_count).then_with(|| a.name.cmp(&b.name)));
+    (total_sum, categories)
+}
 
-        println!("{}, (Total Count: {} - {}%)", key, total_count, rounded_key_percentage); //print the name, total count and percentage of each category 
+// Number of decimal places of the printed percentages unless configured otherwise
+pub const DEFAULT_PRECISION: usize = 1;
 
-        for (entry, count) in entry_counts.iter() { // iterate through each characteristics and their counts 
-            let percentage: f64 = (count.clone() as f64 / total_count as f64) * 100.00; // calculate the percentage of each characteristic within their category
-            let rounded_percentage = (percentage * 10.0).round() / 10.0; //  round the percentage 
-            println!("  {}: {} ({}%)", entry, count, rounded_percentage); // print the name, total count and percentage of each characteristic
+// Function to format a percentage (already multiplied by 100) with the given number of decimal places, without the "%"
+pub fn format_percentage(percentage: f64, precision: usize) -> String {
+    format!("{:.*}", precision, percentage)
+}
+
+// Function to print a characteristic summary (percentages are rounded to one decimal)
+pub fn print_summary(summary: &CharacteristicSummary) -> std::io::Result<()> {
+    write_summary(summary, &mut std::io::stdout().lock())
+}
+
+// Function to write the summary printed by print_summary to any writer (stdout, the --output file, buffer...)
+pub fn write_summary(summary: &CharacteristicSummary, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    write_summary_with(summary, DEFAULT_PRECISION, writer)
+}
+
+// Function to write the summary like write_summary, with the percentages rounded to precision decimal places
+pub fn write_summary_with(summary: &CharacteristicSummary, precision: usize, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    if summary.node_count == 0 { // print statement in case there is no high centrality nodes
+        writeln!(writer, "No high centrality nodes.")?;
+        return Ok(());
+    }
+    for node_index in &summary.invalid_nodes { // print statement for invalid node index
+        writeln!(writer, "Invalid node index: {}", node_index)?;
+    }
+
+    // Print the total counts for each categories and the characteristics within each category
+    writeln!(writer, "Prevalent characteristic categories and their compositions:")?;
+    for category in &summary.categories { // iterate through each category and their characteristics 
+        let rounded_key_percentage = format_percentage(category.percentage, precision); // round the percentage 
+        writeln!(writer, "{}, (Total Count: {} - {}%)", category.name, category.total_count, rounded_key_percentage)?; //print the name, total count and percentage of each category 
+
+        for characteristic in &category.characteristics { // iterate through each characteristics and their counts 
+            let rounded_percentage = format_percentage(characteristic.percentage, precision); //  round the percentage 
+            writeln!(writer, "  {}: {} ({}%)", characteristic.name, characteristic.count, rounded_percentage)?; // print the name, total count and percentage of each characteristic
         }
     }
-    println!("");
+    writeln!(writer)?;
+    Ok(())
+}
 
+// Function to write only the counts of a summary, without the per-category breakdown (the --quiet output)
+pub fn write_summary_counts(summary: &CharacteristicSummary, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    if summary.node_count == 0 {
+        writeln!(writer, "No high centrality nodes.")?;
+        return Ok(());
+    }
+    writeln!(writer, "{} high centrality nodes, {} shared characteristics in {} categories", summary.node_count, summary.total_count, summary.categories.len())?;
+    if !summary.invalid_nodes.is_empty() {
+        writeln!(writer, "{} invalid node indices skipped", summary.invalid_nodes.len())?;
+    }
+    writeln!(writer)?;
     Ok(())
 }
 
-//Function to find the top 4 shared characteristics between a given node and its neighbors
-// helper function used in print_top_shared_characteristics
-pub fn find_top_shared_characteristics(
-    graph: &Graph<&Customer, (), Undirected>, // Reference to the undirected graph of customers
+//Function to find the top N shared characteristics between a given node and its neighbors
+// helper function used in compute_shared_characteristic_summary
+pub fn find_top_shared_characteristics<E>(
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
     node_index: NodeIndex, // Node index for a specific customer
-    customers: &[Customer],// Slice of Customer representing all customers
+    customers: &[Customer],// Slice of Customer the graph was built from
+    top_n: usize, // number of characteristics to return
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
 ) -> Vec<(String, usize)> { // Vector of tuples containing top shared characteristics and their counts (counts=number of time they are shared between a centrality node and its neighbor)
+    top_shared_characteristics_mapped(graph, node_index, customers, &node_customer_indices(graph, customers), top_n, similarity)
+}
+
+// Function to find the top N shared characteristics like find_top_shared_characteristics, with the node to customer map already built
+fn top_shared_characteristics_mapped<E>(
+    graph: &Graph<&Customer, E, Undirected>,
+    node_index: NodeIndex,
+    customers: &[Customer],
+    node_customers: &std::collections::HashMap<NodeIndex, usize>, // see node_customer_indices
+    top_n: usize,
+    similarity: &SimilarityConfig,
+) -> Vec<(String, usize)> {
     let mut characteristic_counts = std::collections::HashMap::<String, usize>::new(); // Create a HashMap to store characteristic counts
+    let Some(customer) = customer_for_node(node_customers, customers, node_index) else {
+        return Vec::new(); // the node isn't one of the customers
+    };
 
     for neighbor_index in graph.neighbors(node_index) { // Iterate over neighbors of the given node
-        // Check if the neighbor index is within the bounds of the customers array
-        if neighbor_index.index() < customers.len() { 
-            let neighbor = &customers[neighbor_index.index()];
-            // Get the shared characteristics between the node and the current neighbor using helper function get_shared_characteristics
-            let shared_characteristics = get_shared_characteristics( 
-                &customers[node_index.index()],
+        // Check if the neighbor is one of the customers
+        if let Some(neighbor) = customer_for_node(node_customers, customers, neighbor_index) { 
+            // Get the shared characteristics between the node and the current neighbor using helper function shared_traits_with
+            let shared_characteristics = shared_traits_with( 
+                customer,
                 neighbor,
+                similarity,
             );
 
             for characteristic in shared_characteristics {  
@@ -125,88 +612,563 @@ pub fn find_top_shared_characteristics(
     }
     // Create a sorted vector of characteristic counts
     let mut sorted_characteristics: Vec<_> = characteristic_counts.into_iter().collect();
-    sorted_characteristics.sort_by(|(_, count1), (_, count2)| count2.cmp(count1));
-    // Return the top 4 shared characteristics
-    sorted_characteristics.into_iter().take(4).collect()
+    // with weighted ranking a characteristic scores count x inverse frequency (the count still breaks ties)
+    let score = |characteristic: &String, count: usize| match (similarity.weighted_ranking, &similarity.inverse_frequencies) {
+        (true, Some(inverse_frequencies)) => count as f64 * inverse_frequencies.get(characteristic).copied().unwrap_or(0.0),
+        _ => count as f64,
+    };
+    // ties are broken alphabetically so the result doesn't depend on the HashMap iteration order
+    sorted_characteristics.sort_by(|(characteristic1, count1), (characteristic2, count2)| score(characteristic2, *count2).total_cmp(&score(characteristic1, *count1))
+        .then_with(|| count2.cmp(count1))
+        .then_with(|| characteristic1.cmp(characteristic2)));
+    // Return the top N shared characteristics
+    sorted_characteristics.into_iter().take(top_n).collect()
 }   
 
-// Function to get shared characteristics between two nodes (nodes=customers)
-pub fn get_shared_characteristics(customer_a: &Customer, customer_b: &Customer) -> Vec<String> {
+// Function to list every neighbor of a node with the full list of traits they share (not only the top N)
+// neighbors are sorted by index and listed once; like find_top_shared_characteristics, neighbors outside customers are skipped
+pub fn neighbor_details<E>(
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    node_index: NodeIndex, // the customer whose neighbors are listed
+    customers: &[Customer], // Slice of Customer the graph was built from
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+) -> Vec<(NodeIndex, Vec<String>)> {
+    let node_customers = node_customer_indices(graph, customers);
+    let Some(customer) = customer_for_node(&node_customers, customers, node_index) else {
+        return Vec::new();
+    };
+    let mut neighbors: Vec<(NodeIndex, &Customer)> = graph.neighbors(node_index)
+        .filter_map(|neighbor| customer_for_node(&node_customers, customers, neighbor).map(|neighbor_customer| (neighbor, neighbor_customer)))
+        .collect();
+    neighbors.sort_by_key(|(neighbor, _)| *neighbor);
+    neighbors.dedup_by_key(|(neighbor, _)| *neighbor); // parallel edges would list a neighbor twice
+    neighbors.into_iter()
+        .map(|(neighbor, neighbor_customer)| (neighbor, shared_traits_with(customer, neighbor_customer, similarity)))
+        .collect()
+}
+
+// Function to get the attributes on which two customers differ (distinguishing traits): the inverse of shared_traits_with,
+// so every attribute of ATTRIBUTE_NAMES that isn't excluded is either shared or distinguishing
+pub fn distinguishing_traits_with(customer_a: &Customer, customer_b: &Customer, similarity: &SimilarityConfig) -> Vec<&'static str> {
+    let shared = shared_traits_with(customer_a, customer_b, similarity);
+    let shared_attributes: HashSet<&str> = shared.iter().map(|label| label.split(':').next().unwrap_or(label)).collect();
+    ATTRIBUTE_NAMES.iter().copied()
+        .filter(|attribute| !shared_attributes.contains(attribute) && !similarity.excluded_attributes.contains(*attribute))
+        .collect()
+}
+
+// struct holding how often an attribute differs between the high centrality nodes and their neighbors
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DistinguishingCharacteristic {
+    pub attribute: String, // e.g. "Card Type"
+    pub count: usize, // number of (high centrality node, neighbor) pairs that differ on the attribute
+    pub percentage: f64, // share of all the pairs (0-100, not rounded)
+}
+
+// Function to rank the attributes by how consistently the high centrality nodes differ from their neighbors on them,
+// the complement of compute_shared_characteristic_summary: every neighbor of every node counts once per attribute.
+// Sorted by count descending, ties alphabetically; attributes that never differ are left out
+pub fn compute_distinguishing_characteristics<E>(
+    high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing high centrality nodes
+    customers: &[Customer], // Slice of Customer representing all customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+) -> Vec<DistinguishingCharacteristic> {
+    let node_customers = node_customer_indices(graph, customers);
+    let mut counts = HashMap::<&str, usize>::new();
+    let mut pairs = 0;
+    for &node_index in high_centrality_nodes {
+        let Some(customer) = customer_for_node(&node_customers, customers, node_index) else {
+            continue; // not one of the customers
+        };
+        for neighbor_index in graph.neighbors(node_index) {
+            if let Some(neighbor) = customer_for_node(&node_customers, customers, neighbor_index) {
+                pairs += 1;
+                for attribute in distinguishing_traits_with(customer, neighbor, similarity) {
+                    *counts.entry(attribute).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    let mut ranked: Vec<DistinguishingCharacteristic> = counts.into_iter()
+        .map(|(attribute, count)| DistinguishingCharacteristic { attribute: attribute.to_string(), count, percentage: count as f64 * 100.0 / pairs as f64 })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.attribute.cmp(&b.attribute)));
+    ranked
+}
+
+// Function to find the k customers sharing the most characteristics with target, without building the graph
+// returns (index in all, number of shared characteristics) sorted by the count descending, ties by index;
+// if target is itself an element of all it is skipped
+pub fn most_similar(target: &Customer, all: &[Customer], k: usize) -> Vec<(usize, usize)> {
+    let mut ranked: Vec<(usize, usize)> = all.iter().enumerate()
+        .filter(|(_, customer)| !std::ptr::eq(*customer, target))
+        .map(|(index, customer)| (index, shared_traits(target, customer).len()))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(k);
+    ranked
+}
+
+// Function to get shared characteristics (traits) between two nodes (nodes=customers), using the default bucket edges
+// this is the only place customers are compared: the neighbor check and the edge weights in graph_utils are derived from its labels
+pub fn shared_traits(customer_a: &Customer, customer_b: &Customer) -> Vec<String> {
+    shared_traits_with(customer_a, customer_b, &SimilarityConfig::default())
+}
+
+// Function to get shared characteristics between two nodes, putting the numeric fields into the buckets of the given specs
+pub fn shared_traits_with<'a>(customer_a: &'a Customer, customer_b: &'a Customer, similarity: &SimilarityConfig) -> Vec<String> {
     let mut shared_characteristics: Vec<String> = Vec::new(); // Create a vector to store shared characteristics
     let is_similar = |value_a: &str, value_b: &str| value_a == value_b; // Closure to check if two values are similar
-    let in_same_group = |value_a: &str, value_b: &str, groups: &[&str]| { // Closure to check if two values are in the same group
-        groups.iter().any(|&group| value_a == group && value_b == group)
+    // the categorical fields go through the unknown policy first; a match with "Unknown" is labeled with the known value, if any
+    let categories_match = |value_a: &str, value_b: &str, compare: &dyn Fn(&str, &str) -> bool| {
+        similarity.unknown_match(value_a, value_b).unwrap_or_else(|| compare(value_a, value_b))
     };
-    if in_same_group(&customer_a.age.to_string(), &customer_b.age.to_string(), &vec!["20-30", "30-40", "40-50", ">50"]) { // create groups and compare whether two nodes are in the same group
-        shared_characteristics.push(format!("Age: {}", customer_a.age));
+    let category_label = |value_a: &'a str, value_b: &'a str| if value_a == UNKNOWN_CATEGORY { value_b } else { value_a };
+    // put both ages into age groups and compare the groups; a missing value goes through the unknown policy (see shared_value)
+    if let Some(age_group) = similarity.shared_bucket(&similarity.age, customer_a.age.map(f64::from), customer_b.age.map(f64::from)) {
+        shared_characteristics.push(format!("Age: {}", age_group)); // labeled with the age group (e.g. "Age: 30-40"), not the exact age
+    }
+    let (categories_a, categories_b) = (&customer_a.one_hot_encoding, &customer_b.one_hot_encoding);
+    if categories_match(&categories_a.education_level, &categories_b.education_level, &|a, b| similarity.similar_education(a, b)) { // Check and add shared characteristics for education level (exact or ordinal, see similar_education)
+        shared_characteristics.push(format!("Education Level: {}", category_label(&categories_a.education_level, &categories_b.education_level)));
     }
-    if is_similar(&customer_a.one_hot_encoding.education_level, &customer_b.one_hot_encoding.education_level) { // Check and add shared characteristics for education level
-        shared_characteristics.push(format!("Education Level: {}", &customer_a.one_hot_encoding.education_level));
+    if categories_match(&categories_a.marital_status, &categories_b.marital_status, &is_similar) {// Check and add shared characteristics for marital status
+        shared_characteristics.push(format!("Marital Status: {}", category_label(&categories_a.marital_status, &categories_b.marital_status)));
     }
-    if is_similar(&customer_a.one_hot_encoding.marital_status, &customer_b.one_hot_encoding.marital_status) {// Check and add shared characteristics for marital status
-        shared_characteristics.push(format!("Marital Status: {}", &customer_a.one_hot_encoding.marital_status));
+    if categories_match(&categories_a.income_range, &categories_b.income_range, &|a, b| similarity.similar_income(a, b)) {// Check and add shared characteristics for income range (exact or ordinal, see similar_income)
+        shared_characteristics.push(format!("Income Range: {}", category_label(&categories_a.income_range, &categories_b.income_range)));
     }
-    if is_similar(&customer_a.one_hot_encoding.income_range, &customer_b.one_hot_encoding.income_range) {// Check and add shared characteristics for income range
-        shared_characteristics.push(format!("Income Range: {}", &customer_a.one_hot_encoding.income_range));
+    if categories_match(&categories_a.card_type, &categories_b.card_type, &is_similar) { // Check and add shared characteristics for card type
+        shared_characteristics.push(format!("Card Type: {}", category_label(&categories_a.card_type, &categories_b.card_type)));
     }
-    if is_similar(&customer_a.one_hot_encoding.card_type, &customer_b.one_hot_encoding.card_type) { // Check and add shared characteristics for card type
-        shared_characteristics.push(format!("Card Type: {}", &customer_a.one_hot_encoding.card_type));
+    // Check and add shared characteristics for Mon W Bank; put both values into groups and compare the groups
+    if let Some(mon_w_bank_group) = similarity.shared_bucket(&similarity.mon_w_bank, customer_a.mon_w_bank.map(f64::from), customer_b.mon_w_bank.map(f64::from)) {
+        shared_characteristics.push(format!("Mon W Bank: {}", mon_w_bank_group));
     }
-    // Check and add shared characteristics for Mon W Bank
-    if in_same_group(&customer_a.mon_w_bank.to_string(), &customer_b.mon_w_bank.to_string(), &vec!["20-30", "30-40", "40-50", ">50"]) { // create groups and compare whether two nodes are in the same group
-        shared_characteristics.push(format!("Mon W Bank: {}", &customer_a.mon_w_bank));
+    // the counts below are compared exactly, or within the tolerance after normalization (see SimilarityConfig::similar_counts)
+    if let Some(label) = similarity.shared_count(None, customer_a.num_product_purchased, customer_b.num_product_purchased, |ranges| ranges.num_product_purchased) { // Check and add shared characteristics for the number of products
+        shared_characteristics.push(format!("Number of Products Purchased: {}", label));
     }
-    if is_similar(&customer_a.num_product_purchased.to_string(), &customer_b.num_product_purchased.to_string()) { // Check and add shared characteristics for the number of products
-        shared_characteristics.push(format!("Number of Products Purchased: {}", customer_a.num_product_purchased));
+    // the months inactive and the contacts can also be put into buckets (see SimilarityConfig::shared_count)
+    if let Some(label) = similarity.shared_count(similarity.mon_inactive_buckets.as_ref(), customer_a.mon_inactive, customer_b.mon_inactive, |ranges| ranges.mon_inactive) {// Check and add shared characteristics for the month inactive
+        shared_characteristics.push(format!("Month inactive: {}", label));
     }
-    if is_similar(&customer_a.mon_inactive.to_string(), &customer_b.mon_inactive.to_string()) {// Check and add shared characteristics for the month inactive
-        shared_characteristics.push(format!("Month inactive: {}", customer_a.mon_inactive));
+    if let Some(label) = similarity.shared_count(similarity.num_contact_buckets.as_ref(), customer_a.num_contact, customer_b.num_contact, |ranges| ranges.num_contact) {// Check and add shared characteristics for the number of contacts from the bank
+        shared_characteristics.push(format!("Number of Contacts from Bank (past 12 months): {}", label));
     }
-    if is_similar(&customer_a.num_contact.to_string(), &customer_b.num_contact.to_string()) {// Check and add shared characteristics for the number of contacts from the bank
-        shared_characteristics.push(format!("Number of Contacts from Bank (past 12 months): {}", customer_a.num_contact));
+    if let Some(amount_group) = similarity.shared_bucket(&similarity.transactions_amount, customer_a.transactions_amount.map(f64::from), customer_b.transactions_amount.map(f64::from)) {
+        shared_characteristics.push(format!("Total Dollar Amount of Transaction via Card: {}", amount_group));
     }
-    if in_same_group(&customer_a.transactions_amount.to_string(), &customer_b.transactions_amount.to_string(), &vec!["500<", "500-1000", "1000-1500","1500-2000",">2000"]) {
-        shared_characteristics.push(format!("Total Dollar Amount of Transaction via Card: {}", &customer_a.transactions_amount));
+    // Check and add shared characteristics for total number of transactions via card; put both values into groups and compare the groups
+    if let Some(transactions_group) = similarity.shared_bucket(&similarity.num_transctions, customer_a.num_transctions.map(f64::from), customer_b.num_transctions.map(f64::from)) {
+        shared_characteristics.push(format!("Total Number of Transactions via Card: {}", transactions_group));
     }
-    // Check and add shared characteristics for total number of transactions via card; create groups and compare whether two nodes are in the same group
-    if in_same_group(&customer_a.num_transctions.to_string(), &customer_b.num_transctions.to_string(), &vec!["<10","10-20","20-30","30-40",">40"]) {
-        shared_characteristics.push(format!("Total Number of Transactions via Card: {}", &customer_a.num_transctions));
+    // Check and add shared characteristics for average card utilization ratio; put both values into groups and compare the groups
+    if let Some(utilization_group) = similarity.shared_bucket(&similarity.avg_card_utilize, customer_a.avg_card_utilize, customer_b.avg_card_utilize) {
+        shared_characteristics.push(format!("Average Card Utilization Ratio: {}", utilization_group));
     }
-    // Check and add shared characteristics for average card utilization ratio; create groups and compare whether two nodes are in the same group
-    if in_same_group(&customer_a.avg_card_utilize.to_string(), &customer_b.avg_card_utilize.to_string(), &vec!["<0.100","0.100-0.200","0.200-0.300","0.300-0.400",">0.400"]) {
-        shared_characteristics.push(format!("Average Card Utilization Ratio: {}", &customer_a.avg_card_utilize));
+    // drop the attributes excluded from the comparison (the attribute is the label before the ":")
+    if !similarity.excluded_attributes.is_empty() {
+        shared_characteristics.retain(|label| !similarity.excluded_attributes.contains(label.split(':').next().unwrap_or(label)));
     }
 
     shared_characteristics // // Return the vector of shared characteristics
 }
 
-// Function to map categorical values
+// Edges of the age groups used when comparing customers: <30, 30-40, 40-50, 50-60, >60
+pub const AGE_BUCKET_EDGES: [f64; 4] = [30.0, 40.0, 50.0, 60.0];
+
+// Edges of the default tenure bands (months with the bank) used when comparing customers: <12, 12-24, 24-36, >36
+// (the 36 months and over band starts at 36, see numeric_bucket)
+pub const TENURE_BAND_EDGES: [f64; 3] = [12.0, 24.0, 36.0];
+
+// which of the two buckets meeting at an edge a value exactly on the edge falls into (see numeric_bucket_with)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundaryInclusion {
+    #[default]
+    LowerInclusive, // each bucket includes its lower edge: with edges [20, 30], 20 is in "20-30" and 30 in ">30" (the default)
+    UpperInclusive, // each bucket includes its upper edge: with edges [20, 30], 20 is in "<20" and 30 in "20-30"
+}
+
+// bucket edges of one numeric field, in ascending order (see numeric_bucket)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketSpec {
+    pub edges: Vec<f64>,
+    // values less than epsilon below an edge count as being on the edge (see numeric_bucket_with_epsilon); 0 for exact edges
+    pub epsilon: f64,
+    pub inclusion: BoundaryInclusion, // bucket a value on an edge falls into (the one above it by default)
+}
+
+impl BucketSpec {
+    pub fn new(edges: &[f64]) -> Self {
+        BucketSpec { edges: edges.to_vec(), epsilon: 0.0, inclusion: BoundaryInclusion::LowerInclusive }
+    }
+
+    // bucket spec for a float field, whose values may carry rounding errors (e.g. 0.29999999 for 0.3)
+    pub fn with_epsilon(edges: &[f64], epsilon: f64) -> Self {
+        BucketSpec { edges: edges.to_vec(), epsilon, inclusion: BoundaryInclusion::LowerInclusive }
+    }
+
+    // puts the values on an edge into the bucket below or above it, keeping the edges
+    pub fn with_inclusion(mut self, inclusion: BoundaryInclusion) -> Self {
+        self.inclusion = inclusion;
+        self
+    }
+
+    // Function to get the label of the bucket a value falls into
+    pub fn bucket(&self, value: f64) -> String {
+        numeric_bucket_with(value, &self.edges, self.epsilon, self.inclusion)
+    }
+}
+
+// Tolerance for the utilization ratio bucket edges: ratios are stored with 3 decimals, so anything closer to an edge
+// than this is a rounding error (0.2999999 is 0.3)
+pub const UTILIZATION_EPSILON: f64 = 1e-6;
+
+// smallest and largest value of a numeric field across the dataset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FieldRange {
+    // Function to min-max normalize a value into 0..1 (0 for every value if the field is constant)
+    pub fn normalize(&self, value: f64) -> f64 {
+        if self.max > self.min { (value - self.min) / (self.max - self.min) } else { 0.0 }
+    }
+}
+
+// Function to compute the inverse frequency ln(N / n) of every characteristic, where n of the N customers have it
+// (the traits a customer shares with itself); a characteristic every customer has weighs 0, a rare one weighs the most
+pub fn inverse_frequencies(customers: &[Customer], similarity: &SimilarityConfig) -> HashMap<String, f64> {
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for customer in customers {
+        for characteristic in shared_traits_with(customer, customer, similarity) {
+            *frequencies.entry(characteristic).or_insert(0) += 1;
+        }
+    }
+    frequencies.into_iter()
+        .map(|(characteristic, count)| (characteristic, (customers.len() as f64 / count as f64).ln()))
+        .collect()
+}
+
+// ranges of the count fields that are otherwise compared by exact value, computed in a pre-pass over the dataset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericRanges {
+    pub num_product_purchased: FieldRange,
+    pub mon_inactive: FieldRange,
+    pub num_contact: FieldRange,
+}
+
+impl NumericRanges {
+    // Function to compute the range of each count field over the customers (None if there are no customers)
+    // missing values are left out
+    pub fn from_customers(customers: &[Customer]) -> Option<NumericRanges> {
+        let range = |value: fn(&Customer) -> Option<i32>| {
+            let values = customers.iter().filter_map(|customer| value(customer).map(f64::from));
+            FieldRange {
+                min: values.clone().fold(f64::INFINITY, f64::min),
+                max: values.fold(f64::NEG_INFINITY, f64::max),
+            }
+        };
+        if customers.is_empty() {
+            return None;
+        }
+        Some(NumericRanges {
+            num_product_purchased: range(|customer| customer.num_product_purchased),
+            mon_inactive: range(|customer| customer.mon_inactive),
+            num_contact: range(|customer| customer.num_contact),
+        })
+    }
+}
+
+// struct holding how the numeric fields are compared: the bucket spec of every field compared by group
+// (two customers share such a characteristic when their values fall into the same bucket), and optionally
+// a tolerance for the count fields (products, inactive months, contacts) that are otherwise compared by exact value
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarityConfig {
+    pub age: BucketSpec,
+    pub mon_w_bank: BucketSpec,
+    pub transactions_amount: BucketSpec,
+    pub num_transctions: BucketSpec,
+    pub avg_card_utilize: BucketSpec,
+    // maximum difference between the min-max normalized counts of two similar customers; None compares exact values
+    pub similarity_tolerance: Option<f64>,
+    // ranges used for the normalization; run_analysis computes them from the customers when a tolerance is set
+    pub ranges: Option<NumericRanges>,
+    // attributes (names as in graph_utils::ATTRIBUTE_NAMES, e.g. "Card Type") ignored for both the edges and the reported traits
+    pub excluded_attributes: HashSet<String>,
+    // maximum number of brackets between two similar income ranges (see income_distance); None compares the labels exactly
+    pub income_max_distance: Option<usize>,
+    // maximum number of levels between two similar education levels (see education_distance); None compares the labels exactly
+    pub education_max_distance: Option<usize>,
+    // rank the shared characteristics of a node by count x inverse frequency instead of count, so rare traits come first
+    pub weighted_ranking: bool,
+    // inverse frequency of each characteristic (see inverse_frequencies); run_analysis computes it when weighted_ranking is set
+    pub inverse_frequencies: Option<HashMap<String, f64>>,
+    // whether an "Unknown" category (missing data) counts as shared (see UnknownPolicy)
+    pub unknown_policy: UnknownPolicy,
+    // buckets of the months inactive and of the contacts from the bank; None compares the counts (see similar_counts)
+    pub mon_inactive_buckets: Option<BucketSpec>,
+    pub num_contact_buckets: Option<BucketSpec>,
+}
+
+// Edges of the buckets of the months inactive and the contacts from the bank when they are bucketed: <2, 2-4, 4-6, >6
+// (each bucket includes its lower edge, so 0-1, 2-3, 4-5 and 6 or more)
+pub const ACTIVITY_BUCKET_EDGES: [f64; 3] = [2.0, 4.0, 6.0];
+
+// how the categorical fields compare when a customer's value is "Unknown" (missing in the data), and likewise the missing numbers (see shared_value)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownPolicy {
+    #[default]
+    Match, // "Unknown" is a category like any other: it matches "Unknown" only (the original behavior)
+    NeverMatch, // "Unknown" matches nothing, not even "Unknown", so missing data doesn't make customers similar
+    AlwaysMatch, // "Unknown" matches every value, giving customers with missing data the benefit of the doubt
+}
+
+// label of the categorical values missing in the data (see map_category)
+pub const UNKNOWN_CATEGORY: &str = "Unknown";
+
+impl SimilarityConfig {
+    // Function to check two categorical values under the unknown policy: Some(whether they match) if either is "Unknown",
+    // None if both are known and have to be compared as usual
+    pub fn unknown_match(&self, value_a: &str, value_b: &str) -> Option<bool> {
+        if value_a != UNKNOWN_CATEGORY && value_b != UNKNOWN_CATEGORY {
+            return None;
+        }
+        Some(match self.unknown_policy {
+            UnknownPolicy::Match => value_a == value_b,
+            UnknownPolicy::NeverMatch => false,
+            UnknownPolicy::AlwaysMatch => true,
+        })
+    }
+
+    // Function to check whether two values of a count field are similar: equal, or within similarity_tolerance
+    // of each other once normalized with the field's range (exact comparison if no ranges were computed)
+    pub fn similar_counts(&self, value_a: i32, value_b: i32, range: impl Fn(&NumericRanges) -> FieldRange) -> bool {
+        match (self.similarity_tolerance, &self.ranges) {
+            (Some(tolerance), Some(ranges)) => {
+                let range = range(ranges);
+                (range.normalize(value_a as f64) - range.normalize(value_b as f64)).abs() <= tolerance
+            }
+            _ => value_a == value_b,
+        }
+    }
+
+    // Function to compare two values of a count field that may be bucketed: with buckets, the values are similar when they
+    // fall into the same bucket, labeled with the bucket; without, they are compared like similar_counts, labeled with value_a.
+    // Missing values go through the unknown policy (see shared_value)
+    pub fn shared_count(&self, buckets: Option<&BucketSpec>, value_a: Option<i32>, value_b: Option<i32>, range: impl Fn(&NumericRanges) -> FieldRange) -> Option<String> {
+        match buckets {
+            Some(buckets) => self.shared_bucket(buckets, value_a.map(f64::from), value_b.map(f64::from)),
+            None => self.shared_value(value_a, value_b, |value_a, value_b| self.similar_counts(value_a, value_b, &range).then(|| value_a.to_string()), |value| value.to_string()),
+        }
+    }
+
+    // Function to compare two values of a bucketed field: the label of their common bucket if they fall into the same one
+    // missing values go through the unknown policy (see shared_value)
+    pub fn shared_bucket(&self, buckets: &BucketSpec, value_a: Option<f64>, value_b: Option<f64>) -> Option<String> {
+        self.shared_value(value_a, value_b, |value_a, value_b| {
+            let group_a = buckets.bucket(value_a);
+            (group_a == buckets.bucket(value_b)).then_some(group_a)
+        }, |value| buckets.bucket(value))
+    }
+
+    // Function to compare two possibly missing numeric values: two known values are compared with `compare` (the label of
+    // a match, or None), while a missing value is treated like an "Unknown" category under the unknown policy: with Match
+    // it only matches another missing value (labeled "Unknown"), with NeverMatch nothing, and with AlwaysMatch anything
+    // (labeled with the known value, see `label`)
+    pub fn shared_value<T: Copy>(&self, value_a: Option<T>, value_b: Option<T>, compare: impl Fn(T, T) -> Option<String>, label: impl Fn(T) -> String) -> Option<String> {
+        match (value_a, value_b) {
+            (Some(value_a), Some(value_b)) => compare(value_a, value_b),
+            (None, None) => (self.unknown_policy != UnknownPolicy::NeverMatch).then(|| UNKNOWN_CATEGORY.to_string()),
+            (Some(value), None) | (None, Some(value)) => (self.unknown_policy == UnknownPolicy::AlwaysMatch).then(|| label(value)),
+        }
+    }
+
+    // Function to check whether two income ranges are similar: the same label, or (in ordinal mode) at most
+    // income_max_distance brackets apart; a label outside INCOME_RANGES (e.g. "Unknown") only matches itself
+    pub fn similar_income(&self, income_a: &str, income_b: &str) -> bool {
+        match (self.income_max_distance, income_distance(income_a, income_b)) {
+            (Some(max_distance), Some(distance)) => distance <= max_distance,
+            _ => income_a == income_b,
+        }
+    }
+
+    // Function to check whether two education levels are similar: the same label, or (in ordinal mode) at most
+    // education_max_distance levels apart; a label outside EDUCATION_LEVELS (e.g. "Unknown") only matches itself
+    pub fn similar_education(&self, education_a: &str, education_b: &str) -> bool {
+        match (self.education_max_distance, education_distance(education_a, education_b)) {
+            (Some(max_distance), Some(distance)) => distance <= max_distance,
+            _ => education_a == education_b,
+        }
+    }
+}
+
+// Income_Category labels of BankChurners.csv from the lowest to the highest bracket ("Unknown" has no place in the order)
+pub const INCOME_RANGES: [&str; 5] = ["Less than $40K", "$40K - $60K", "$60K - $80K", "$80K - $120K", "$120K +"];
+
+// Function to get the number of brackets between two income ranges (0 for the same one), or None if a label isn't in INCOME_RANGES
+pub fn income_distance(income_a: &str, income_b: &str) -> Option<usize> {
+    let rank = |income: &str| INCOME_RANGES.iter().position(|&label| label == income.trim());
+    Some(rank(income_a)?.abs_diff(rank(income_b)?))
+}
+
+// Education_Level labels of BankChurners.csv from the lowest to the highest level ("Unknown" has no place in the order)
+pub const EDUCATION_LEVELS: [&str; 6] = ["Uneducated", "High School", "College", "Graduate", "Post-Graduate", "Doctorate"];
+
+// Function to get the number of levels between two education levels (0 for the same one), or None if a label isn't in EDUCATION_LEVELS
+pub fn education_distance(education_a: &str, education_b: &str) -> Option<usize> {
+    let rank = |education: &str| EDUCATION_LEVELS.iter().position(|&label| label == education.trim());
+    Some(rank(education_a)?.abs_diff(rank(education_b)?))
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        SimilarityConfig {
+            age: BucketSpec::new(&AGE_BUCKET_EDGES),
+            mon_w_bank: BucketSpec::new(&TENURE_BAND_EDGES),
+            transactions_amount: BucketSpec::new(&[500.0, 1000.0, 1500.0, 2000.0]),
+            num_transctions: BucketSpec::new(&[10.0, 20.0, 30.0, 40.0]),
+            avg_card_utilize: BucketSpec::with_epsilon(&[0.1, 0.2, 0.3, 0.4], UTILIZATION_EPSILON),
+            similarity_tolerance: None,
+            ranges: None,
+            excluded_attributes: HashSet::new(),
+            income_max_distance: None,
+            education_max_distance: None,
+            weighted_ranking: false,
+            inverse_frequencies: None,
+            unknown_policy: UnknownPolicy::Match,
+            mon_inactive_buckets: None,
+            num_contact_buckets: None,
+        }
+    }
+}
+
+// Function to put a numeric value into a bucket, given the bucket edges in ascending order
+// returns the bucket label: "<first edge", "lower-upper" for the buckets in between, or ">last edge"
+// each bucket includes its lower edge (BoundaryInclusion::LowerInclusive), so with edges [30, 40] the value 30 falls into "30-40" and 40 into ">40"
+pub fn numeric_bucket(value: f64, edges: &[f64]) -> String {
+    numeric_bucket_with_epsilon(value, edges, 0.0)
+}
+
+// Function to put a numeric value into a bucket like numeric_bucket, treating a value less than epsilon below an edge
+// as being on the edge, so float values that are off by a rounding error don't fall into the bucket below
+pub fn numeric_bucket_with_epsilon(value: f64, edges: &[f64], epsilon: f64) -> String {
+    numeric_bucket_with(value, edges, epsilon, BoundaryInclusion::LowerInclusive)
+}
+
+// Function to put a numeric value into a bucket like numeric_bucket_with_epsilon, with an explicit choice of the bucket
+// a value on an edge falls into; with UpperInclusive a value less than epsilon above an edge is on the edge.
+// The labels are the same in both modes, so "<30" holds 30 itself when the buckets include their upper edge
+pub fn numeric_bucket_with(value: f64, edges: &[f64], epsilon: f64, inclusion: BoundaryInclusion) -> String {
+    let below = |edge: f64| match inclusion {
+        BoundaryInclusion::LowerInclusive => value < edge - epsilon,
+        BoundaryInclusion::UpperInclusive => value <= edge + epsilon,
+    };
+    match edges.iter().position(|&edge| below(edge)) {
+        Some(0) => format!("<{}", edges[0]), // below the first edge
+        Some(i) => format!("{}-{}", edges[i - 1], edges[i]),
+        None => match edges.last() {
+            Some(last) => format!(">{}", last), // at or above the last edge
+            None => "All".to_string(), // no edges: everything falls into the same bucket
+        },
+    }
+}
+
+// names of the Customer fields a CustomerFilter can test (the field names of the Customer struct)
+pub const FILTER_FIELDS: [&str; 13] = [
+    "churn_status", "age", "education_level", "marital_status", "income_range", "card_type", "mon_w_bank",
+    "num_product_purchased", "mon_inactive", "num_contact", "transactions_amount", "num_transctions", "avg_card_utilize",
+];
+
+// condition "field=value" a customer must meet to be analyzed, e.g. card_type=Gold (see FILTER_FIELDS for the fields)
+// categorical values are compared exactly, numbers by value (so age=45 matches 45.0), the churn status by its label or alias
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomerFilter {
+    pub field: String,
+    pub value: String,
+}
+
+impl CustomerFilter {
+    // Function to parse a "field=value" filter; returns a message if the "=" is missing or the field is unknown
+    pub fn parse(text: &str) -> Result<CustomerFilter, String> {
+        let (field, value) = text.split_once('=').ok_or(format!("invalid filter '{}', expected field=value", text))?;
+        let field = field.trim();
+        if !FILTER_FIELDS.contains(&field) {
+            return Err(format!("unknown filter field '{}', expected one of {}", field, FILTER_FIELDS.join(", ")));
+        }
+        Ok(CustomerFilter { field: field.to_string(), value: value.trim().to_string() })
+    }
+
+    // Function to check whether a customer meets the condition
+    pub fn matches(&self, customer: &Customer) -> bool {
+        // a missing number only matches "Unknown"
+        let number = |actual: Option<f64>| match actual {
+            Some(actual) => self.value.parse::<f64>().is_ok_and(|expected| expected == actual),
+            None => self.value == UNKNOWN_CATEGORY,
+        };
+        let categories = &customer.one_hot_encoding;
+        match self.field.as_str() {
+            "churn_status" => customer.churn_status == ChurnStatus::from_label(&self.value),
+            "age" => number(customer.age.map(f64::from)),
+            "education_level" => categories.education_level == self.value,
+            "marital_status" => categories.marital_status == self.value,
+            "income_range" => categories.income_range == self.value,
+            "card_type" => categories.card_type == self.value,
+            "mon_w_bank" => number(customer.mon_w_bank.map(f64::from)),
+            "num_product_purchased" => number(customer.num_product_purchased.map(f64::from)),
+            "mon_inactive" => number(customer.mon_inactive.map(f64::from)),
+            "num_contact" => number(customer.num_contact.map(f64::from)),
+            "transactions_amount" => number(customer.transactions_amount.map(f64::from)),
+            "num_transctions" => number(customer.num_transctions.map(f64::from)),
+            "avg_card_utilize" => number(customer.avg_card_utilize),
+            _ => false, // not a field (only possible if the struct was built by hand)
+        }
+    }
+}
+
+// Function to keep the customers meeting every filter (an empty list keeps everyone), in their original order
+pub fn filter_customers(customers: Vec<Customer>, filters: &[CustomerFilter]) -> Vec<Customer> {
+    customers.into_iter().filter(|customer| filters.iter().all(|filter| filter.matches(customer))).collect()
+}
+
+// Function to map categorical values (lenient: any unrecognized value becomes "Unknown")
 pub fn map_category(value: &str) -> String {
+    map_category_strict(value).unwrap_or_else(|| UNKNOWN_CATEGORY.to_string()) // Default to unknown category
+}
+
+// Function to map categorical values, returning None for a value that isn't a known category (e.g. a new income
+// bracket or a typo), so schema drift can be caught instead of inflating the Unknown category
+pub fn map_category_strict(value: &str) -> Option<String> {
     match value {
-        "Unknown" => "Unknown".to_string(),// Unknown category
-        "High School" | "Graduate" | "Uneducated" | "College" | "Post-Graduate" | "Doctorate" => value.to_string(),  // Education categories
-        "Married" | "Single" | "Divorced" => value.to_string(),// Marital status categories
-        "Less than $40K" | "$40K - $60K" | "$60K - $80K" | "$80K - $120K" | "$120K +" => value.to_string(),// Income range categories 
-        "Blue" | "Silver" | "Gold" | "Platinum" => value.to_string(), // Card type categories
-        _ => "Unknown".to_string(), // Default to unknown category
+        "Unknown" => Some("Unknown".to_string()),// Unknown category
+        "High School" | "Graduate" | "Uneducated" | "College" | "Post-Graduate" | "Doctorate" => Some(value.to_string()),  // Education categories
+        "Married" | "Single" | "Divorced" => Some(value.to_string()),// Marital status categories
+        "Less than $40K" | "$40K - $60K" | "$60K - $80K" | "$80K - $120K" | "$120K +" => Some(value.to_string()),// Income range categories 
+        "Blue" | "Silver" | "Gold" | "Platinum" => Some(value.to_string()), // Card type categories
+        _ => None,
     }
 }
 
 #[cfg(test)]
-
 pub mod tests {
     use super::*;
+    use crate::graph_utils::determine_neighbor;
 
-    // test whether the get_shared_characteristics function is working correctly
+    // test whether the shared_traits function is working correctly
     #[test]
     pub fn test_shared_characteristics() {
         // Create two customers with known characteristics
         let customer1 = create_sample_customer1();
         let customer2 = create_sample_customer2();
 
-        // Use the get_shared_characteristics function to find shared characteristics
-        let shared_characteristics = get_shared_characteristics(&customer1, &customer2);
-        let correct_shared_characteristics =  ["Education Level: Graduate", "Marital Status: Single", "Income Range: $40K - $60K", "Card Type: Silver"];
+        // Use the shared_traits function to find shared characteristics
+        let shared_characteristics = shared_traits(&customer1, &customer2);
+        let correct_shared_characteristics =  ["Education Level: Graduate", "Marital Status: Single", "Income Range: $40K - $60K", "Card Type: Silver",
+            "Total Dollar Amount of Transaction via Card: >2000"];
         // Verify that the shared characteristics are correct
         assert_eq!(shared_characteristics, correct_shared_characteristics);
     }
@@ -222,50 +1184,807 @@ pub mod tests {
         assert_eq!(test_neighbor, correct_neighbor);
     }
 
-    //pub fn determine_neighbor(customer_a: &Customer, customer_b: &Customer) -> bool {
+    // test the counts and percentages computed for a known graph
+    #[test]
+    pub fn test_compute_shared_characteristic_summary() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        customer3_twin.num_product_purchased = Some(4); // only the four categorical characteristics are shared with customer 3
+        customer3_twin.mon_inactive = Some(1);
+        customer3_twin.num_contact = Some(3);
+        customer3_twin.mon_w_bank = Some(35);
+        customer3_twin.transactions_amount = Some(1200);
+        customer3_twin.num_transctions = Some(35);
+        customer3_twin.avg_card_utilize = Some(0.25);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3(), customer3_twin];
+        let graph = crate::graph_utils::construct_graph(&customers);
+
+        // the top four of customer 1 are its categorical characteristics (shared with both neighbors), customer 3 only shares those with its single neighbor
+        let summary = compute_shared_characteristic_summary(&[NodeIndex::new(0), NodeIndex::new(3)], &customers, &graph, 4, &SimilarityConfig::default());
+        assert_eq!(summary.node_count, 2);
+        assert_eq!(summary.total_count, 12);
+        assert!(summary.invalid_nodes.is_empty());
+        let names: Vec<&str> = summary.categories.iter().map(|category| category.name.as_str()).collect();
+        assert_eq!(names, ["Card Type", "Education Level", "Income Range", "Marital Status"]);
+
+        let education = &summary.categories[1];
+        assert_eq!(education.total_count, 3);
+        assert_eq!(education.percentage, 25.0);
+        assert_eq!((education.characteristics[0].name.as_str(), education.characteristics[0].count), ("Graduate", 2));
+        assert!((education.characteristics[0].percentage - 66.667).abs() < 0.001);
+        assert_eq!((education.characteristics[1].name.as_str(), education.characteristics[1].count), ("Doctorate", 1));
+        assert!((education.characteristics[1].percentage - 33.333).abs() < 0.001);
+
+        // out of bounds nodes are reported instead of printed
+        let summary = compute_shared_characteristic_summary(&[NodeIndex::new(7)], &customers, &graph, 4, &SimilarityConfig::default());
+        assert_eq!(summary.invalid_nodes, vec![7]);
+        assert!(summary.categories.is_empty());
+    }
+
+    // test that percentages are rounded to the configured number of decimal places
+    #[test]
+    pub fn test_format_percentage() {
+        assert_eq!(format_percentage(35.186, 0), "35");
+        assert_eq!(format_percentage(35.186, 1), "35.2");
+        assert_eq!(format_percentage(35.186, 2), "35.19");
+        assert_eq!(format_percentage(35.0, DEFAULT_PRECISION), "35.0");
+
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let summary = compute_shared_characteristic_summary(&[NodeIndex::new(0), NodeIndex::new(1)], &customers, &graph, 4, &SimilarityConfig::default());
+        let mut output = Vec::new();
+        write_summary_with(&summary, 2, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let category = &summary.categories[0];
+        assert!(output.contains(&format!("{}, (Total Count: {} - {:.2}%)", category.name, category.total_count, category.percentage)));
+    }
+
+    // test that the quiet output keeps the counts of the summary but not the lines of each category and characteristic
+    #[test]
+    pub fn test_write_summary_counts() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let summary = compute_shared_characteristic_summary(&[NodeIndex::new(0), NodeIndex::new(1)], &customers, &graph, 4, &SimilarityConfig::default());
+        let written = |quiet: bool| {
+            let mut output = Vec::new();
+            if quiet { write_summary_counts(&summary, &mut output).unwrap() } else { write_summary(&summary, &mut output).unwrap() }
+            String::from_utf8(output).unwrap()
+        };
+
+        let (verbose, quiet) = (written(false), written(true));
+        let detail_lines = |output: &str| output.lines().filter(|line| line.contains("Total Count") || line.starts_with("  ")).count();
+        assert!(detail_lines(&verbose) > 0);
+        assert_eq!(detail_lines(&quiet), 0);
+        assert!(quiet.contains(&format!("2 high centrality nodes, {} shared characteristics in {} categories", summary.total_count, summary.categories.len())));
+    }
+
+    // test that a characteristic without a ":" is still counted, and the category percentages use the same total
+    #[test]
+    pub fn test_categorize_characteristic_without_colon() {
+        let counts: std::collections::HashMap<String, usize> = [
+            ("Card Type: Blue".to_string(), 3),
+            ("Card Type: Gold".to_string(), 1),
+            ("Loyal".to_string(), 4),
+        ].into_iter().collect();
+        let (total, categories) = categorize_characteristic_counts(&counts);
+        assert_eq!(total, 8);
+        assert_eq!(categories.iter().map(|category| category.total_count).sum::<usize>(), total);
+        let other = categories.iter().find(|category| category.name == UNCATEGORIZED).unwrap();
+        assert_eq!((other.characteristics[0].name.as_str(), other.characteristics[0].count), ("Loyal", 4));
+        assert_eq!(other.percentage, 50.0);
+        assert!((categories.iter().map(|category| category.percentage).sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    // test that requesting the top 2 returns the two characteristics shared most often
+    #[test]
+    pub fn test_find_top_n_shared_characteristics() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer1_cousin = create_sample_customer1(); // shares education, marital status and products with customer 1
+        customer1_cousin.age = Some(41);
+        customer1_cousin.one_hot_encoding.income_range = "$80K - $120K".to_string();
+        customer1_cousin.one_hot_encoding.card_type = "Blue".to_string();
+        customer1_cousin.mon_inactive = Some(5);
+        customer1_cousin.num_contact = Some(1);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, customer1_cousin];
+        let graph = crate::graph_utils::construct_graph(&customers);
+
+        let top_two = find_top_shared_characteristics(&graph, NodeIndex::new(0), &customers, 2, &SimilarityConfig::default());
+        assert_eq!(top_two, vec![("Education Level: Graduate".to_string(), 3), ("Marital Status: Single".to_string(), 3)]);
+        assert_eq!(find_top_shared_characteristics(&graph, NodeIndex::new(0), &customers, 4, &SimilarityConfig::default()).len(), 4);
+        assert_eq!(find_top_shared_characteristics(&graph, NodeIndex::new(0), &customers, 12, &SimilarityConfig::default()).len(), 11); // all 11 shared characteristics
+    }
+
+    // test that adjacent income brackets are similar in ordinal mode but not in exact mode
+    #[test]
+    pub fn test_income_ordinal_distance() {
+        let customer1 = create_sample_customer1(); // $40K - $60K
+        let mut customer1_richer = create_sample_customer1();
+        customer1_richer.one_hot_encoding.income_range = "$60K - $80K".to_string(); // the next bracket up
+        let shares_income = |similarity: &SimilarityConfig| shared_traits_with(&customer1, &customer1_richer, similarity)
+            .iter().any(|characteristic| characteristic.starts_with("Income Range"));
+
+        assert!(!shares_income(&SimilarityConfig::default())); // exact mode
+        let ordinal = SimilarityConfig { income_max_distance: Some(1), ..SimilarityConfig::default() };
+        assert!(shares_income(&ordinal));
+        assert_eq!(income_distance("Less than $40K", "$120K +"), Some(4));
+        assert_eq!(income_distance("Unknown", "$40K - $60K"), None);
+        assert!(ordinal.similar_income("Unknown", "Unknown") && !ordinal.similar_income("Unknown", "Less than $40K"));
+    }
+
+    // test that in ordinal mode adjacent education levels are similar while levels far apart are not
+    #[test]
+    pub fn test_education_ordinal_distance() {
+        let graduate = create_sample_customer1(); // Graduate
+        let mut post_graduate = create_sample_customer1();
+        post_graduate.one_hot_encoding.education_level = "Post-Graduate".to_string(); // the next level up
+        let mut uneducated = create_sample_customer1();
+        uneducated.one_hot_encoding.education_level = "Uneducated".to_string(); // three levels down
+        let shares_education = |other: &Customer, similarity: &SimilarityConfig| shared_traits_with(&graduate, other, similarity)
+            .iter().any(|characteristic| characteristic.starts_with("Education Level"));
+
+        assert!(!shares_education(&post_graduate, &SimilarityConfig::default())); // exact mode
+        let ordinal = SimilarityConfig { education_max_distance: Some(1), ..SimilarityConfig::default() };
+        assert!(shares_education(&post_graduate, &ordinal));
+        assert!(!shares_education(&uneducated, &ordinal));
+        assert_eq!(education_distance("Doctorate", "Uneducated"), Some(5));
+        assert_eq!(education_distance("Unknown", "College"), None);
+        assert!(ordinal.similar_education("Unknown", "Unknown") && !ordinal.similar_education("Unknown", "Uneducated"));
+    }
+
+    // test that close counts are similar under a loose tolerance and not under a tight one
+    #[test]
+    pub fn test_similarity_tolerance() {
+        let customer1 = create_sample_customer1(); // 5 products
+        let mut customer1_cousin = create_sample_customer1();
+        customer1_cousin.num_product_purchased = Some(4);
+        let customers = vec![customer1.clone(), customer1_cousin.clone(), create_sample_customer3()]; // products range over 1..5
+        let shares_products = |similarity: &SimilarityConfig| shared_traits_with(&customer1, &customer1_cousin, similarity)
+            .iter()
+            .any(|characteristic| characteristic.starts_with("Number of Products Purchased"));
+        assert!(!shares_products(&SimilarityConfig::default()));
+
+        let ranges = NumericRanges::from_customers(&customers);
+        assert_eq!(ranges.unwrap().num_product_purchased, FieldRange { min: 1.0, max: 5.0 });
+        let loose = SimilarityConfig { similarity_tolerance: Some(0.3), ranges, ..SimilarityConfig::default() };
+        assert!(shares_products(&loose)); // normalized 1.0 vs 0.75
+        let tight = SimilarityConfig { similarity_tolerance: Some(0.1), ranges, ..SimilarityConfig::default() };
+        assert!(!shares_products(&tight));
+        // a tolerance without ranges falls back to exact comparison
+        assert!(!shares_products(&SimilarityConfig { similarity_tolerance: Some(0.3), ..SimilarityConfig::default() }));
+        assert_eq!(NumericRanges::from_customers(&[]), None);
+    }
+
+    // test that an excluded attribute is neither reported nor counted toward the neighbor threshold
+    #[test]
+    pub fn test_excluded_attributes() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2()]; // 5 shared characteristics, including the income range
+        let excluding_income = SimilarityConfig { excluded_attributes: HashSet::from(["Income Range".to_string()]), ..SimilarityConfig::default() };
+        let traits = shared_traits_with(&customers[0], &customers[1], &excluding_income);
+        assert_eq!(traits.len(), 4);
+        assert!(!traits.iter().any(|characteristic| characteristic.starts_with("Income Range")));
+
+        let weights = std::collections::HashMap::new();
+        let edges = |similarity: &SimilarityConfig| crate::graph_utils::construct_graph_with_weights(&customers, &weights, 5.0, similarity).edge_count();
+        assert_eq!(edges(&SimilarityConfig::default()), 1);
+        assert_eq!(edges(&excluding_income), 0);
+    }
+
+    // test that every neighbor is returned with all the traits it shares with the node
+    #[test]
+    pub fn test_neighbor_details() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), customer1_twin, create_sample_customer2()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+
+        let details = neighbor_details(&graph, NodeIndex::new(0), &customers, &SimilarityConfig::default());
+        let neighbors: Vec<NodeIndex> = details.iter().map(|(neighbor, _)| *neighbor).collect();
+        assert_eq!(neighbors, vec![NodeIndex::new(2), NodeIndex::new(3)]); // customer 3 isn't connected
+        assert_eq!(details[0].1.len(), 11); // everything but the age group
+        assert!(!details[0].1.iter().any(|characteristic| characteristic.starts_with("Age")));
+        assert_eq!(details[1].1, shared_traits(&customers[0], &customers[3]));
+        assert_eq!(details[1].1.len(), 5); // 12 and 8 months with the bank fall in different tenure bands
+
+        // the traits follow the given config: a single tenure band below 20 months puts customers 1 and 2 together
+        let wide_bands = SimilarityConfig { mon_w_bank: BucketSpec::new(&[20.0]), ..SimilarityConfig::default() };
+        let details = neighbor_details(&graph, NodeIndex::new(0), &customers, &wide_bands);
+        assert_eq!(details[1].1.len(), 6);
+        assert!(details[1].1.contains(&"Mon W Bank: <20".to_string()));
+
+        assert!(neighbor_details(&graph, NodeIndex::new(1), &customers, &SimilarityConfig::default()).is_empty());
+        assert!(neighbor_details(&graph, NodeIndex::new(9), &customers, &SimilarityConfig::default()).is_empty());
+    }
+
+    // test that the attribute on which a central customer differs from every neighbor tops the distinguishing list
+    #[test]
+    pub fn test_distinguishing_characteristics() {
+        let mut neighbors: Vec<Customer> = (0..3).map(|_| {
+            let mut neighbor = create_sample_customer1();
+            neighbor.one_hot_encoding.card_type = "Gold".to_string(); // the center has a Silver card
+            neighbor
+        }).collect();
+        neighbors[0].one_hot_encoding.marital_status = "Married".to_string();
+        let customers: Vec<Customer> = std::iter::once(create_sample_customer1()).chain(neighbors).collect();
+        let graph = crate::graph_utils::construct_graph(&customers);
+        assert_eq!(distinguishing_traits_with(&customers[0], &customers[1], &SimilarityConfig::default()), vec!["Marital Status", "Card Type"]);
+        assert!(distinguishing_traits_with(&customers[0], &customers[0], &SimilarityConfig::default()).is_empty());
+
+        let ranked = compute_distinguishing_characteristics(&[NodeIndex::new(0)], &customers, &graph, &SimilarityConfig::default());
+        assert_eq!(ranked, vec![
+            DistinguishingCharacteristic { attribute: "Card Type".to_string(), count: 3, percentage: 100.0 },
+            DistinguishingCharacteristic { attribute: "Marital Status".to_string(), count: 1, percentage: 100.0 / 3.0 },
+        ]);
+    }
+
+    // test that tied characteristics always come back in the same (alphabetical) order
+    #[test]
+    pub fn test_top_shared_characteristics_ties() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let expected = vec![
+            ("Card Type: Silver".to_string(), 1),
+            ("Education Level: Graduate".to_string(), 1),
+            ("Income Range: $40K - $60K".to_string(), 1),
+            ("Marital Status: Single".to_string(), 1),
+        ];
+        for _ in 0..20 { // every call builds a new HashMap with a different iteration order
+            assert_eq!(find_top_shared_characteristics(&graph, NodeIndex::new(0), &customers, 4, &SimilarityConfig::default()), expected);
+        }
+        assert_eq!(find_top_shared_characteristics(&graph, NodeIndex::new(0), &customers, 2, &SimilarityConfig::default()), expected[..2].to_vec());
+    }
+
+    // test that under weighted ranking a rare shared trait outranks a common one shared with more neighbors
+    #[test]
+    pub fn test_weighted_ranking_prefers_rare_traits() {
+        let mut customers = vec![create_sample_customer1(); 8]; // every trait of customer 1 is common to all of them...
+        for (index, customer) in customers.iter_mut().enumerate() {
+            customer.one_hot_encoding.education_level = if index < 3 { "Doctorate" } else { "High School" }.to_string(); // ...but the doctorate
+        }
+        let mut graph: Graph<&Customer, u32, Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = customers.iter().map(|customer| graph.add_node(customer)).collect();
+        for &neighbor in &nodes[1..4] {
+            graph.add_edge(nodes[0], neighbor, 1); // the doctorate is shared with 2 of the 3 neighbors, the card type with all of them
+        }
+        let card_type = format!("Card Type: {}", customers[0].one_hot_encoding.card_type);
+        let position = |top: &[(String, usize)], characteristic: &str| top.iter().position(|(name, _)| name == characteristic).unwrap();
+
+        let unweighted = find_top_shared_characteristics(&graph, nodes[0], &customers, 12, &SimilarityConfig::default());
+        assert!(position(&unweighted, &card_type) < position(&unweighted, "Education Level: Doctorate"));
+
+        let mut weighted_similarity = SimilarityConfig { weighted_ranking: true, ..SimilarityConfig::default() };
+        weighted_similarity.inverse_frequencies = Some(inverse_frequencies(&customers, &weighted_similarity));
+        let weighted = find_top_shared_characteristics(&graph, nodes[0], &customers, 12, &weighted_similarity);
+        assert_eq!(weighted[0], ("Education Level: Doctorate".to_string(), 2)); // the counts are unchanged
+        assert!(position(&weighted, &card_type) > 0);
+        assert_eq!(weighted_similarity.inverse_frequencies.unwrap()[&card_type], 0.0);
+    }
+
+    // test that a characteristic every pair of high centrality customers shares dominates their mutual profile
+    #[test]
+    pub fn test_compute_mutual_characteristic_summary() {
+        let mut silver_customer3 = create_sample_customer3();
+        silver_customer3.one_hot_encoding.card_type = "Silver".to_string(); // shares only the card type with customer 1
+        let mut graduate = create_sample_customer3(); // shares the card type with both and the education level with customer 1
+        graduate.age = Some(45);
+        graduate.one_hot_encoding = OneHotEncoding {
+            education_level: "Graduate".to_string(),
+            marital_status: "Divorced".to_string(),
+            income_range: "$60K - $80K".to_string(),
+            card_type: "Silver".to_string(),
+        };
+        graduate.mon_w_bank = Some(35);
+        graduate.num_product_purchased = Some(2);
+        graduate.mon_inactive = Some(4);
+        graduate.num_contact = Some(5);
+        graduate.transactions_amount = Some(1200);
+        graduate.num_transctions = Some(35);
+        graduate.avg_card_utilize = Some(0.15);
+        let customers = vec![create_sample_customer1(), silver_customer3, graduate, create_sample_customer2()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let central = [NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)];
+
+        let summary = compute_mutual_characteristic_summary(&central, &customers, &graph, &SimilarityConfig::default());
+        assert_eq!(summary.total_count, 4); // Silver in all three pairs, Graduate in one
+        let (category, characteristic) = summary.headline().unwrap();
+        assert_eq!((category.name.as_str(), characteristic.name.as_str(), characteristic.count), ("Card Type", "Silver", 3));
+        assert!(summary.invalid_nodes.is_empty());
+    }
+
+    // test that the bootstrap intervals are the same for the same seed and bracket the prevalence over all the nodes
+    #[test]
+    pub fn test_bootstrap_prevalence() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.num_contact = Some(3);
+        let mut customer2_cousin = create_sample_customer2();
+        customer2_cousin.one_hot_encoding.card_type = "Blue".to_string();
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3(), customer3_twin, customer2_cousin];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+        let similarity = SimilarityConfig::default();
+
+        let intervals = bootstrap_prevalence(&nodes, &customers, &graph, 4, &similarity, 200, 7);
+        assert_eq!(intervals, bootstrap_prevalence(&nodes, &customers, &graph, 4, &similarity, 200, 7));
+        assert!(!intervals.is_empty());
+        // the estimates are the prevalences of the summary over all the nodes
+        let summary = compute_shared_characteristic_summary(&nodes, &customers, &graph, 4, &similarity);
+        let (category, characteristic) = summary.headline().unwrap();
+        assert_eq!(intervals[0].characteristic, format!("{}: {}", category.name, characteristic.name));
+        assert!((intervals[0].estimate - characteristic.count as f64 / summary.total_count as f64 * 100.0).abs() < 1e-9);
+        for interval in &intervals {
+            assert!(interval.lower <= interval.estimate && interval.estimate <= interval.upper, "{:?}", interval);
+        }
+    }
+
+    // test that two customers known only to share missing categories are neighbors unless "Unknown" never matches
+    #[test]
+    pub fn test_unknown_policy() {
+        let unknown = OneHotEncoding {
+            education_level: UNKNOWN_CATEGORY.to_string(),
+            marital_status: UNKNOWN_CATEGORY.to_string(),
+            income_range: UNKNOWN_CATEGORY.to_string(),
+            card_type: UNKNOWN_CATEGORY.to_string(),
+        };
+        let mut customer1 = create_sample_customer1(); // customers 1 and 3 share no numeric characteristic
+        customer1.one_hot_encoding = unknown.clone();
+        let mut customer3 = create_sample_customer3();
+        customer3.one_hot_encoding = unknown;
+        let customers = vec![customer1, customer3];
+        let neighbors = |unknown_policy| {
+            let similarity = SimilarityConfig { unknown_policy, ..SimilarityConfig::default() };
+            crate::graph_utils::construct_graph_with_weights(&customers, &crate::graph_utils::default_attribute_weights(), 2.0, &similarity).edge_count() == 1
+        };
+        assert!(neighbors(UnknownPolicy::Match));
+        assert!(neighbors(UnknownPolicy::AlwaysMatch));
+        assert!(!neighbors(UnknownPolicy::NeverMatch));
+
+        // a missing value matches a known one only when "Unknown" always matches, labeled with the known value
+        let always = SimilarityConfig { unknown_policy: UnknownPolicy::AlwaysMatch, ..SimilarityConfig::default() };
+        let mut graduate = customers[1].clone();
+        graduate.one_hot_encoding.education_level = "Graduate".to_string();
+        assert!(shared_traits_with(&customers[0], &graduate, &always).contains(&"Education Level: Graduate".to_string()));
+        assert!(!shared_traits(&customers[0], &graduate).iter().any(|label| label.starts_with("Education Level")));
+    }
+
+    // test that missing numbers never look like a shared zero: a missing value doesn't match a known one (not even 0),
+    // and two missing values only match under the unknown policy, labeled "Unknown"
+    #[test]
+    pub fn test_missing_numbers_not_shared() {
+        let mut customer1 = create_sample_customer1();
+        let mut customer3 = create_sample_customer3(); // customers 1 and 3 share no numeric characteristic
+        for customer in [&mut customer1, &mut customer3] {
+            customer.transactions_amount = None;
+            customer.avg_card_utilize = None;
+        }
+        let shared = |customer_a: &Customer, customer_b: &Customer, unknown_policy| {
+            shared_traits_with(customer_a, customer_b, &SimilarityConfig { unknown_policy, ..SimilarityConfig::default() })
+        };
+        assert!(shared(&customer1, &customer3, UnknownPolicy::NeverMatch).is_empty());
+        assert_eq!(shared(&customer1, &customer3, UnknownPolicy::Match), vec![
+            "Total Dollar Amount of Transaction via Card: Unknown".to_string(),
+            "Average Card Utilization Ratio: Unknown".to_string(),
+        ]);
+
+        let mut zero = create_sample_customer3();
+        zero.transactions_amount = Some(0);
+        zero.avg_card_utilize = Some(0.0);
+        assert!(shared(&customer1, &zero, UnknownPolicy::Match).is_empty());
+        assert!(shared_traits_with(&customer1, &zero, &SimilarityConfig { unknown_policy: UnknownPolicy::AlwaysMatch, ..SimilarityConfig::default() })
+            .contains(&"Average Card Utilization Ratio: <0.1".to_string())); // the benefit of the doubt, labeled with the known value
+        assert_eq!(customer1.missing_fields(), vec!["Total_Trans_Amt", "Avg_Utilization_Ratio"]);
+    }
+
+    // test that the nodes are reported from the most to the least central
+    #[test]
+    pub fn test_write_node_characteristics_by_centrality() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let graph = crate::graph_utils::construct_graph(&customers);
+        let centrality = HashMap::from([(NodeIndex::new(0), 0.5), (NodeIndex::new(1), 0.9), (NodeIndex::new(2), 0.7)]);
+        let nodes = NodeOrder::Centrality.sort(&[NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)], &centrality);
+        assert_eq!(nodes, [1, 2, 0].map(NodeIndex::new));
+
+        let mut output = Vec::new();
+        write_node_characteristics(&nodes, &centrality, &customers, &graph, 2, &SimilarityConfig::default(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let reported: Vec<&str> = output.lines().map(|line| line.split(" (").next().unwrap().trim()).collect();
+        assert_eq!(reported, ["Node 1", "Node 2", "Node 0"]);
+        assert!(output.lines().next().unwrap().starts_with("  Node 1 (centrality 0.9000): "));
+        assert_eq!(NodeOrder::Index.sort(&nodes, &centrality), [0, 1, 2].map(NodeIndex::new));
+    }
+
+    // test that the rare characteristics of a category collapse into one "Other (rare)" entry while the frequent ones stay explicit
+    #[test]
+    pub fn test_collapse_rare_characteristics() {
+        let counts = std::collections::HashMap::from([
+            ("Card Type: Blue".to_string(), 40), ("Card Type: Silver".to_string(), 5),
+            ("Age: 40-50".to_string(), 20), ("Age: 50-60".to_string(), 18), ("Age: <30".to_string(), 2), ("Age: >60".to_string(), 1),
+            ("Age: 30-40".to_string(), 1),
+        ]);
+        let (total_count, categories) = categorize_characteristic_counts(&counts);
+        let summary = CharacteristicSummary { node_count: 3, total_count, categories, invalid_nodes: Vec::new() };
+
+        let collapsed = summary.collapse_rare(RareThreshold::Count(3));
+        let age = collapsed.categories.iter().find(|category| category.name == "Age").unwrap();
+        let entries: Vec<(&str, usize)> = age.characteristics.iter().map(|characteristic| (characteristic.name.as_str(), characteristic.count)).collect();
+        assert_eq!(entries, [("40-50", 20), ("50-60", 18), ("Other (rare)", 4)]);
+        assert_ne!(RARE_CHARACTERISTICS, UNCATEGORIZED); // "Other: Other" would mix up the two
+        assert_eq!(age.total_count, 42);
+        assert!((age.characteristics.iter().map(|characteristic| characteristic.percentage).sum::<f64>() - 100.0).abs() < 1e-9);
+        // Silver is the only rare card type, so it stays explicit
+        assert_eq!(collapsed.categories.iter().find(|category| category.name == "Card Type"), summary.categories.iter().find(|category| category.name == "Card Type"));
+        assert_eq!(collapsed.total_count, summary.total_count);
+
+        // by share: Silver (11% of the card types) is still alone, the three ages under 5% collapse
+        let by_share = summary.collapse_rare(RareThreshold::Percentage(15.0));
+        assert_eq!(by_share.categories.iter().map(|category| category.characteristics.len()).collect::<Vec<_>>(), [2, 3]);
+    }
+
+    // test that bucketed months inactive and contacts are shared within a bucket but not across a bucket edge
+    #[test]
+    pub fn test_activity_buckets() {
+        let bucketed = SimilarityConfig {
+            mon_inactive_buckets: Some(BucketSpec::new(&ACTIVITY_BUCKET_EDGES)),
+            num_contact_buckets: Some(BucketSpec::new(&ACTIVITY_BUCKET_EDGES)),
+            ..SimilarityConfig::default()
+        };
+        let with_activity = |mon_inactive, num_contact| {
+            let mut customer = create_sample_customer1();
+            customer.mon_inactive = Some(mon_inactive);
+            customer.num_contact = Some(num_contact);
+            customer
+        };
+        let activity_traits = |a: &Customer, b: &Customer, similarity: &SimilarityConfig| -> Vec<String> {
+            shared_traits_with(a, b, similarity).into_iter()
+                .filter(|label| label.starts_with("Month inactive") || label.starts_with("Number of Contacts"))
+                .collect()
+        };
+
+        // 2 and 3 months inactive, 4 and 5 contacts: the same buckets, but different exact values
+        let (a, b) = (with_activity(2, 4), with_activity(3, 5));
+        assert_eq!(activity_traits(&a, &b, &bucketed), ["Month inactive: 2-4", "Number of Contacts from Bank (past 12 months): 4-6"]);
+        assert!(activity_traits(&a, &b, &SimilarityConfig::default()).is_empty());
+        // 1 and 2 months inactive, 5 and 6 contacts: one apart, but across a bucket edge
+        let (c, d) = (with_activity(1, 5), with_activity(2, 6));
+        assert!(activity_traits(&c, &d, &bucketed).is_empty());
+        // the exact comparison is kept for a field without buckets
+        let contacts_only = SimilarityConfig { mon_inactive_buckets: None, ..bucketed };
+        assert_eq!(activity_traits(&with_activity(3, 0), &with_activity(3, 1), &contacts_only),
+            ["Month inactive: 3", "Number of Contacts from Bank (past 12 months): <2"]);
+    }
+
+    // test that the headline is the characteristic shared the most, even in a category that isn't the largest
+    #[test]
+    pub fn test_summary_headline() {
+        let counts = std::collections::HashMap::from([
+            ("Card Type: Blue".to_string(), 40), ("Card Type: Silver".to_string(), 5),
+            ("Age: 40-50".to_string(), 20), ("Age: 50-60".to_string(), 18), ("Age: 30-40".to_string(), 15),
+            ("Marital Status: Married".to_string(), 12),
+        ]);
+        let (total_count, categories) = categorize_characteristic_counts(&counts);
+        let summary = CharacteristicSummary { node_count: 3, total_count, categories, invalid_nodes: Vec::new() };
+        assert_eq!(summary.categories[0].name, "Age"); // the largest category...
+        let (category, characteristic) = summary.headline().unwrap();
+        assert_eq!((category.name.as_str(), characteristic.name.as_str(), characteristic.count), ("Card Type", "Blue", 40)); // ...but Blue dominates
+
+        let empty = CharacteristicSummary { node_count: 0, total_count: 0, categories: Vec::new(), invalid_nodes: Vec::new() };
+        assert!(empty.headline().is_none());
+    }
+
+    // test that the customers sharing the most characteristics come first, and the target itself is skipped
+    #[test]
+    pub fn test_most_similar() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41); // 11 shared with customer 1
+        let customers = vec![create_sample_customer3(), create_sample_customer1(), create_sample_customer2(), customer1_twin];
+        assert_eq!(most_similar(&customers[1], &customers, 2), vec![(3, 11), (2, 5)]);
+        assert_eq!(most_similar(&customers[1], &customers, 10).len(), 3);
+        // a target that isn't in the slice is compared with every customer, including an identical one
+        assert_eq!(most_similar(&create_sample_customer1(), &customers, 1), vec![(1, 12)]);
+    }
+
+    // test the one-line profile of a sample customer
+    #[test]
+    pub fn test_customer_display() {
+        assert_eq!(create_sample_customer3().to_string(),
+            "Attrited Customer, age 62, $120K +, Gold card, 48 months with the bank, 1 products, $900 in 60 transactions, utilization 0.05");
+    }
+
+    // test that the BankChurners churn labels are parsed into the enum, and anything else becomes Unknown
+    #[test]
+    pub fn test_churn_status_from_label() {
+        assert_eq!(ChurnStatus::from_label("Existing Customer"), ChurnStatus::Existing);
+        assert_eq!(ChurnStatus::from_label("Attrited Customer"), ChurnStatus::Attrited);
+        assert_eq!(ChurnStatus::from_label(" attrited customer "), ChurnStatus::Attrited);
+        assert_eq!(ChurnStatus::from_label("Churned"), ChurnStatus::Attrited);
+        assert_eq!(ChurnStatus::from_label("Existing Custmer"), ChurnStatus::Unknown);
+        assert_eq!(ChurnStatus::from_label("1"), ChurnStatus::Unknown);
+        assert_eq!(ChurnStatus::Attrited.to_string(), "Attrited Customer");
+    }
+
+    // test that only the customers matching every filter are kept, so only they enter the graph
+    #[test]
+    pub fn test_filter_customers() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let silver = vec![CustomerFilter::parse("card_type=Silver").unwrap()];
+        let filtered = filter_customers(customers.clone(), &silver);
+        assert_eq!(filtered, customers[..2]);
+        let graph = crate::graph_utils::construct_graph(&filtered);
+        assert_eq!(graph.node_count(), 2);
+        assert!(graph.node_weights().all(|customer| customer.one_hot_encoding.card_type == "Silver"));
+
+        // filters are ANDed, and numbers are compared by value
+        let silver_attrited = vec![silver[0].clone(), CustomerFilter::parse("churn_status = Attrited Customer").unwrap()];
+        assert_eq!(filter_customers(customers.clone(), &silver_attrited), customers[1..2]);
+        assert_eq!(filter_customers(customers.clone(), &[CustomerFilter::parse("age=62.0").unwrap()]), customers[2..]);
+        assert_eq!(filter_customers(customers.clone(), &[]), customers);
+        assert!(CustomerFilter::parse("card=Gold").is_err());
+        assert!(CustomerFilter::parse("card_type").is_err());
+    }
+
+    // test that an unexpected category becomes Unknown by default and is an error in strict mode
+    #[test]
+    pub fn test_strict_categories() {
+        assert_eq!(map_category("$200K +"), "Unknown");
+        assert_eq!(map_category_strict("$200K +"), None);
+        assert_eq!(map_category_strict("$120K +").as_deref(), Some("$120K +"));
+        assert_eq!(map_category_strict("Unknown").as_deref(), Some("Unknown")); // the label used by the dataset itself is known
+
+        let columns = ColumnMap::default();
+        let row = csv::StringRecord::from(vec!["768805383", "Existing Customer", "45", "M", "3", "High School", "Married", "$200K +", "Blue", "39", "5", "1", "3", "12691", "777", "11914", "1.335", "1144", "42", "1.625", "0.061"]);
+        assert_eq!(Customer::from_record(&row, &columns).unwrap().one_hot_encoding.income_range, "Unknown");
+        assert_eq!(Customer::from_record_with(&row, &columns, true),
+            Err(RecordError::UnknownCategory { field: "Income_Category", value: "$200K +".to_string() }));
+    }
+
+    // test that missing and non-numeric fields are reported as different errors (an empty number is missing data, not an error)
+    #[test]
+    pub fn test_from_record_errors() {
+        let columns = ColumnMap::default();
+        let row = |values: &[&str]| csv::StringRecord::from(values.to_vec());
+        let valid = ["768805383", "Existing Customer", "45", "M", "3", "High School", "Married", "$60K - $80K", "Blue", "39", "5", "1", "3", "12691", "777", "11914", "1.335", "1144", "42", "1.625", "0.061"];
+
+        let customer = Customer::from_record(&row(&valid), &columns).unwrap();
+        assert_eq!(customer.age, Some(45));
+        assert_eq!(customer.avg_card_utilize, Some(0.061));
+
+        let mut empty_age = valid;
+        empty_age[2] = "";
+        assert_eq!(Customer::from_record(&row(&empty_age), &columns).unwrap().age, None); // a missing number is kept as unknown
+
+        let mut text_amount = valid;
+        text_amount[17] = "lots";
+        assert_eq!(Customer::from_record(&row(&text_amount), &columns),
+            Err(RecordError::UnparseableField { field: "Total_Trans_Amt", value: "lots".to_string() }));
+
+        // formatted dollar amounts are accepted, the cents are truncated
+        let mut formatted_amount = valid;
+        formatted_amount[17] = "$1,234.56";
+        assert_eq!(Customer::from_record(&row(&formatted_amount), &columns).unwrap().transactions_amount, Some(1234));
+        formatted_amount[17] = "1777";
+        assert_eq!(Customer::from_record(&row(&formatted_amount), &columns).unwrap().transactions_amount, Some(1777));
+        formatted_amount[17] = "$12.3x";
+        assert_eq!(Customer::from_record(&row(&formatted_amount), &columns),
+            Err(RecordError::UnparseableField { field: "Total_Trans_Amt", value: "$12.3x".to_string() }));
+
+        let mut empty_card = valid;
+        empty_card[8] = " ";
+        assert_eq!(Customer::from_record(&row(&empty_card), &columns), Err(RecordError::MissingField("Card_Category")));
+
+    }
+
+    // test that a utilization ratio outside [0, 1] is clamped and reported by default, and an error with strict ranges
+    #[test]
+    pub fn test_utilization_out_of_range() {
+        let columns = ColumnMap::default();
+        let mut values = ["768805383", "Existing Customer", "45", "M", "3", "High School", "Married", "$60K - $80K", "Blue", "39", "5", "1", "3", "12691", "777", "11914", "1.335", "1144", "42", "1.625", "5000"];
+        let row = |values: &[&str]| csv::StringRecord::from(values.to_vec());
+        let (customer, clamped) = Customer::from_record_checked(&row(&values), &columns, false, false).unwrap();
+        assert_eq!(customer.avg_card_utilize, Some(1.0));
+        assert_eq!(clamped, vec!["Avg_Utilization_Ratio"]);
+        assert_eq!(Customer::from_record(&row(&values), &columns).unwrap().avg_card_utilize, Some(1.0));
+        assert_eq!(Customer::from_record_checked(&row(&values), &columns, false, true),
+            Err(RecordError::OutOfRange { field: "Avg_Utilization_Ratio", value: "5000".to_string() }));
+
+        values[20] = "-0.2";
+        assert_eq!(Customer::from_record_checked(&row(&values), &columns, false, false).unwrap().0.avg_card_utilize, Some(0.0));
+        values[20] = "1";
+        assert!(Customer::from_record_checked(&row(&values), &columns, false, true).unwrap().1.is_empty()); // the bounds are valid
+    }
+
+    // test that a truncated row is rejected with an error naming the first column that was cut off
+    #[test]
+    pub fn test_from_record_short_row() {
+        let columns = ColumnMap::default();
+        let valid = ["768805383", "Existing Customer", "45", "M", "3", "High School", "Married", "$60K - $80K", "Blue", "39", "5", "1", "3", "12691", "777", "11914", "1.335", "1144", "42", "1.625", "0.061"];
+        let short = Customer::from_record(&csv::StringRecord::from(valid[..19].to_vec()), &columns); // cut off before the utilization ratio column
+        assert_eq!(short, Err(RecordError::ShortRow { fields: 19, expected: 21, column: "Avg_Utilization_Ratio" }));
+        assert_eq!(short.unwrap_err().to_string(), "row has 19 fields, expected at least 21 (missing column Avg_Utilization_Ratio)");
+
+        let cut_early = Customer::from_record(&csv::StringRecord::from(valid[..6].to_vec()), &columns);
+        assert!(matches!(cut_early, Err(RecordError::ShortRow { column: "Marital_Status", .. })));
+    }
+
+    // test that customers in the same age group share the age characteristic
+    #[test]
+    pub fn test_age_same_bucket() {
+        let mut customer1 = create_sample_customer1();
+        let mut customer2 = create_sample_customer2();
+        customer1.age = Some(34);
+        customer2.age = Some(35);
+        let shared_characteristics = shared_traits(&customer1, &customer2);
+        assert_eq!(shared_characteristics[0], "Age: 30-40");
+    }
+
+    // test that ages on either side of a group boundary don't share the age characteristic
+    #[test]
+    pub fn test_age_across_bucket_boundary() {
+        let mut customer1 = create_sample_customer1();
+        let mut customer2 = create_sample_customer2();
+        customer1.age = Some(39);
+        customer2.age = Some(40);
+        let shared_characteristics = shared_traits(&customer1, &customer2);
+        assert!(!shared_characteristics.iter().any(|characteristic| characteristic.starts_with("Age")));
+        assert_eq!(numeric_bucket(39.0, &AGE_BUCKET_EDGES), "30-40");
+        assert_eq!(numeric_bucket(40.0, &AGE_BUCKET_EDGES), "40-50");
+        assert_eq!(numeric_bucket(18.0, &AGE_BUCKET_EDGES), "<30");
+        assert_eq!(numeric_bucket(75.0, &AGE_BUCKET_EDGES), ">60");
+    }
+
+    // test that a value exactly on an edge falls into the bucket above it by default, and below it when upper inclusive
+    #[test]
+    pub fn test_boundary_inclusion() {
+        let edges = [20.0, 30.0, 40.0];
+        assert_eq!(numeric_bucket_with(20.0, &edges, 0.0, BoundaryInclusion::LowerInclusive), "20-30");
+        assert_eq!(numeric_bucket_with(30.0, &edges, 0.0, BoundaryInclusion::LowerInclusive), "30-40");
+        assert_eq!(numeric_bucket_with(40.0, &edges, 0.0, BoundaryInclusion::LowerInclusive), ">40");
+        assert_eq!(numeric_bucket_with(20.0, &edges, 0.0, BoundaryInclusion::UpperInclusive), "<20");
+        assert_eq!(numeric_bucket_with(30.0, &edges, 0.0, BoundaryInclusion::UpperInclusive), "20-30");
+        assert_eq!(numeric_bucket_with(40.0, &edges, 0.0, BoundaryInclusion::UpperInclusive), "30-40");
+        assert_eq!(numeric_bucket_with(25.0, &edges, 0.0, BoundaryInclusion::UpperInclusive), "20-30"); // off the edges nothing changes
+        assert_eq!(numeric_bucket_with(30.0000001, &edges, 1e-6, BoundaryInclusion::UpperInclusive), "20-30"); // within epsilon above
+
+        // the default spec is lower inclusive, like numeric_bucket
+        let spec = BucketSpec::new(&edges);
+        assert_eq!(spec.inclusion, BoundaryInclusion::default());
+        assert_eq!(spec.bucket(30.0), numeric_bucket(30.0, &edges));
+        assert_eq!(spec.with_inclusion(BoundaryInclusion::UpperInclusive).bucket(30.0), "20-30");
+
+        // two customers 40 and 41 years old share the age group only when the groups include their upper edge
+        let (mut customer1, mut customer2) = (create_sample_customer1(), create_sample_customer2());
+        customer1.age = Some(40);
+        customer2.age = Some(41);
+        let shares_age = |similarity: &SimilarityConfig| shared_traits_with(&customer1, &customer2, similarity).iter().any(|trait_| trait_.starts_with("Age"));
+        assert!(shares_age(&SimilarityConfig::default()));
+        let upper = SimilarityConfig { age: BucketSpec::new(&AGE_BUCKET_EDGES).with_inclusion(BoundaryInclusion::UpperInclusive), ..SimilarityConfig::default() };
+        assert!(!shares_age(&upper));
+    }
+
+    // test that customers in the same tenure band share it, customers in adjacent bands don't, and the bands can be changed
+    #[test]
+    pub fn test_tenure_bands() {
+        let (mut customer1, mut customer2) = (create_sample_customer1(), create_sample_customer2());
+        let shared_tenure = |customer1: &Customer, customer2: &Customer, similarity: &SimilarityConfig| shared_traits_with(customer1, customer2, similarity)
+            .into_iter()
+            .find(|characteristic| characteristic.starts_with("Mon W Bank"));
+        customer1.mon_w_bank = Some(13);
+        customer2.mon_w_bank = Some(23);
+        assert_eq!(shared_tenure(&customer1, &customer2, &SimilarityConfig::default()), Some("Mon W Bank: 12-24".to_string()));
+        customer2.mon_w_bank = Some(24); // the next band starts at 24 months
+        assert_eq!(shared_tenure(&customer1, &customer2, &SimilarityConfig::default()), None);
+        customer1.mon_w_bank = Some(40);
+        customer2.mon_w_bank = Some(56);
+        assert_eq!(shared_tenure(&customer1, &customer2, &SimilarityConfig::default()), Some("Mon W Bank: >36".to_string()));
+
+        // with yearly bands 40 and 56 months are in different bands
+        let yearly = crate::analysis::AnalysisConfig::builder().tenure_bands(&[12.0, 24.0, 36.0, 48.0]).build().similarity;
+        assert_eq!(shared_tenure(&customer1, &customer2, &yearly), None);
+        customer2.mon_w_bank = Some(47);
+        assert_eq!(shared_tenure(&customer1, &customer2, &yearly), Some("Mon W Bank: 36-48".to_string()));
+    }
+
+    // test that utilization ratios off by a rounding error land in the same bucket
+    #[test]
+    pub fn test_utilization_epsilon_bucket() {
+        let utilization = SimilarityConfig::default().avg_card_utilize;
+        assert_eq!(utilization.bucket(0.2999999), utilization.bucket(0.3));
+        assert_eq!(utilization.bucket(0.1 + 0.2), "0.3-0.4"); // 0.30000000000000004
+        assert_eq!(utilization.bucket(0.299), "0.2-0.3"); // a real difference still counts
+        assert_eq!(numeric_bucket(0.2999999, &[0.1, 0.2, 0.3, 0.4]), "0.2-0.3"); // without epsilon the edge is exact
+
+        let mut customer1 = create_sample_customer1();
+        let mut customer2 = create_sample_customer2();
+        customer1.avg_card_utilize = Some(0.2999999);
+        customer2.avg_card_utilize = Some(0.3);
+        assert!(shared_traits(&customer1, &customer2).contains(&"Average Card Utilization Ratio: 0.3-0.4".to_string()));
+    }
+
+    // test that retuning a field's bucket spec changes which pairs share that characteristic
+    #[test]
+    pub fn test_bucket_spec_changes_shared_characteristics() {
+        let customer1 = create_sample_customer1(); // 25 transactions
+        let customer2 = create_sample_customer2(); // 15 transactions
+        let shares_transactions = |similarity: &SimilarityConfig| shared_traits_with(&customer1, &customer2, similarity)
+            .iter()
+            .any(|characteristic| characteristic.starts_with("Total Number of Transactions via Card"));
+        assert!(!shares_transactions(&SimilarityConfig::default())); // "20-30" and "10-20"
+
+        let wider_bins = SimilarityConfig { num_transctions: BucketSpec::new(&[10.0, 30.0]), ..SimilarityConfig::default() };
+        assert!(shares_transactions(&wider_bins));
+        assert!(shared_traits_with(&customer1, &customer2, &wider_bins).contains(&"Total Number of Transactions via Card: 10-30".to_string()));
+        let score = crate::graph_utils::shared_characteristics_score_with(&customer1, &customer2, &std::collections::HashMap::new(), &wider_bins);
+        assert_eq!(score, 6.0); // the neighbor score uses the same spec
+    }
+
 
     // Helper functions to create two sample customers with known characteristics
     pub fn create_sample_customer1() -> Customer {
         Customer {
-            churn_status: "Existing Customer".to_string(),
-            age: 25,
+            churn_status: ChurnStatus::Existing,
+            age: Some(25),
             one_hot_encoding: OneHotEncoding {
                 education_level: "Graduate".to_string(),
                 marital_status: "Single".to_string(),
                 income_range: "$40K - $60K".to_string(),
                 card_type: "Silver".to_string(),
             },
-            mon_w_bank: 12,
-            num_product_purchased: 5,
-            mon_inactive: 2,
-            num_contact: 8,
+            mon_w_bank: Some(12),
+            num_product_purchased: Some(5),
+            mon_inactive: Some(2),
+            num_contact: Some(8),
             //card_credit_limit: 15000,
             //evolving_bal: 1200,
-            transactions_amount: 5000,
-            num_transctions: 25,
-            avg_card_utilize: 0.4,
+            transactions_amount: Some(5000),
+            num_transctions: Some(25),
+            avg_card_utilize: Some(0.4),
         }
     }
     
     pub fn create_sample_customer2() -> Customer {
         Customer {
-            churn_status: "Attrited Customer".to_string(),
-            age: 30,
+            churn_status: ChurnStatus::Attrited,
+            age: Some(30),
             one_hot_encoding: OneHotEncoding {
                 education_level: "Graduate".to_string(),
                 marital_status: "Single".to_string(),
                 income_range: "$40K - $60K".to_string(),
                 card_type: "Silver".to_string(),
             },
-            mon_w_bank: 8,
-            num_product_purchased: 3,
-            mon_inactive: 3,
-            num_contact: 12,
+            mon_w_bank: Some(8),
+            num_product_purchased: Some(3),
+            mon_inactive: Some(3),
+            num_contact: Some(12),
             //card_credit_limit: 12000,
             //evolving_bal: 800,
-            transactions_amount: 3000,
-            num_transctions: 15,
-            avg_card_utilize: 0.3,
+            transactions_amount: Some(3000),
+            num_transctions: Some(15),
+            avg_card_utilize: Some(0.3),
+        }
+    }
+
+    // a third customer sharing no characteristics with the two above
+    pub fn create_sample_customer3() -> Customer {
+        Customer {
+            churn_status: ChurnStatus::Attrited,
+            age: Some(62),
+            one_hot_encoding: OneHotEncoding {
+                education_level: "Doctorate".to_string(),
+                marital_status: "Married".to_string(),
+                income_range: "$120K +".to_string(),
+                card_type: "Gold".to_string(),
+            },
+            mon_w_bank: Some(48),
+            num_product_purchased: Some(1),
+            mon_inactive: Some(6),
+            num_contact: Some(0),
+            transactions_amount: Some(900),
+            num_transctions: Some(60),
+            avg_card_utilize: Some(0.05),
         }
     }
-}    
+}
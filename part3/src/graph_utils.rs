@@ -1,112 +1,535 @@
 use petgraph::graph::{Graph, NodeIndex};
-use petgraph::algo::dijkstra;
-use std::collections::HashMap;
-use petgraph::Undirected;
-use crate::customer::{Customer}; // Import the Customer struct from the local module
+use petgraph::algo::{dijkstra, floyd_warshall};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use petgraph::{Directed, Undirected};
+use crate::customer::{ChurnStatus, Customer, SimilarityConfig, shared_traits, shared_traits_with}; // Import the Customer struct and the trait comparison from the local module
 
-// Function to construct a graph from customers
-pub fn construct_graph(customers: &[Customer]) -> Graph<&Customer, (), Undirected> {
+// trait for the records the graph can be built from: anything that can list the characteristics it shares with another record
+// (Customer implements it with shared_traits), so construct_graph and determine_neighbor work for other data too
+pub trait GraphableEntity {
+    fn shared_characteristics(&self, other: &Self) -> Vec<String>;
+}
+
+impl GraphableEntity for Customer {
+    fn shared_characteristics(&self, other: &Self) -> Vec<String> {
+        shared_traits(self, other)
+    }
+}
+
+// Function to construct a graph from customers (or any GraphableEntity)
+// each edge carries the number of characteristics the two customers share, so stronger connections can be told apart.
+// For customers this is the same graph as construct_graph_with_weights with every attribute weighing 1.0
+pub fn construct_graph<T: GraphableEntity>(entities: &[T]) -> Graph<&T, u32, Undirected> {
+    let mut graph = Graph::new_undirected();
+    let nodes: Vec<NodeIndex> = entities.iter().map(|entity| graph.add_node(entity)).collect();
+    for i in 0..entities.len() {
+        for j in (i + 1)..entities.len() {
+            let shared = entities[i].shared_characteristics(&entities[j]); // computed once, for both the neighbor check (as in determine_neighbor) and the weight
+            if shared.len() >= NEIGHBOR_THRESHOLD as usize {
+                graph.add_edge(nodes[i], nodes[j], shared.len() as u32);
+            }
+        }
+    }
+    graph
+}
+
+// Function to construct a graph where each shared attribute contributes its configured weight toward the threshold
+// (see shared_characteristics_score); edges still carry the plain number of shared characteristics
+pub fn construct_graph_with_weights<'a>(
+    customers: &'a [Customer], // Slice of Customer representing all customers
+    attribute_weights: &HashMap<String, f64>, // weight per attribute name, missing attributes weigh 1.0
+    threshold: f64, // minimum weighted score for two customers to be connected
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+) -> Graph<&'a Customer, u32, Undirected> {
+    construct_graph_with_progress(customers, attribute_weights, threshold, similarity, |_, _| {}) // silent
+}
+
+// Number of progress reports made while constructing a graph (one every 10% of the customers)
+pub const PROGRESS_STEPS: usize = 10;
+
+// Function to construct the graph like construct_graph_with_weights, calling progress(done, total) each time another
+// 10% of the customers has been compared with the rest, so long runs on the full dataset can show they're alive
+// (the first customers take the longest, since each one is compared with every customer after it)
+pub fn construct_graph_with_progress<'a>(
+    customers: &'a [Customer], // Slice of Customer representing all customers
+    attribute_weights: &HashMap<String, f64>, // weight per attribute name, missing attributes weigh 1.0
+    threshold: f64, // minimum weighted score for two customers to be connected
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+    progress: impl FnMut(usize, usize), // called with the number of customers done and the total
+) -> Graph<&'a Customer, u32, Undirected> {
+    construct_graph_where(customers, attribute_weights, threshold, similarity, |_, _| true, progress)
+}
+
+// Function to construct the graph like construct_graph_with_progress, but only within each churn group: customers
+// with a different churn status are never connected, however much they share, so the graph is made of disconnected
+// subgraphs (churned, existing and unknown) that keep the node indices of the customers slice
+pub fn construct_graph_within_churn_groups<'a>(
+    customers: &'a [Customer], // Slice of Customer representing all customers
+    attribute_weights: &HashMap<String, f64>, // weight per attribute name, missing attributes weigh 1.0
+    threshold: f64, // minimum weighted score for two customers to be connected
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+    progress: impl FnMut(usize, usize), // called with the number of customers done and the total
+) -> Graph<&'a Customer, u32, Undirected> {
+    construct_graph_where(customers, attribute_weights, threshold, similarity, |a, b| a.churn_status == b.churn_status, progress)
+}
+
+// Function to construct the graph, only comparing the pairs of customers for which allowed(a, b) holds
+fn construct_graph_where<'a>(
+    customers: &'a [Customer],
+    attribute_weights: &HashMap<String, f64>,
+    threshold: f64,
+    similarity: &SimilarityConfig,
+    allowed: impl Fn(&Customer, &Customer) -> bool, // whether the pair may be connected at all
+    mut progress: impl FnMut(usize, usize),
+) -> Graph<&'a Customer, u32, Undirected> {
     let mut graph = Graph::new_undirected(); // Create an undirected graph
     let node_indices: Vec<NodeIndex> = customers.iter().map(|customer| graph.add_node(customer)).collect();
+    let mut reported_steps = 0;
 
     // Iterate through pairs of customers and add edges if conditions are met
+    // the graph is undirected, so every pair only needs to be checked (and connected) once
     for (i, &customer_a) in node_indices.iter().enumerate() {
-        for (j, &customer_b) in node_indices.iter().enumerate() {
-            if i != j && determine_neighbor(graph.node_weight(customer_a).unwrap(), graph.node_weight(customer_b).unwrap()) { // use helper function determine_neighbor to check condition
-                graph.add_edge(customer_a, customer_b, ()); // Add an edge between customers with shared characteristics
+        for &customer_b in node_indices.iter().skip(i + 1) {
+            if !allowed(graph[customer_a], graph[customer_b]) {
+                continue;
             }
+            let traits = shared_traits_with(graph[customer_a], graph[customer_b], similarity); // compare the pair once for both the check and the weight
+            if weighted_score(&traits, attribute_weights) >= threshold { // same check as determine_neighbor_weighted, with the given bucket specs
+                let shared_count = traits.len() as u32;
+                graph.add_edge(customer_a, customer_b, shared_count); // Add an edge weighted by the number of shared characteristics
+            }
+        }
+        let steps = (i + 1) * PROGRESS_STEPS / node_indices.len(); // number of 10% steps completed so far
+        if steps > reported_steps {
+            reported_steps = steps;
+            progress(i + 1, node_indices.len());
         }
     }
 
     graph// Return the constructed graph
 }
 
-// Function to determine if two customers (=nodes) are neighbors (base on wehther the number of share characteristics is above threshold)
-//helper function used in construct_graph
-pub fn determine_neighbor(customer_a: &Customer, customer_b: &Customer) -> bool {
-    let mut shared_characteristics_count = 0; // Initialize a count for number of shared characteristics between two nodes 
-
-    let is_similar = |value_a: &str, value_b: &str| value_a == value_b; // Closure to check if two values are similar
-    let in_same_group = |value_a: &str, value_b: &str, groups: &[&str]| { // Closure to check if two values are in the same group
-        groups.iter().any(|&group| value_a == group && value_b == group)
-    };
-    if in_same_group(&customer_a.age.to_string(), &customer_b.age.to_string(), &vec!["20-30", "30-40", "40-50", ">50"]) {// Check and increment count for shared characteristics for months with the bank
-        shared_characteristics_count += 1;
+// Function to construct the graph like construct_graph, but owning the customers instead of borrowing them,
+// so the graph can be returned from functions and passed around without keeping the customers vector alive
+// node i is still customers[i], and the edges carry the same shared-characteristic counts
+pub fn construct_owned_graph(customers: Vec<Customer>) -> Graph<Customer, u32, Undirected> {
+    let edges: Vec<(NodeIndex, NodeIndex, u32)> = construct_graph(&customers).edge_references()
+        .map(|edge| (edge.source(), edge.target(), *edge.weight()))
+        .collect();
+    let mut graph = Graph::with_capacity(customers.len(), edges.len());
+    for customer in customers {
+        graph.add_node(customer);
     }
-    if is_similar(&customer_a.one_hot_encoding.education_level, &customer_b.one_hot_encoding.education_level) { // Check and increment count for shared characteristics for education level
-        shared_characteristics_count += 1;
+    for (a, b, shared_count) in edges {
+        graph.add_edge(a, b, shared_count);
     }
-    if is_similar(&customer_a.one_hot_encoding.marital_status, &customer_b.one_hot_encoding.marital_status) { // Check and increment count for shared characteristics for marital status
-        shared_characteristics_count += 1;
+    graph
+}
+
+// Function to add one customer (or any GraphableEntity) to a graph built by construct_owned_graph, without rebuilding it
+// the new node is only compared with the existing nodes (O(n) instead of the O(n²) rebuild), with the same neighbor
+// check and edge weight as construct_graph, so adding customers one by one gives the same graph as building it at once.
+// The graph owns its nodes: a graph borrowing a customers vector couldn't be updated while that vector grows
+pub fn add_customer<T: GraphableEntity>(graph: &mut Graph<T, u32, Undirected>, entity: T) -> NodeIndex {
+    let new_node = graph.add_node(entity);
+    let new_edges: Vec<(NodeIndex, u32)> = graph.node_indices()
+        .filter(|&node| node != new_node)
+        .filter_map(|node| {
+            let shared = graph[node].shared_characteristics(&graph[new_node]);
+            (shared.len() >= NEIGHBOR_THRESHOLD as usize).then_some((node, shared.len() as u32))
+        })
+        .collect();
+    for (node, shared_count) in new_edges {
+        graph.add_edge(node, new_node, shared_count);
     }
-    if is_similar(&customer_a.one_hot_encoding.income_range, &customer_b.one_hot_encoding.income_range) { // Check and increment count for shared characteristics for income range
-        shared_characteristics_count += 1;
+    new_node
+}
+
+// Function to sparsify a graph into a k-nearest-neighbor graph: every node ranks its edges by shared-characteristic count
+// (ties go to the neighbor with the lowest index) and an edge is kept only if it is in the top k of both its ends,
+// so no node ends up with more than k neighbors. The nodes (and their indices) are unchanged
+pub fn keep_top_k_edges<N: Clone>(graph: &Graph<N, u32, Undirected>, k: usize) -> Graph<N, u32, Undirected> {
+    let top_edges: Vec<HashSet<petgraph::graph::EdgeIndex>> = graph.node_indices().map(|node| {
+        let mut edges: Vec<(u32, NodeIndex, petgraph::graph::EdgeIndex)> = graph.edges(node)
+            .map(|edge| (*edge.weight(), if edge.source() == node { edge.target() } else { edge.source() }, edge.id()))
+            .collect();
+        edges.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        edges.into_iter().take(k).map(|(_, _, edge)| edge).collect()
+    }).collect();
+    graph.filter_map(
+        |_, node| Some(node.clone()),
+        |edge, &weight| {
+            let (a, b) = graph.edge_endpoints(edge)?;
+            (top_edges[a.index()].contains(&edge) && top_edges[b.index()].contains(&edge)).then_some(weight)
+        },
+    )
+}
+
+// Function to construct the graph without edge weights (every connection is treated as equally strong)
+// kept for code that only cares about whether two customers are connected
+pub fn construct_unweighted_graph(customers: &[Customer]) -> Graph<&Customer, (), Undirected> {
+    construct_graph(customers).map(|_, &customer| customer, |_, _| ())
+}
+
+// Function to construct a directed graph from customers, for similarity notions that aren't symmetric
+// an edge a -> b is added for every ordered pair where predicate(a, b) holds, so a pair can be connected in one
+// direction only (e.g. "a's credit limit bucket contains b's"); in-degree and out-degree then measure different things
+pub fn construct_directed_graph(
+    customers: &[Customer], // Slice of Customer representing all customers
+    predicate: impl Fn(&Customer, &Customer) -> bool, // whether to add the edge from the first customer to the second
+) -> Graph<&Customer, (), Directed> {
+    let mut graph = Graph::new(); // Create a directed graph
+    let node_indices: Vec<NodeIndex> = customers.iter().map(|customer| graph.add_node(customer)).collect();
+
+    // unlike the undirected graph, both (a, b) and (b, a) have to be checked
+    for &customer_a in &node_indices {
+        for &customer_b in &node_indices {
+            if customer_a != customer_b && predicate(graph[customer_a], graph[customer_b]) {
+                graph.add_edge(customer_a, customer_b, ());
+            }
+        }
     }
-    if is_similar(&customer_a.one_hot_encoding.card_type, &customer_b.one_hot_encoding.card_type) {// Check and increment count for shared characteristics for card type
-        shared_characteristics_count += 1;
+
+    graph// Return the constructed graph
+}
+
+// Minimum number of shared characteristics for two customers to be connected; adjust the threshold as needed
+pub const NEIGHBOR_THRESHOLD: u32 = 2;
+
+// Names of the attributes compared between customers, as used for attribute weights and in the shared characteristic labels
+pub const ATTRIBUTE_NAMES: [&str; 12] = [
+    "Age",
+    "Education Level",
+    "Marital Status",
+    "Income Range",
+    "Card Type",
+    "Mon W Bank",
+    "Number of Products Purchased",
+    "Month inactive",
+    "Number of Contacts from Bank (past 12 months)",
+    "Total Dollar Amount of Transaction via Card",
+    "Total Number of Transactions via Card",
+    "Average Card Utilization Ratio",
+];
+
+// Function to map every node of a graph built from customers to the index of its customer in the slice
+// nodes are matched by the address of the customer they refer to, not by position, so the map stays right when nodes
+// were added in another order or removed (petgraph moves the last node into the freed index); nodes referring to
+// a customer outside the slice are left out
+pub fn node_customer_indices<E>(graph: &Graph<&Customer, E, Undirected>, customers: &[Customer]) -> HashMap<NodeIndex, usize> {
+    let positions: HashMap<*const Customer, usize> = customers.iter().enumerate().map(|(index, customer)| (customer as *const Customer, index)).collect();
+    graph.node_indices()
+        .filter_map(|node| positions.get(&(graph[node] as *const Customer)).map(|&index| (node, index)))
+        .collect()
+}
+
+// Function to get the customer of a node through the map built by node_customer_indices (None for a node that isn't in it)
+pub fn customer_for_node<'a>(node_customers: &HashMap<NodeIndex, usize>, customers: &'a [Customer], node: NodeIndex) -> Option<&'a Customer> {
+    node_customers.get(&node).and_then(|&index| customers.get(index))
+}
+
+// Function to build the default attribute weights: every attribute weighs 1.0 (same as plain counting)
+pub fn default_attribute_weights() -> HashMap<String, f64> {
+    ATTRIBUTE_NAMES.iter().map(|name| (name.to_string(), 1.0)).collect()
+}
+
+// Function to determine if two customers (=nodes) are neighbors (base on wehther the number of share characteristics is above threshold)
+//helper function used in construct_graph
+pub fn determine_neighbor<T: GraphableEntity>(customer_a: &T, customer_b: &T) -> bool {
+    customer_a.shared_characteristics(customer_b).len() >= NEIGHBOR_THRESHOLD as usize
+}
+
+// Function to determine if two customers are neighbors when shared attributes are weighted
+pub fn determine_neighbor_weighted(customer_a: &Customer, customer_b: &Customer, attribute_weights: &HashMap<String, f64>, threshold: f64) -> bool {
+    shared_characteristics_score(customer_a, customer_b, attribute_weights) >= threshold
+}
+
+// Function to count the number of shared characteristics between two customers (used as the edge weight)
+pub fn count_shared_characteristics(customer_a: &Customer, customer_b: &Customer) -> u32 {
+    shared_traits(customer_a, customer_b).len() as u32
+}
+
+// Function to compute the weighted sum of the characteristics two customers share, using the default bucket edges
+// each shared attribute contributes its weight from attribute_weights (keyed by the names in ATTRIBUTE_NAMES), or 1.0 if it has none
+pub fn shared_characteristics_score(customer_a: &Customer, customer_b: &Customer, attribute_weights: &HashMap<String, f64>) -> f64 {
+    shared_characteristics_score_with(customer_a, customer_b, attribute_weights, &SimilarityConfig::default())
+}
+
+// Function to compute the weighted sum of the characteristics two customers share, putting the numeric fields into the buckets of the given specs
+pub fn shared_characteristics_score_with(customer_a: &Customer, customer_b: &Customer, attribute_weights: &HashMap<String, f64>, similarity: &SimilarityConfig) -> f64 {
+    weighted_score(&shared_traits_with(customer_a, customer_b, similarity), attribute_weights)
+}
+
+// Function to compute a single similarity between two customers: the fraction of the attributes (ATTRIBUTE_NAMES) they share,
+// from 0.0 (nothing in common) to 1.0 (every attribute shared), e.g. for nearest-neighbor rankings or a threshold
+pub fn similarity_score(customer_a: &Customer, customer_b: &Customer) -> f64 {
+    similarity_score_weighted(customer_a, customer_b, &default_attribute_weights())
+}
+
+// Function to compute the similarity like similarity_score, each attribute counting with its weight (1.0 if it has none):
+// the weighted score of the shared attributes divided by the total weight of all attributes (0.0 if that total is 0)
+pub fn similarity_score_weighted(customer_a: &Customer, customer_b: &Customer, attribute_weights: &HashMap<String, f64>) -> f64 {
+    let total_weight: f64 = ATTRIBUTE_NAMES.iter().map(|attribute| attribute_weights.get(*attribute).copied().unwrap_or(1.0)).sum();
+    if total_weight <= 0.0 {
+        return 0.0;
     }
-    if in_same_group(&customer_a.mon_w_bank.to_string(), &customer_b.mon_w_bank.to_string(), &vec!["20-30", "30-40", "40-50", ">50"]) {// Check and increment count for shared characteristics for months with the bank
-        shared_characteristics_count += 1;
+    shared_characteristics_score(customer_a, customer_b, attribute_weights) / total_weight
+}
+
+// Function to add up the weights of a list of shared traits; the attribute of a trait is its label before the ":"
+fn weighted_score(traits: &[String], attribute_weights: &HashMap<String, f64>) -> f64 {
+    traits.iter()
+        .map(|label| label.split(':').next().unwrap_or(label))
+        .map(|attribute| attribute_weights.get(attribute).copied().unwrap_or(1.0))
+        .sum()
+}
+
+// Function to calculate centrality for each node in the graph
+// uses Wasserman-Faust closeness: only finite distances are summed and the result is scaled by the fraction of nodes
+// the node can reach, i.e. (r / (n - 1)) * (r / sum of distances to the r reachable nodes).
+// On a connected graph this equals the usual normalized closeness; an isolated node gets 0 instead of NaN/infinity
+// and a node that only reaches a tiny island is penalized for it rather than looking highly central.
+// Graphs of up to FLOYD_WARSHALL_MAX_NODES nodes get all their distances from one Floyd-Warshall call, larger ones
+// run one Dijkstra per source; with the `rayon` feature those sources are processed in parallel (see calculate_centrality_parallel)
+pub fn calculate_centrality<E: Sync>(graph: &Graph<&Customer, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    calculate_centrality_with(graph, FLOYD_WARSHALL_MAX_NODES)
+}
+
+// Largest graph (in nodes) for which calculate_centrality uses Floyd-Warshall instead of one Dijkstra per source.
+// Floyd-Warshall is O(V^3) whatever the number of edges, repeated Dijkstra O(V * E log V). On the nearly complete
+// graphs of the first BankChurners rows Floyd-Warshall was about 6x faster at 200 to 500 nodes (0.2s vs 1.4s at 500),
+// but on sparse graphs (high neighbor threshold) Dijkstra wins, and Floyd-Warshall keeps a V^2 distance map in memory,
+// so it is only used up to 500 nodes
+pub const FLOYD_WARSHALL_MAX_NODES: usize = 500;
+
+// Function to calculate closeness centrality, using Floyd-Warshall when the graph has at most floyd_warshall_max_nodes nodes
+pub fn calculate_centrality_with<E: Sync>(graph: &Graph<&Customer, E, Undirected>, floyd_warshall_max_nodes: usize) -> HashMap<NodeIndex, f64> {
+    if graph.node_count() <= floyd_warshall_max_nodes {
+        return calculate_centrality_floyd_warshall(graph);
     }
-    
-    if is_similar(&customer_a.num_product_purchased.to_string(), &customer_b.num_product_purchased.to_string()) {
-        shared_characteristics_count += 1;
+    #[cfg(feature = "rayon")]
+    let centrality = calculate_centrality_parallel(graph);
+    #[cfg(not(feature = "rayon"))]
+    let centrality = calculate_centrality_serial(graph);
+    centrality
+}
+
+// Function to calculate closeness centrality one source node at a time
+pub fn calculate_centrality_serial<E>(graph: &Graph<&Customer, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    let centrality: HashMap<_, _> = graph.node_indices()
+        .map(|node| (node, closeness_of(graph, node)))
+        .collect();
+
+    centrality // Return the HashMap of node indices and their centrality values
+}
+
+// Function to calculate closeness centrality with the source nodes spread over rayon's thread pool
+// every source runs its own Dijkstra on the shared, read-only graph, so the results equal the serial version
+#[cfg(feature = "rayon")]
+pub fn calculate_centrality_parallel<E: Sync>(graph: &Graph<&Customer, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    use rayon::prelude::*;
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    nodes.into_par_iter()
+        .map(|node| (node, closeness_of(graph, node)))
+        .collect()
+}
+
+// Function to calculate closeness centrality from the full distance matrix, computed with a single Floyd-Warshall call
+// gives the same values as the Dijkstra versions; meant for small graphs (see FLOYD_WARSHALL_MAX_NODES)
+pub fn calculate_centrality_floyd_warshall<E>(graph: &Graph<&Customer, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    // unreachable pairs are left at usize::MAX
+    let distances = floyd_warshall(graph, |_edge| 1usize).expect("unit edge costs can't form a negative cycle");
+    graph.node_indices().map(|node| {
+        let to_others = graph.node_indices()
+            .filter(|&other| other != node)
+            .filter_map(|other| distances.get(&(node, other)))
+            .filter(|&&distance| distance != usize::MAX)
+            .map(|&distance| distance as f64);
+        (node, wasserman_faust_closeness(to_others, n))
+    }).collect()
+}
+
+// Function to calculate weighted closeness centrality: each edge is 1.0 / weight long, so customers linked by many
+// shared characteristics are closer than customers linked by a few (edges with a weight of 0 or less can't be crossed).
+// Otherwise the same Wasserman-Faust closeness as calculate_centrality, which treats every edge as 1.0 long
+pub fn calculate_weighted_centrality<E: EdgeWeight>(graph: &Graph<&Customer, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    let edge_cost = |edge: petgraph::graph::EdgeReference<E>| {
+        let weight = edge.weight().weight();
+        if weight > 0.0 { 1.0 / weight } else { f64::INFINITY }
+    };
+    graph.node_indices().map(|node| (node, closeness_with_costs(graph, node, edge_cost))).collect()
+}
+
+// Function to calculate the Wasserman-Faust closeness of one node among all the nodes of the graph
+fn closeness_of<E>(graph: &Graph<&Customer, E, Undirected>, node: NodeIndex) -> f64 {
+    closeness_with_costs(graph, node, |_edge| 1.0)
+}
+
+// Function to calculate the Wasserman-Faust closeness of one node, with the length of each edge given by edge_cost
+fn closeness_with_costs<E>(graph: &Graph<&Customer, E, Undirected>, node: NodeIndex, edge_cost: impl FnMut(petgraph::graph::EdgeReference<E>) -> f64) -> f64 {
+    let distance_map: HashMap<NodeIndex, f64> = dijkstra(graph, node, None, edge_cost); // one Dijkstra per source gives the distances to every reachable node
+    let to_others = distance_map.iter()
+        .filter(|(&target, distance)| target != node && distance.is_finite())
+        .map(|(_, &distance)| distance);
+    wasserman_faust_closeness(to_others, graph.node_count())
+}
+
+// Function to calculate the Wasserman-Faust closeness from a node's distances to the other nodes it can reach, out of n nodes
+fn wasserman_faust_closeness(distances: impl Iterator<Item = f64>, n: usize) -> f64 {
+    let mut reachable = 0; // number of other nodes that can be reached from this node
+    let mut distance_sum = 0.0;
+    for distance in distances {
+        reachable += 1;
+        distance_sum += distance;
     }
-    
-    if is_similar(&customer_a.mon_inactive.to_string(), &customer_b.mon_inactive.to_string()) {
-        shared_characteristics_count += 1;
+    if reachable == 0 {
+        0.0 // isolated node (or single-node graph)
+    } else {
+        (reachable as f64 / (n - 1) as f64) * (reachable as f64 / distance_sum)
     }
+}
+
+// Function to calculate harmonic centrality for each node: the sum of 1 / distance to every other node (edges are 1 long),
+// where an unreachable node adds 0 instead of an infinite distance, so it stays finite and comparable on disconnected graphs.
+// An isolated node scores 0; the result can be passed to identify_high_centrality_nodes like closeness
+pub fn calculate_harmonic_centrality<N, E>(graph: &Graph<N, E, Undirected>) -> HashMap<NodeIndex, f64> {
+    harmonic_centrality_from(&distance_matrix(graph))
+}
+
+// Function to calculate harmonic centrality like calculate_harmonic_centrality, from an already computed distance matrix
+pub fn harmonic_centrality_from(distances: &DistanceMatrix) -> HashMap<NodeIndex, f64> {
+    distances.iter().map(|(&node, to_targets)| {
+        let harmonic = to_targets.iter()
+            .filter(|(&target, _)| target != node)
+            .map(|(_, &distance)| 1.0 / distance)
+            .sum();
+        (node, harmonic)
+    }).collect()
+}
+
+// all-pairs shortest path lengths: source -> target -> distance (see distance_matrix)
+pub type DistanceMatrix = HashMap<NodeIndex, HashMap<NodeIndex, f64>>;
 
-    if is_similar(&customer_a.num_contact.to_string(), &customer_b.num_contact.to_string()) {
-        shared_characteristics_count += 1;
+// Function to compute the shortest path length (in edges) between every pair of nodes, with one Dijkstra per source,
+// so the metrics built on distances (harmonic centrality, average path length, diameter) can share one traversal.
+// Every node is at distance 0 of itself; unreachable targets are left out. Holds V^2 entries on a connected graph,
+// which is why the closeness centrality keeps running its own Dijkstra per source instead
+pub fn distance_matrix<N, E>(graph: &Graph<N, E, Undirected>) -> DistanceMatrix {
+    graph.node_indices().map(|node| (node, dijkstra(graph, node, None, |_edge| 1.0))).collect()
+}
+
+// Function to calculate PageRank centrality for each node using power iteration over the undirected adjacency
+// every edge can be followed both ways; the rank of nodes without neighbors is spread evenly over all nodes.
+// Scores are normalized to sum to 1, so the result can be passed to identify_high_centrality_nodes like closeness
+pub fn calculate_pagerank<N, E>(graph: &Graph<N, E, Undirected>, damping: f64, iterations: usize) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    if n == 0 {
+        return HashMap::new();
     }
-    if in_same_group(&customer_a.transactions_amount.to_string(), &customer_b.transactions_amount.to_string(), &vec!["500<", "500-1000", "1000-1500","1500-2000",">2000"]) {
-        shared_characteristics_count += 1;
+    let degrees: Vec<usize> = graph.node_indices().map(|node| graph.neighbors(node).count()).collect();
+    let mut ranks = vec![1.0 / n as f64; n]; // start from a uniform distribution
+
+    for _ in 0..iterations {
+        let dangling_rank: f64 = graph.node_indices().filter(|node| degrees[node.index()] == 0).map(|node| ranks[node.index()]).sum();
+        let base = (1.0 - damping) / n as f64 + damping * dangling_rank / n as f64;
+        let mut new_ranks = vec![base; n];
+        for node in graph.node_indices() {
+            for neighbor in graph.neighbors(node) { // node passes an equal share of its rank to each neighbor
+                new_ranks[neighbor.index()] += damping * ranks[node.index()] / degrees[node.index()] as f64;
+            }
+        }
+        let total: f64 = new_ranks.iter().sum();
+        ranks = new_ranks.into_iter().map(|rank| rank / total).collect(); // keep the scores summing to 1
     }
-    if in_same_group(&customer_a.num_transctions.to_string(), &customer_b.num_transctions.to_string(), &vec!["<10","10-20","20-30","30-40",">40"]) {
-        shared_characteristics_count += 1;
+
+    graph.node_indices().map(|node| (node, ranks[node.index()])).collect()
+}
+
+// Function to calculate eigenvector centrality for each node using power iteration on the adjacency matrix
+// each step computes x + A x (the shift by x keeps the iteration from oscillating on bipartite graphs without changing
+// the eigenvectors) and L2-normalizes it; stops once the L2 change is below `tolerance` or after `max_iter` steps.
+// On a disconnected graph the scores concentrate on the component with the largest eigenvalue: nodes in smaller or
+// sparser components decay toward 0 (isolated nodes as well), and if two components have nearly equal eigenvalues
+// convergence is slow, so the result after `max_iter` steps may still depend on the starting vector
+pub fn calculate_eigenvector_centrality<N, E>(graph: &Graph<N, E, Undirected>, max_iter: usize, tolerance: f64) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    if n == 0 {
+        return HashMap::new();
     }
-    
-    if in_same_group(&customer_a.avg_card_utilize.to_string(), &customer_b.avg_card_utilize.to_string(), &vec!["<0.100","0.100-0.200","0.200-0.300","0.300-0.400",">0.400"]) {
-        shared_characteristics_count += 1;
+    let mut scores = vec![1.0 / (n as f64).sqrt(); n]; // start from a uniform unit vector
+
+    for _ in 0..max_iter {
+        let mut new_scores = scores.clone();
+        for node in graph.node_indices() {
+            for neighbor in graph.neighbors(node) {
+                new_scores[node.index()] += scores[neighbor.index()];
+            }
+        }
+        let norm = new_scores.iter().map(|score| score * score).sum::<f64>().sqrt();
+        new_scores.iter_mut().for_each(|score| *score /= norm);
+        let change = new_scores.iter().zip(&scores).map(|(new, old)| (new - old) * (new - old)).sum::<f64>().sqrt();
+        scores = new_scores;
+        if change < tolerance { // converged
+            break;
+        }
     }
-    // Adjust the threshold as needed; if the number of shared characteristic is above this threshold, we connect the two customers
-    shared_characteristics_count >= 2
+
+    graph.node_indices().map(|node| (node, scores[node.index()])).collect()
 }
 
-// Function to calculate centrality for each node in the graph
+// statistic of the centrality values that the threshold factor multiplies to get the high centrality cut point
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ThresholdMode {
+    #[default]
+    Mean, // threshold_factor * mean centrality (the original cut point)
+    Median, // threshold_factor * median centrality, robust to a few outliers on a skewed distribution
+    Percentile(f64), // threshold_factor * the given percentile (0-100, linearly interpolated) of the centrality values
+}
 
-pub fn calculate_centrality(graph: &Graph<&Customer, (), Undirected>, customers: &[Customer]) -> HashMap<NodeIndex, f64> {
-    let petgraph_indices: Vec<NodeIndex> = customers.iter().enumerate().map(|(i, _)| NodeIndex::new(i)).collect(); // Create node indices for customers
-    let mut all_distances: HashMap<NodeIndex, HashMap<NodeIndex, f64>> = HashMap::new();// HashMap to store distances between nodes
-    for node in &petgraph_indices {
-        let mut distances: HashMap<NodeIndex, f64> = HashMap::new();
-        for nodew in &petgraph_indices {
-            if node != nodew {
-                if let Some(distance) = all_distances.get(nodew).and_then(|map| map.get(node)) {
-                    distances.insert(*nodew, *distance);
-                } else {
-                    let distance_map = dijkstra(graph, *node, Some(*nodew), |_edge| 1.0);
-                    let distance = *distance_map.get(&nodew).unwrap_or(&f64::INFINITY);
-                    distances.insert(*nodew, distance);
-                }
-            }
+impl ThresholdMode {
+    // Function to compute the statistic of the centrality values this mode uses (NaN if there are none)
+    pub fn reference_value(&self, values: &[f64]) -> f64 {
+        match self {
+            ThresholdMode::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            ThresholdMode::Median => percentile(values, 50.0),
+            ThresholdMode::Percentile(percent) => percentile(values, *percent),
         }
-        all_distances.insert(*node, distances);
     }
-     //calculate centrality of each nodes using normalized closeness centrality
-     let centrality: HashMap<_, _> = petgraph_indices.iter().map(|&node| {
-        let distance_sum: f64 = all_distances[&node].values().copied().sum();
-        let centrality = (customers.len() - 1) as f64 / distance_sum;
-        (node, centrality)
-    }).collect();
+}
 
-    centrality // Return the HashMap of node indices and their centrality values
+// Function to compute the p-th percentile (0-100) of values by linear interpolation between the closest ranks (NaN if empty)
+pub fn percentile(values: &[f64], percent: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = percent.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
 }
 
 // Function to identify nodes with high centrality
 pub fn identify_high_centrality_nodes(centrality: &HashMap<NodeIndex, f64>, threshold_factor: f64) -> Vec<NodeIndex> {
-    let threshold = threshold_factor * centrality.values().sum::<f64>() / centrality.len() as f64; // Adjusted threshold
+    identify_high_centrality_nodes_with(centrality, threshold_factor, ThresholdMode::Mean)
+}
+
+// Function to get the k nodes with the highest centrality, most central first (ties go to the lowest node index)
+// an alternative to thresholding with a factor: "the 10 most central customers". All nodes if there are fewer than k
+pub fn top_k_central_nodes(centrality: &HashMap<NodeIndex, f64>, k: usize) -> Vec<NodeIndex> {
+    let nodes: Vec<NodeIndex> = centrality.keys().copied().collect();
+    top_k_by_centrality(&nodes, centrality, k)
+}
+
+// Function to identify nodes with high centrality: those above threshold_factor * the statistic of the given mode
+pub fn identify_high_centrality_nodes_with(centrality: &HashMap<NodeIndex, f64>, threshold_factor: f64, mode: ThresholdMode) -> Vec<NodeIndex> {
+    let values: Vec<f64> = centrality.values().copied().collect();
+    let threshold = threshold_factor * mode.reference_value(&values); // Adjusted threshold
     centrality.iter().filter_map(|(&node, &centrality)| {
         if centrality > threshold {// Return the node index if its centrality is above the threshold
             Some(node)
@@ -115,3 +538,1531 @@ pub fn identify_high_centrality_nodes(centrality: &HashMap<NodeIndex, f64>, thre
         }
     }).collect()// Return a vector of node indices with high centrality
 }
+
+// Fractions of a group's nodes below and above which the high centrality selection is flagged (see threshold_warning)
+pub const MIN_SELECTED_FRACTION: f64 = 0.01;
+pub const MAX_SELECTED_FRACTION: f64 = 0.9;
+
+// Function to check whether the threshold factor selected a meaningful share of the nodes: returns a warning suggesting
+// how to adjust the factor when it selected less than MIN_SELECTED_FRACTION or more than MAX_SELECTED_FRACTION of them
+// (None for an empty group). On a nearly uniform centrality distribution a factor like 1.1 easily selects nothing
+pub fn threshold_warning(selected: usize, total: usize, threshold_factor: f64) -> Option<String> {
+    if total == 0 {
+        return None;
+    }
+    let fraction = selected as f64 / total as f64;
+    let advice = if fraction < MIN_SELECTED_FRACTION {
+        "lower the threshold factor to select more"
+    } else if fraction > MAX_SELECTED_FRACTION {
+        "raise the threshold factor to select fewer"
+    } else {
+        return None;
+    };
+    Some(format!("threshold factor {} selected {} of {} nodes ({:.1}%); {}", threshold_factor, selected, total, fraction * 100.0, advice))
+}
+
+// struct holding descriptive statistics of a centrality distribution (see centrality_stats), to help pick a threshold factor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CentralityStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64, // population standard deviation
+}
+
+// Function to compute the min, max, mean, median and standard deviation of the centrality values (all NaN if there are none)
+pub fn centrality_stats(centrality: &HashMap<NodeIndex, f64>) -> CentralityStats {
+    let values: Vec<f64> = centrality.values().copied().collect();
+    if values.is_empty() {
+        return CentralityStats { min: f64::NAN, max: f64::NAN, mean: f64::NAN, median: f64::NAN, std_dev: f64::NAN };
+    }
+    let mean = ThresholdMode::Mean.reference_value(&values);
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    CentralityStats {
+        min: values.iter().copied().fold(f64::INFINITY, f64::min),
+        max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        mean,
+        median: percentile(&values, 50.0),
+        std_dev: variance.sqrt(),
+    }
+}
+
+// struct holding the basic size of a graph (see graph_stats)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphStats {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub density: f64, // fraction of the possible edges that exist, 2E / (N(N-1)); 0 for graphs with fewer than 2 nodes
+}
+
+// Function to keep the k nodes with the highest centrality, sorted by centrality descending (ties go to the lowest node index)
+// nodes without a centrality value are dropped
+pub fn top_k_by_centrality(nodes: &[NodeIndex], centrality: &HashMap<NodeIndex, f64>, k: usize) -> Vec<NodeIndex> {
+    let mut ranked: Vec<(NodeIndex, f64)> = nodes.iter().filter_map(|node| centrality.get(node).map(|&value| (*node, value))).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.into_iter().take(k).map(|(node, _)| node).collect()
+}
+
+// order in which the high centrality nodes are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeOrder {
+    #[default]
+    Index, // ascending node index (the order of the customers in the input)
+    Centrality, // descending centrality, so the most important customers come first (ties go to the lowest node index)
+}
+
+impl NodeOrder {
+    // Function to sort nodes in this order; with Centrality, nodes without a centrality value are dropped (see top_k_by_centrality)
+    pub fn sort(&self, nodes: &[NodeIndex], centrality: &HashMap<NodeIndex, f64>) -> Vec<NodeIndex> {
+        match self {
+            NodeOrder::Index => {
+                let mut sorted = nodes.to_vec();
+                sorted.sort();
+                sorted
+            }
+            NodeOrder::Centrality => top_k_by_centrality(nodes, centrality, nodes.len()),
+        }
+    }
+}
+
+// Function to compute, for every attribute in ATTRIBUTE_NAMES, the fraction of edges whose two customers share it (0.0 to 1.0)
+// shows which attributes drive the edges; the customers are compared with the given bucket specs (as when the graph was built)
+// and read from the nodes themselves, so the rates stay right on a filtered or reordered graph. Empty if there are no edges
+pub fn edge_attribute_rates<E>(graph: &Graph<&Customer, E, Undirected>, similarity: &SimilarityConfig) -> HashMap<String, f64> {
+    let mut counts: HashMap<String, usize> = ATTRIBUTE_NAMES.iter().map(|name| (name.to_string(), 0)).collect();
+    for edge in graph.edge_references() {
+        for characteristic in shared_traits_with(graph[edge.source()], graph[edge.target()], similarity) {
+            let attribute = characteristic.split(':').next().unwrap_or_default().trim(); // the label before ":"
+            *counts.entry(attribute.to_string()).or_insert(0) += 1;
+        }
+    }
+    let edge_count = graph.edge_count();
+    if edge_count == 0 {
+        return HashMap::new();
+    }
+    counts.into_iter().map(|(attribute, count)| (attribute, count as f64 / edge_count as f64)).collect()
+}
+
+// Function to compute the node count, edge count and density of the graph
+// a density close to 1.0 means nearly every pair of customers is connected, i.e. the neighbor threshold is too permissive
+pub fn graph_stats<N, E>(graph: &Graph<N, E, Undirected>) -> GraphStats {
+    let node_count = graph.node_count();
+    let edge_count = graph.edge_count();
+    let density = if node_count < 2 { 0.0 } else { 2.0 * edge_count as f64 / (node_count * (node_count - 1)) as f64 };
+    GraphStats { node_count, edge_count, density }
+}
+
+// Function to find the connected components of the graph using a BFS flood fill
+// returns the nodes of each component (sorted by index), largest component first
+pub fn connected_components<N, E>(graph: &Graph<N, E, Undirected>) -> Vec<Vec<NodeIndex>> {
+    let mut visited = vec![false; graph.node_count()];
+    let mut components: Vec<Vec<NodeIndex>> = Vec::new();
+
+    for start in graph.node_indices() {
+        if visited[start.index()] { // node already belongs to a component
+            continue;
+        }
+        visited[start.index()] = true;
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for neighbor in graph.neighbors(node) {
+                if !visited[neighbor.index()] {
+                    visited[neighbor.index()] = true;
+                    component.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    components.sort_by_key(|component| std::cmp::Reverse(component.len())); // largest component first (stable, so ties keep discovery order)
+    components
+}
+
+// Function to compute the average shortest path length (in edges) between the pairs of customers of the largest connected component
+// a disconnected graph is restricted to its giant component so unreachable pairs don't make it infinite; 0 if it has a single node
+pub fn average_path_length<N, E>(graph: &Graph<N, E, Undirected>) -> f64 {
+    average_path_length_from(graph, &distance_matrix(graph))
+}
+
+// Function to compute the average shortest path length like average_path_length, from the graph's distance matrix
+pub fn average_path_length_from<N, E>(graph: &Graph<N, E, Undirected>, distances: &DistanceMatrix) -> f64 {
+    let (distance_sum, pairs, _) = giant_component_distances(graph, distances);
+    if pairs == 0 { 0.0 } else { distance_sum / pairs as f64 }
+}
+
+// Function to compute the diameter (longest shortest path, in edges) of the largest connected component; 0 if it has a single node
+pub fn diameter<N, E>(graph: &Graph<N, E, Undirected>) -> usize {
+    diameter_from(graph, &distance_matrix(graph))
+}
+
+// Function to compute the diameter like diameter, from the graph's distance matrix
+pub fn diameter_from<N, E>(graph: &Graph<N, E, Undirected>, distances: &DistanceMatrix) -> usize {
+    giant_component_distances(graph, distances).2
+}
+
+// Function to go through the distances from every node of the largest connected component (see distance_matrix)
+// returns the sum of the distances, the number of ordered pairs and the longest distance
+fn giant_component_distances<N, E>(graph: &Graph<N, E, Undirected>, distances: &DistanceMatrix) -> (f64, usize, usize) {
+    let components = connected_components(graph);
+    let Some(component) = components.first() else {
+        return (0.0, 0, 0); // empty graph
+    };
+    let (mut distance_sum, mut pairs, mut longest) = (0.0, 0, 0.0_f64);
+    for node in component {
+        let Some(to_targets) = distances.get(node) else {
+            continue;
+        };
+        for (&target, &distance) in to_targets { // only the nodes of the component are reachable
+            if target != *node {
+                distance_sum += distance;
+                pairs += 1;
+                longest = longest.max(distance);
+            }
+        }
+    }
+    (distance_sum, pairs, longest as usize)
+}
+
+// Function to list the nodes of the connected components with fewer than min_size nodes (in index order)
+// tiny components (isolated customers, pairs, triples) are usually noise rather than meaningful structure
+pub fn nodes_in_small_components<N, E>(graph: &Graph<N, E, Undirected>, min_size: usize) -> Vec<NodeIndex> {
+    let mut nodes: Vec<NodeIndex> = connected_components(graph).into_iter()
+        .filter(|component| component.len() < min_size)
+        .flatten()
+        .collect();
+    nodes.sort();
+    nodes
+}
+
+// Function to list the nodes without any edge (degree 0), in index order
+// such customers are connected to no one, so they can't be central and the centrality report silently leaves them out
+pub fn isolated_nodes<N, E>(graph: &Graph<N, E, Undirected>) -> Vec<NodeIndex> {
+    graph.node_indices().filter(|&node| graph.edges(node).next().is_none()).collect()
+}
+
+// Function to count how many nodes have each degree (number of edges), keyed by degree in ascending order
+// if most nodes have a degree close to the number of customers, the neighbor threshold is too loose
+pub fn degree_histogram<N, E>(graph: &Graph<N, E, Undirected>) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for node in graph.node_indices() {
+        *histogram.entry(graph.edges(node).count()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+// Function to calculate the local clustering coefficient of a node:
+// the fraction of pairs of its neighbors that are themselves connected (0 for nodes with fewer than 2 neighbors)
+pub fn local_clustering_coefficient<N, E>(graph: &Graph<N, E, Undirected>, node: NodeIndex) -> f64 {
+    clustering_from_adjacency(&sorted_adjacency(graph), node.index())
+}
+
+// Function to list the neighbors of every node, sorted and without duplicates (parallel edges) or self-loops
+fn sorted_adjacency<N, E>(graph: &Graph<N, E, Undirected>) -> Vec<Vec<usize>> {
+    graph.node_indices().map(|node| {
+        let mut neighbors: Vec<usize> = graph.neighbors(node).filter(|&neighbor| neighbor != node).map(|neighbor| neighbor.index()).collect();
+        neighbors.sort_unstable();
+        neighbors.dedup(); // count each neighbor once even if there are parallel edges
+        neighbors
+    }).collect()
+}
+
+// Function to calculate the local clustering coefficient of a node from the sorted neighbor lists
+// the connected pairs are counted by intersecting the node's neighbors with each neighbor's neighbors (a sorted merge),
+// which stays fast on dense graphs where checking every pair with contains_edge doesn't
+fn clustering_from_adjacency(adjacency: &[Vec<usize>], node: usize) -> f64 {
+    let neighbors = &adjacency[node];
+    let k = neighbors.len();
+    if k < 2 {
+        return 0.0;
+    }
+    let common_count = |a: &[usize], b: &[usize]| {
+        let (mut i, mut j, mut count) = (0, 0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => { count += 1; i += 1; j += 1; }
+            }
+        }
+        count
+    };
+    // every connected pair of neighbors is found from both of its ends
+    let connected_pairs: usize = neighbors.iter().map(|&neighbor| common_count(neighbors, &adjacency[neighbor])).sum::<usize>() / 2;
+    connected_pairs as f64 / (k * (k - 1) / 2) as f64
+}
+
+// Function to calculate the average clustering coefficient over all nodes of the graph
+pub fn average_clustering_coefficient<N, E>(graph: &Graph<N, E, Undirected>) -> f64 {
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    average_clustering_coefficient_of(graph, &nodes)
+}
+
+// Function to calculate the average clustering coefficient over a subset of nodes (e.g. one churn group)
+pub fn average_clustering_coefficient_of<N, E>(graph: &Graph<N, E, Undirected>, nodes: &[NodeIndex]) -> f64 {
+    if nodes.is_empty() {
+        return 0.0;
+    }
+    let adjacency = sorted_adjacency(graph); // built once for all the nodes
+    nodes.iter().map(|&node| clustering_from_adjacency(&adjacency, node.index())).sum::<f64>() / nodes.len() as f64
+}
+
+// trait for edge weights that can be used as a numeric strength of a connection
+// unweighted edges count as 1.0, shared-characteristic counts as their value
+pub trait EdgeWeight {
+    fn weight(&self) -> f64;
+}
+
+impl EdgeWeight for () {
+    fn weight(&self) -> f64 {
+        1.0
+    }
+}
+
+impl EdgeWeight for u32 {
+    fn weight(&self) -> f64 {
+        *self as f64
+    }
+}
+
+impl EdgeWeight for f64 {
+    fn weight(&self) -> f64 {
+        *self
+    }
+}
+
+// Function to detect communities with the Louvain method (greedy modularity optimization)
+// phase 1 moves single nodes to the neighboring community with the largest modularity gain until nothing moves,
+// phase 2 merges each community into one node; both are repeated until no node changes community.
+// Edge weights are used (see EdgeWeight), so on the construct_graph output stronger similarity pulls customers together.
+// Returns a community id per node; ids are numbered 0, 1, 2... in order of their lowest node index
+pub fn detect_communities<N, E: EdgeWeight>(graph: &Graph<N, E, Undirected>) -> HashMap<NodeIndex, usize> {
+    // weighted adjacency of the current (super)nodes; a self-loop holds the weight inside a merged community
+    let mut adjacency: Vec<HashMap<usize, f64>> = vec![HashMap::new(); graph.node_count()];
+    for edge in graph.edge_references() {
+        let (a, b) = (edge.source().index(), edge.target().index());
+        *adjacency[a].entry(b).or_insert(0.0) += edge.weight().weight();
+        if a != b {
+            *adjacency[b].entry(a).or_insert(0.0) += edge.weight().weight();
+        }
+    }
+    let mut node_to_supernode: Vec<usize> = (0..graph.node_count()).collect(); // which supernode each original node is in
+
+    loop {
+        let communities = louvain_local_moving(&adjacency);
+        let community_count = communities.iter().max().map_or(0, |&max| max + 1);
+        if community_count == adjacency.len() { // no node moved: the partition can't be improved further
+            break;
+        }
+        // phase 2: aggregate every community into a single supernode
+        let mut aggregated: Vec<HashMap<usize, f64>> = vec![HashMap::new(); community_count];
+        for (node, neighbors) in adjacency.iter().enumerate() {
+            for (&neighbor, &weight) in neighbors {
+                let (community_a, community_b) = (communities[node], communities[neighbor]);
+                // an edge inside a community is seen from both of its ends, so each side adds half of it
+                let weight = if node != neighbor && community_a == community_b { weight / 2.0 } else { weight };
+                *aggregated[community_a].entry(community_b).or_insert(0.0) += weight;
+            }
+        }
+        for supernode in node_to_supernode.iter_mut() {
+            *supernode = communities[*supernode];
+        }
+        adjacency = aggregated;
+    }
+
+    graph.node_indices().map(|node| (node, node_to_supernode[node.index()])).collect()
+}
+
+// Function to calculate the modularity of a partition of the graph: the fraction of the edge weight inside the groups
+// minus the fraction expected if the edges were placed at random with the same degrees (from about -0.5 to 1; higher means better separated groups).
+// partition maps each node to a group id (e.g. from detect_communities, or churn / not churn); a node missing from it is a group on its own.
+// Edge weights are used (see EdgeWeight); a graph without edges has modularity 0
+pub fn modularity<N, E: EdgeWeight>(graph: &Graph<N, E, Undirected>, partition: &HashMap<NodeIndex, usize>) -> f64 {
+    // group of a node: Ok(id) for nodes in the partition, Err(index) for a node left out of it
+    let group = |node: NodeIndex| partition.get(&node).copied().ok_or(node.index());
+    let mut total_weight = 0.0; // m, the total edge weight
+    let mut inside_weight = 0.0; // total weight of the edges within a group
+    let mut group_degree: HashMap<Result<usize, usize>, f64> = HashMap::new(); // total weighted degree of each group
+    for edge in graph.edge_references() {
+        let weight = edge.weight().weight();
+        let (group_a, group_b) = (group(edge.source()), group(edge.target()));
+        total_weight += weight;
+        if group_a == group_b {
+            inside_weight += weight;
+        }
+        *group_degree.entry(group_a).or_insert(0.0) += weight; // both ends add to the degree (a self-loop counts twice)
+        *group_degree.entry(group_b).or_insert(0.0) += weight;
+    }
+    if total_weight == 0.0 {
+        return 0.0;
+    }
+    let expected: f64 = group_degree.values().map(|degree| (degree / (2.0 * total_weight)).powi(2)).sum();
+    inside_weight / total_weight - expected
+}
+
+// Function to calculate the assortativity coefficient of the churn status over the edges (Newman's coefficient for a
+// categorical attribute, churned vs not churned): from -1 (edges only between the groups) through 0 (no preference) to 1
+// (edges only within a group); positive values mean churned customers cluster together.
+// Edges to a node outside the customers slice or with an unknown churn status are ignored; 0 if it's undefined
+// (no edges left, or every customer in the same group)
+pub fn churn_assortativity<E>(graph: &Graph<&Customer, E, Undirected>, customers: &[Customer]) -> f64 {
+    let node_customers = node_customer_indices(graph, customers);
+    let churned = |node: NodeIndex| match customer_for_node(&node_customers, customers, node)?.churn_status {
+        ChurnStatus::Attrited => Some(1),
+        ChurnStatus::Existing => Some(0),
+        ChurnStatus::Unknown => None,
+    };
+    let mut mixing = [[0.0; 2]; 2]; // edge ends by (group of one end, group of the other), each edge counted both ways
+    for edge in graph.edge_references() {
+        let (Some(group_a), Some(group_b)) = (churned(edge.source()), churned(edge.target())) else {
+            continue;
+        };
+        mixing[group_a][group_b] += 1.0;
+        mixing[group_b][group_a] += 1.0;
+    }
+    let total: f64 = mixing.iter().flatten().sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let within = (mixing[0][0] + mixing[1][1]) / total; // fraction of edge ends within a group
+    let expected: f64 = mixing.iter().map(|row| (row.iter().sum::<f64>() / total).powi(2)).sum(); // same, for random edges
+    if expected >= 1.0 {
+        return 0.0;
+    }
+    (within - expected) / (1.0 - expected)
+}
+
+// struct holding how strongly a node connects to the other groups of a partition (see bridging_nodes)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BridgeScore {
+    pub node: NodeIndex,
+    pub cross_edges: usize, // edges to a neighbor in another group
+    pub degree: usize, // edges to a neighbor in any group
+    pub fraction: f64, // cross_edges / degree
+}
+
+// Function to rank the nodes by the fraction of their edges that cross to another group of the partition
+// (e.g. churn / not churn): the customers connecting the groups, candidates for intervention.
+// Nodes and neighbors missing from the partition are ignored, as are nodes without an edge to a partitioned neighbor.
+// Sorted by fraction descending, then by the number of cross-group edges descending, then by node index
+pub fn bridging_nodes<N, E>(graph: &Graph<N, E, Undirected>, partition: &HashMap<NodeIndex, usize>) -> Vec<BridgeScore> {
+    let mut scores: Vec<BridgeScore> = graph.node_indices().filter_map(|node| {
+        let group = partition.get(&node)?;
+        let neighbor_groups: Vec<usize> = graph.neighbors(node).filter_map(|neighbor| partition.get(&neighbor).copied()).collect();
+        if neighbor_groups.is_empty() {
+            return None;
+        }
+        let cross_edges = neighbor_groups.iter().filter(|&neighbor_group| neighbor_group != group).count();
+        Some(BridgeScore { node, cross_edges, degree: neighbor_groups.len(), fraction: cross_edges as f64 / neighbor_groups.len() as f64 })
+    }).collect();
+    scores.sort_by(|a, b| b.fraction.total_cmp(&a.fraction).then_with(|| b.cross_edges.cmp(&a.cross_edges)).then_with(|| a.node.cmp(&b.node)));
+    scores
+}
+
+// Function to pick the most central node of each community (the "prototype" customer of the cluster)
+// returns community id -> node with the highest centrality in it; ties go to the lowest node index, and nodes
+// without a community or a centrality value are ignored (so a community may have no representative)
+pub fn community_representatives<N, E>(
+    graph: &Graph<N, E, Undirected>,
+    communities: &HashMap<NodeIndex, usize>, // community id per node, e.g. from detect_communities
+    centrality: &HashMap<NodeIndex, f64>, // centrality per node, e.g. from calculate_centrality
+) -> HashMap<usize, NodeIndex> {
+    let mut representatives: HashMap<usize, NodeIndex> = HashMap::new();
+    for node in graph.node_indices() { // ascending index, so only a strictly higher centrality replaces a representative
+        let (Some(&community), Some(&value)) = (communities.get(&node), centrality.get(&node)) else {
+            continue;
+        };
+        match representatives.get(&community) {
+            Some(current) if centrality[current] >= value => {}
+            _ => { representatives.insert(community, node); }
+        }
+    }
+    representatives
+}
+
+// Function for phase 1 of Louvain: move nodes between communities while modularity improves
+// returns the community of each node, numbered 0, 1, 2... in order of first appearance
+fn louvain_local_moving(adjacency: &[HashMap<usize, f64>]) -> Vec<usize> {
+    let n = adjacency.len();
+    // weighted degree of each node (a self-loop counts twice)
+    let degrees: Vec<f64> = adjacency.iter().enumerate()
+        .map(|(node, neighbors)| neighbors.iter().map(|(&neighbor, &weight)| if neighbor == node { 2.0 * weight } else { weight }).sum())
+        .collect();
+    let two_m: f64 = degrees.iter().sum(); // twice the total edge weight
+    let mut community: Vec<usize> = (0..n).collect();
+    if two_m == 0.0 {
+        return community;
+    }
+    let mut community_degree: Vec<f64> = degrees.clone(); // total degree of each community
+
+    let mut moved = true;
+    while moved {
+        moved = false;
+        for node in 0..n {
+            let current = community[node];
+            community_degree[current] -= degrees[node]; // take the node out of its community
+
+            // weight of the edges from the node to each neighboring community
+            let mut links: HashMap<usize, f64> = HashMap::new();
+            for (&neighbor, &weight) in &adjacency[node] {
+                if neighbor != node {
+                    *links.entry(community[neighbor]).or_insert(0.0) += weight;
+                }
+            }
+            // modularity gain (up to a constant factor) of putting the node into a community
+            let gain = |candidate: usize, link_weight: f64| link_weight - community_degree[candidate] * degrees[node] / two_m;
+            let mut best = current;
+            let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+            let mut candidates: Vec<(usize, f64)> = links.into_iter().collect();
+            candidates.sort_by_key(|&(candidate, _)| candidate); // visit candidates in a fixed order so results are reproducible
+            for (candidate, link_weight) in candidates {
+                let candidate_gain = gain(candidate, link_weight);
+                if candidate_gain > best_gain + 1e-12 {
+                    best = candidate;
+                    best_gain = candidate_gain;
+                }
+            }
+
+            community_degree[best] += degrees[node];
+            if best != current {
+                community[node] = best;
+                moved = true;
+            }
+        }
+    }
+
+    // renumber the communities 0, 1, 2...
+    let mut renumbered: HashMap<usize, usize> = HashMap::new();
+    community.iter().map(|&c| {
+        let next_id = renumbered.len();
+        *renumbered.entry(c).or_insert(next_id)
+    }).collect()
+}
+
+// Function to export the graph in GraphViz DOT format (render with e.g. `dot -Tsvg customers.dot`)
+// every node is labeled with its index; with show_churn the label also carries the churn status and the node is colored by it
+pub fn export_dot<E>(
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    writer: &mut impl Write, // destination of the .dot output (file, stdout, buffer...)
+    show_churn: bool, // whether to add the churn status to the labels and color the nodes
+) -> std::io::Result<()> {
+    writeln!(writer, "graph customers {{")?;
+    for node in graph.node_indices() {
+        if show_churn {
+            let customer = graph[node];
+            let color = match customer.churn_status { // existing vs churned customers, and the ones left out of both groups
+                ChurnStatus::Existing => "lightblue",
+                ChurnStatus::Attrited => "salmon",
+                ChurnStatus::Unknown => "lightgray",
+            };
+            writeln!(writer, "    {} [label=\"{}\\n{}\", style=filled, fillcolor={}];", node.index(), node.index(), customer.churn_status, color)?;
+        } else {
+            writeln!(writer, "    {} [label=\"{}\"];", node.index(), node.index())?;
+        }
+    }
+    // the graph is undirected, so only write each pair of customers once (even if the edge was added in both directions)
+    let mut written_edges: HashSet<(usize, usize)> = HashSet::new();
+    for edge in graph.edge_references() {
+        let (a, b) = (edge.source().index(), edge.target().index());
+        let pair = (a.min(b), a.max(b));
+        if written_edges.insert(pair) {
+            writeln!(writer, "    {} -- {};", pair.0, pair.1)?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+// Function to print every customer's churn status and sorted neighbor indices, one line per customer (e.g. "0 (Existing Customer): 1, 4"),
+// to check determine_neighbor by hand on small runs
+pub fn print_adjacency_list<E>(graph: &Graph<&Customer, E, Undirected>) -> std::io::Result<()> {
+    write_adjacency_list(graph, &mut std::io::stdout().lock())
+}
+
+// Function to write the adjacency list printed by print_adjacency_list to any writer
+// the churn status is read from each node's own customer, so the list stays right on a filtered or reordered graph
+pub fn write_adjacency_list<E>(
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    writer: &mut impl Write, // destination of the output (stdout, buffer...)
+) -> std::io::Result<()> {
+    for (node, neighbors) in graph.node_indices().zip(sorted_adjacency(graph)) {
+        let neighbors: Vec<String> = neighbors.iter().map(|neighbor| neighbor.to_string()).collect();
+        let neighbors = if neighbors.is_empty() { "none".to_string() } else { neighbors.join(", ") }; // isolated customers
+        writeln!(writer, "{} ({}): {}", node.index(), graph[node].churn_status, neighbors)?;
+    }
+    Ok(())
+}
+
+// struct holding a constructed graph in an owned form that can be saved to a file and reloaded (see save_graph and load_graph),
+// so a later run can skip construct_graph; the fingerprint identifies the customers and config it was built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedGraph {
+    pub fingerprint: u64, // see analysis::graph_fingerprint
+    pub customers: Vec<Customer>, // node i is customers[i]
+    pub edges: Vec<(usize, usize, u32)>, // (node, node, number of shared characteristics)
+}
+
+impl SavedGraph {
+    // Function to take the nodes and edges of a graph (built with construct_graph or its variants)
+    pub fn from_graph(graph: &Graph<&Customer, u32, Undirected>, fingerprint: u64) -> SavedGraph {
+        SavedGraph {
+            fingerprint,
+            customers: graph.node_weights().map(|&customer| customer.clone()).collect(),
+            edges: graph.edge_references().map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())).collect(),
+        }
+    }
+
+    // Function to rebuild the graph over the saved customers, with the same node indices and edges
+    pub fn graph(&self) -> Graph<&Customer, u32, Undirected> {
+        let mut graph = Graph::with_capacity(self.customers.len(), self.edges.len());
+        for customer in &self.customers {
+            graph.add_node(customer);
+        }
+        for &(a, b, weight) in &self.edges {
+            graph.add_edge(NodeIndex::new(a), NodeIndex::new(b), weight);
+        }
+        graph
+    }
+}
+
+// Function to save a graph to a JSON file
+pub fn save_graph(path: &Path, saved: &SavedGraph) -> std::io::Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer(&mut writer, saved)?;
+    writer.flush()
+}
+
+// Function to load a graph saved by save_graph; a file that isn't a saved graph is an InvalidData error
+pub fn load_graph(path: &Path) -> std::io::Result<SavedGraph> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::tests::{create_sample_customer1, create_sample_customer2, create_sample_customer3};
+
+    // test that export_dot writes every node once and every undirected edge once
+    #[test]
+    fn test_export_dot() {
+        // customer 1 and 2 are neighbors, customer 3 shares nothing with them but everything with its copy of unknown churn status
+        let mut unknown_customer = create_sample_customer3();
+        unknown_customer.churn_status = ChurnStatus::Unknown;
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3(), unknown_customer];
+        let graph = construct_graph(&customers);
+
+        let mut output = Vec::new();
+        export_dot(&graph, &mut output, true).unwrap();
+        let dot = String::from_utf8(output).unwrap();
+
+        assert!(dot.starts_with("graph customers {"));
+        assert!(dot.contains("    0 [label=\"0\\nExisting Customer\", style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("    1 [label=\"1\\nAttrited Customer\", style=filled, fillcolor=salmon];"));
+        assert!(dot.contains("    2 [label=\"2\\nAttrited Customer\", style=filled, fillcolor=salmon];"));
+        assert!(dot.contains("    3 [label=\"3\\nUnknown\", style=filled, fillcolor=lightgray];"));
+        assert_eq!(dot.matches(" -- ").count(), 2); // each edge is only emitted once
+        assert!(dot.contains("    0 -- 1;"));
+        assert!(dot.contains("    2 -- 3;"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    // test that the adjacency list has one line per customer with its churn status and sorted neighbors
+    #[test]
+    fn test_write_adjacency_list() {
+        // customer 1 and 2 are neighbors, customer 3 shares nothing with them
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let graph = construct_graph(&customers);
+
+        let mut output = Vec::new();
+        write_adjacency_list(&graph, &mut output).unwrap();
+        let lines: Vec<String> = String::from_utf8(output).unwrap().lines().map(str::to_string).collect();
+        assert_eq!(lines, ["0 (Existing Customer): 1", "1 (Attrited Customer): 0", "2 (Attrited Customer): none"]);
+
+        // without customer 1, customer 3 moves to index 0: its line shows its own churn status
+        let mut filtered = graph.clone();
+        filtered.remove_node(NodeIndex::new(0));
+        let mut output = Vec::new();
+        write_adjacency_list(&filtered, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "0 (Attrited Customer): none\n1 (Attrited Customer): none\n");
+    }
+
+    // test that nodes are mapped to their customers even after removing a node reorders the node indices
+    #[test]
+    fn test_node_customer_indices() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let mut graph = construct_graph(&customers);
+        let node_customers = node_customer_indices(&graph, &customers);
+        assert_eq!(node_customers, HashMap::from([(NodeIndex::new(0), 0), (NodeIndex::new(1), 1), (NodeIndex::new(2), 2)]));
+
+        graph.remove_node(NodeIndex::new(0)); // the last node (customer 3) takes index 0
+        let node_customers = node_customer_indices(&graph, &customers);
+        assert_eq!(node_customers, HashMap::from([(NodeIndex::new(0), 2), (NodeIndex::new(1), 1)]));
+        assert_eq!(customer_for_node(&node_customers, &customers, NodeIndex::new(0)), Some(&customers[2]));
+        assert_eq!(customer_for_node(&node_customers, &customers, NodeIndex::new(2)), None);
+
+        // a copy of the customers is a different slice, so none of the nodes refer to it
+        let copies = customers.clone();
+        assert!(node_customer_indices(&graph, &copies).is_empty());
+    }
+
+    // test that the graph builds for a type other than Customer through GraphableEntity
+    #[test]
+    fn test_construct_graph_generic() {
+        struct Tags(Vec<&'static str>); // a record is just a set of tags; two records share their common tags
+        impl GraphableEntity for Tags {
+            fn shared_characteristics(&self, other: &Self) -> Vec<String> {
+                self.0.iter().filter(|tag| other.0.contains(tag)).map(|tag| tag.to_string()).collect()
+            }
+        }
+        let records = vec![Tags(vec!["red", "round", "small"]), Tags(vec!["red", "round"]), Tags(vec!["red", "square"]), Tags(vec!["blue", "square"])];
+        let graph = construct_graph(&records);
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 1); // only the first two share two tags
+        let edge = graph.find_edge(NodeIndex::new(0), NodeIndex::new(1)).unwrap();
+        assert_eq!(graph[edge], 2);
+        assert!(determine_neighbor(&records[0], &records[1]));
+        assert!(!determine_neighbor(&records[2], &records[3]));
+    }
+
+    // test that an edge stores the number of characteristics its two customers share
+    #[test]
+    fn test_weighted_edges() {
+        let customer1 = create_sample_customer1();
+        let mut customer1_twin = create_sample_customer1(); // same categorical and count characteristics as customer1
+        customer1_twin.churn_status = ChurnStatus::Attrited;
+        customer1_twin.age = Some(41);
+        let customers = vec![customer1, customer1_twin, create_sample_customer2(), create_sample_customer3()];
+        let graph = construct_graph(&customers);
+
+        // everything but the age group is shared
+        let twin_edge = graph.find_edge(NodeIndex::new(0), NodeIndex::new(1)).unwrap();
+        assert_eq!(graph[twin_edge], 11);
+        // customer 1 and 2 share the four categorical characteristics and the transaction amount, but not the tenure band
+        let edge = graph.find_edge(NodeIndex::new(0), NodeIndex::new(2)).unwrap();
+        assert_eq!(graph[edge], 5);
+        assert!(graph.find_edge(NodeIndex::new(0), NodeIndex::new(3)).is_none());
+        assert_eq!(graph.edge_count(), 3); // each pair is only connected once
+
+        // the unweighted graph keeps the same connections
+        let unweighted = construct_unweighted_graph(&customers);
+        assert_eq!(unweighted.edge_count(), graph.edge_count());
+        assert!(unweighted.find_edge(NodeIndex::new(0), NodeIndex::new(1)).is_some());
+    }
+
+    // test that boosting an attribute's weight can turn a pair into neighbors
+    #[test]
+    fn test_attribute_weights() {
+        let customer1 = create_sample_customer1();
+        let mut customer4 = create_sample_customer3();
+        customer4.one_hot_encoding.card_type = "Silver".to_string(); // only the card type is shared with customer 1
+
+        let default_weights = default_attribute_weights();
+        assert!(!determine_neighbor_weighted(&customer1, &customer4, &default_weights, 2.0));
+        assert_eq!(determine_neighbor(&customer1, &customer4), determine_neighbor_weighted(&customer1, &customer4, &default_weights, 2.0));
+
+        let mut weights = default_attribute_weights();
+        weights.insert("Card Type".to_string(), 2.5);
+        assert!(determine_neighbor_weighted(&customer1, &customer4, &weights, 2.0));
+
+        let customers = vec![customer1, customer4];
+        assert_eq!(construct_graph_with_weights(&customers, &default_weights, 2.0, &SimilarityConfig::default()).edge_count(), 0);
+        let graph = construct_graph_with_weights(&customers, &weights, 2.0, &SimilarityConfig::default());
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.edge_weight(graph.edge_indices().next().unwrap()), Some(&1)); // the edge still carries the shared count
+    }
+
+    // test that the progress callback fires once per 10% of the customers and ends at the total
+    #[test]
+    fn test_construct_graph_progress() {
+        let customers: Vec<Customer> = (0..25).map(|i| {
+            let mut customer = create_sample_customer1();
+            customer.age = Some(20 + i);
+            customer
+        }).collect();
+        let mut calls = Vec::new();
+        let graph = construct_graph_with_progress(&customers, &HashMap::new(), 2.0, &SimilarityConfig::default(), |done, total| calls.push((done, total)));
+        assert_eq!(calls.len(), PROGRESS_STEPS);
+        assert_eq!(calls.first(), Some(&(3, 25))); // 3 of 25 customers is the first point past 10%
+        assert_eq!(calls.last(), Some(&(25, 25)));
+        assert_eq!(graph.edge_count(), construct_graph(&customers).edge_count());
+
+        // with fewer customers than steps, every customer is reported
+        let mut count = 0;
+        construct_graph_with_progress(&customers[..4], &HashMap::new(), 2.0, &SimilarityConfig::default(), |_, _| count += 1);
+        assert_eq!(count, 4);
+    }
+
+    // test that building within the churn groups leaves no edge between customers of different churn status
+    #[test]
+    fn test_construct_graph_within_churn_groups() {
+        let customers: Vec<Customer> = (0..8).map(|i| {
+            let mut customer = create_sample_customer1(); // all alike, so the full graph is complete
+            customer.churn_status = if i % 2 == 0 { ChurnStatus::Attrited } else { ChurnStatus::Existing };
+            customer
+        }).collect();
+        let graph = construct_graph_within_churn_groups(&customers, &HashMap::new(), 2.0, &SimilarityConfig::default(), |_, _| {});
+        assert_eq!(graph.node_count(), 8);
+        assert!(graph.edge_references().all(|edge| graph[edge.source()].churn_status == graph[edge.target()].churn_status));
+        assert_eq!(graph.edge_count(), 2 * 6); // a complete graph on each group of 4
+        assert_eq!(petgraph::algo::connected_components(&graph), 2);
+        assert_eq!(construct_graph(&customers).edge_count(), 28); // every pair, across the groups too
+    }
+
+    // test that the similarity score is 1 for identical customers, 0 for customers with nothing in common and the shared fraction otherwise
+    #[test]
+    fn test_similarity_score() {
+        let (customer1, customer2, customer3) = (create_sample_customer1(), create_sample_customer2(), create_sample_customer3());
+        assert_eq!(similarity_score(&customer1, &customer1.clone()), 1.0);
+        assert_eq!(similarity_score(&customer1, &customer3), 0.0);
+        assert!((similarity_score(&customer1, &customer2) - 5.0 / 12.0).abs() < 1e-12); // 5 of the 12 attributes
+        assert_eq!(similarity_score(&customer2, &customer1), similarity_score(&customer1, &customer2));
+
+        // the shared card type weighing 7: (4 + 7) of (11 + 7)
+        let mut weights = default_attribute_weights();
+        weights.insert("Card Type".to_string(), 7.0);
+        assert!((similarity_score_weighted(&customer1, &customer2, &weights) - 11.0 / 18.0).abs() < 1e-12);
+    }
+
+    // test that the k-nearest-neighbor graph keeps every node, caps the degrees at k and keeps the strongest edges
+    #[test]
+    fn test_keep_top_k_edges() {
+        let mut customers = vec![create_sample_customer1(); 8];
+        for (i, customer) in customers.iter_mut().enumerate() {
+            customer.num_transctions = Some(5 + 10 * (i as i32 % 3)); // three transaction groups
+            customer.age = Some(20 + 10 * (i as i32 % 2)); // two age groups
+        }
+        let graph = construct_graph(&customers);
+        assert_eq!(graph.edge_count(), 28); // dense: every pair shares at least 10 characteristics
+
+        for k in [1, 2, 3] {
+            let sparse = keep_top_k_edges(&graph, k);
+            assert_eq!(sparse.node_count(), graph.node_count());
+            assert!(graph.node_indices().all(|node| sparse.edges(node).count() <= k), "a node has more than {} neighbors", k);
+            assert!(sparse.edge_references().all(|edge| graph.find_edge(edge.source(), edge.target()).map(|e| graph[e]) == Some(*edge.weight())));
+        }
+        // customers 0 and 6 are identical, so they are each other's nearest neighbor
+        let nearest = keep_top_k_edges(&graph, 1);
+        assert!(nearest.contains_edge(NodeIndex::new(0), NodeIndex::new(6)));
+        assert!(nearest.edge_references().all(|edge| *edge.weight() == 12));
+    }
+
+    // test that the owned graph holds copies of the customers and the same edges as the borrowed one
+    #[test]
+    fn test_construct_owned_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let owned = construct_owned_graph(customers.clone());
+
+        assert_eq!(owned.node_count(), customers.len());
+        for (i, customer) in customers.iter().enumerate() {
+            assert_eq!(&owned[NodeIndex::new(i)], customer);
+        }
+        let borrowed = construct_graph(&customers);
+        let edge_list = |edges: Vec<(usize, usize, u32)>| { let mut edges = edges; edges.sort(); edges };
+        assert_eq!(
+            edge_list(owned.edge_references().map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())).collect()),
+            edge_list(borrowed.edge_references().map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())).collect()),
+        );
+
+        // the owned graph can outlive the vector it was built from
+        let returned = { let temporary = vec![create_sample_customer1(), create_sample_customer2()]; construct_owned_graph(temporary) };
+        assert_eq!(returned.edge_count(), 1);
+    }
+
+    // test that adding the customers one by one gives the same graph as building it from scratch
+    #[test]
+    fn test_add_customer_matches_construct_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer3_twin, customer1_twin];
+
+        let mut incremental: Graph<Customer, u32, Undirected> = Graph::new_undirected();
+        for (i, customer) in customers.iter().enumerate() {
+            assert_eq!(add_customer(&mut incremental, customer.clone()), NodeIndex::new(i));
+        }
+        let rebuilt = construct_owned_graph(customers.clone());
+        let edge_list = |graph: &Graph<Customer, u32, Undirected>| {
+            let mut edges: Vec<(usize, usize, u32)> = graph.edge_references().map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())).collect();
+            edges.sort();
+            edges
+        };
+        assert_eq!(incremental.node_count(), rebuilt.node_count());
+        assert_eq!(edge_list(&incremental), edge_list(&rebuilt));
+        assert!(incremental.edge_count() > 0);
+    }
+
+    // test that the neighbor check, the edge weight and the weighted score agree with the shared trait labels
+    #[test]
+    fn test_neighbor_check_agrees_with_shared_traits() {
+        let customer1 = create_sample_customer1();
+        let customer2 = create_sample_customer2();
+        let traits = shared_traits(&customer1, &customer2);
+        assert_eq!(count_shared_characteristics(&customer1, &customer2) as usize, traits.len());
+        assert_eq!(determine_neighbor(&customer1, &customer2), traits.len() >= NEIGHBOR_THRESHOLD as usize);
+
+        // only the attributes among the traits contribute their weight
+        let mut weights = HashMap::new();
+        weights.insert("Card Type".to_string(), 3.0);
+        weights.insert("Age".to_string(), 10.0); // the two customers are in different age groups
+        assert!(!traits.iter().any(|label| label.starts_with("Age")));
+        assert_eq!(shared_characteristics_score(&customer1, &customer2, &weights), traits.len() as f64 + 2.0);
+    }
+
+    // test that an asymmetric predicate only adds edges in one direction
+    #[test]
+    fn test_construct_directed_graph() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        // "a spends more than b": transaction amounts are 5000, 3000 and 900
+        let graph = construct_directed_graph(&customers, |a, b| a.transactions_amount > b.transactions_amount);
+        let (c1, c2, c3) = (NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2));
+
+        assert_eq!(graph.edge_count(), 3);
+        assert!(graph.contains_edge(c1, c2) && !graph.contains_edge(c2, c1));
+        assert!(graph.contains_edge(c1, c3) && !graph.contains_edge(c3, c1));
+        assert!(graph.contains_edge(c2, c3) && !graph.contains_edge(c3, c2));
+        assert_eq!(graph.neighbors_directed(c1, petgraph::Direction::Outgoing).count(), 2);
+        assert_eq!(graph.neighbors_directed(c1, petgraph::Direction::Incoming).count(), 0);
+
+        // a symmetric predicate adds both directions
+        let symmetric = construct_directed_graph(&customers, determine_neighbor);
+        assert!(symmetric.contains_edge(c1, c2) && symmetric.contains_edge(c2, c1));
+        assert_eq!(symmetric.edge_count(), 2);
+    }
+
+    // test that two separate clusters of customers are found as two components
+    #[test]
+    fn test_connected_components() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        // customers 1, 2 and the twin of 1 form one cluster, customer 3 and its twin another
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer3_twin, customer1_twin];
+        let graph = construct_graph(&customers);
+
+        let components = connected_components(&graph);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0], vec![NodeIndex::new(0), NodeIndex::new(2), NodeIndex::new(4)]);
+        assert_eq!(components[1], vec![NodeIndex::new(1), NodeIndex::new(3)]);
+    }
+
+    // test that only the nodes of the components below the minimum size are listed
+    #[test]
+    fn test_nodes_in_small_components() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..11).map(|_| graph.add_node(())).collect();
+        for pair in nodes[..5].windows(2) {
+            graph.add_edge(pair[0], pair[1], ()); // component of 5: 0 - 1 - 2 - 3 - 4
+        }
+        graph.add_edge(nodes[5], nodes[6], ()); // component of 2
+        graph.add_edge(nodes[7], nodes[8], ()); // component of 3
+        graph.add_edge(nodes[8], nodes[9], ());
+        // 10 is isolated
+
+        assert_eq!(nodes_in_small_components(&graph, 4), [5, 6, 7, 8, 9, 10].map(NodeIndex::new));
+        assert_eq!(nodes_in_small_components(&graph, 3), [5, 6, 10].map(NodeIndex::new));
+        assert!(nodes_in_small_components(&graph, 1).is_empty());
+    }
+
+    // test that a customer sharing too little with everyone else is reported as isolated
+    #[test]
+    fn test_isolated_nodes() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        // customer 3 shares no characteristics with the others
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer1_twin];
+        let graph = construct_graph(&customers);
+        assert_eq!(isolated_nodes(&graph), vec![NodeIndex::new(1)]);
+
+        let empty: Graph<(), (), Undirected> = Graph::new_undirected();
+        assert!(isolated_nodes(&empty).is_empty());
+    }
+
+    // test the path metrics on a path graph, with a smaller component that must be ignored
+    #[test]
+    fn test_path_length_and_diameter() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..7).map(|_| graph.add_node(())).collect();
+        for pair in nodes[..5].windows(2) {
+            graph.add_edge(pair[0], pair[1], ()); // path 0 - 1 - 2 - 3 - 4
+        }
+        graph.add_edge(nodes[5], nodes[6], ()); // separate pair 5 - 6
+
+        assert_eq!(diameter(&graph), 4);
+        // the 10 pairs of the path are 1 (x4), 2 (x3), 3 (x2) and 4 (x1) apart: 20 / 10
+        assert!((average_path_length(&graph) - 2.0).abs() < 1e-12);
+
+        let mut single: Graph<(), (), Undirected> = Graph::new_undirected();
+        single.add_node(());
+        assert_eq!(diameter(&single), 0);
+        assert_eq!(average_path_length(&single), 0.0);
+    }
+
+    // test that a higher threshold factor selects a subset of the nodes selected by a lower one
+    #[test]
+    fn test_threshold_factor_selects_subset() {
+        let centrality: HashMap<NodeIndex, f64> = [0.2, 0.9, 0.5, 0.65, 0.3, 0.8, 0.55].iter().enumerate()
+            .map(|(i, &value)| (NodeIndex::new(i), value))
+            .collect(); // mean 0.557
+        let mut previous: Option<HashSet<NodeIndex>> = None;
+        let mut sizes = Vec::new();
+        for factor in [0.5, 1.0, 1.1, 1.3, 1.7] {
+            let selected: HashSet<NodeIndex> = identify_high_centrality_nodes(&centrality, factor).into_iter().collect();
+            if let Some(previous) = &previous {
+                assert!(selected.is_subset(previous), "factor {} selected a node the lower factor didn't", factor);
+            }
+            sizes.push(selected.len());
+            previous = Some(selected);
+        }
+        assert_eq!(sizes, vec![6, 3, 3, 2, 0]);
+    }
+
+    // test that on a skewed distribution the median cut point keeps the upper half while the mean only keeps the outlier
+    #[test]
+    fn test_threshold_modes_on_skewed_centrality() {
+        let centrality: HashMap<NodeIndex, f64> = [0.1, 0.1, 0.1, 0.1, 0.2, 0.2, 0.3, 5.0].iter().enumerate()
+            .map(|(i, &value)| (NodeIndex::new(i), value))
+            .collect(); // mean 0.7625, pulled up by node 7; median 0.15
+        let selected = |mode: ThresholdMode| {
+            let mut nodes: Vec<usize> = identify_high_centrality_nodes_with(&centrality, 1.0, mode).into_iter().map(|node| node.index()).collect();
+            nodes.sort();
+            nodes
+        };
+        assert_eq!(selected(ThresholdMode::Mean), vec![7]);
+        assert_eq!(selected(ThresholdMode::Median), vec![4, 5, 6, 7]);
+        assert_eq!(selected(ThresholdMode::Percentile(75.0)), vec![6, 7]); // 75th percentile 0.225
+        assert_eq!(ThresholdMode::Percentile(50.0).reference_value(&[3.0, 1.0, 2.0]), 2.0);
+        assert!(identify_high_centrality_nodes_with(&HashMap::new(), 1.0, ThresholdMode::Median).is_empty());
+    }
+
+    // test that two cliques of one churn status each give a high positive assortativity, and random statuses about 0
+    #[test]
+    fn test_churn_assortativity() {
+        use rand::{Rng, SeedableRng};
+        let customers: Vec<Customer> = (0..40).map(|i| {
+            let mut customer = crate::customer::tests::create_sample_customer1();
+            customer.churn_status = if i < 20 { ChurnStatus::Attrited } else { ChurnStatus::Existing };
+            customer
+        }).collect();
+        let mut homophily: Graph<&Customer, (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = customers.iter().map(|customer| homophily.add_node(customer)).collect();
+        for clique in [&nodes[0..20], &nodes[20..40]] {
+            for (i, &a) in clique.iter().enumerate() {
+                for &b in clique.iter().skip(i + 1) {
+                    homophily.add_edge(a, b, ());
+                }
+            }
+        }
+        homophily.add_edge(nodes[0], nodes[20], ()); // a single edge across the groups
+        assert!(churn_assortativity(&homophily, &customers) > 0.99);
+
+        // the same graph with the statuses shuffled at random
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mixed: Vec<Customer> = customers.iter().map(|customer| {
+            let mut customer = customer.clone();
+            customer.churn_status = if rng.gen_bool(0.5) { ChurnStatus::Attrited } else { ChurnStatus::Existing };
+            customer
+        }).collect();
+        let mixed_graph = homophily.map(|node, _| &mixed[node.index()], |_, _| ());
+        assert!(churn_assortativity(&mixed_graph, &mixed).abs() < 0.1);
+
+        let empty: Graph<&Customer, (), Undirected> = Graph::new_undirected();
+        assert_eq!(churn_assortativity(&empty, &customers), 0.0);
+    }
+
+    // test that the node linking a churned clique to an existing clique ranks as the top bridge
+    #[test]
+    fn test_bridging_nodes() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..7).map(|_| graph.add_node(())).collect();
+        for (a, b) in [(0, 1), (0, 2), (1, 2), (3, 4), (3, 5), (4, 5), (6, 0), (6, 1), (6, 3), (6, 4)] {
+            graph.add_edge(nodes[a], nodes[b], ());
+        }
+        // 0, 1, 2 churned, 3, 4, 5 and the bridge 6 existing
+        let partition: HashMap<NodeIndex, usize> = nodes.iter().map(|&node| (node, usize::from(node.index() < 3))).collect();
+        let scores = bridging_nodes(&graph, &partition);
+        assert_eq!(scores.len(), 7);
+        assert_eq!(scores[0], BridgeScore { node: nodes[6], cross_edges: 2, degree: 4, fraction: 0.5 });
+        assert_eq!(scores[1..3].iter().map(|score| score.node).collect::<Vec<_>>(), [nodes[0], nodes[1]]); // one cross edge of three each
+        assert!(scores[3..].iter().all(|score| score.cross_edges == 0));
+
+        // a node missing from the partition is neither ranked nor counted as a neighbor
+        let without_bridge: HashMap<NodeIndex, usize> = partition.into_iter().filter(|(node, _)| *node != nodes[6]).collect();
+        assert!(bridging_nodes(&graph, &without_bridge).iter().all(|score| score.cross_edges == 0));
+    }
+
+    // test that only the k most central nodes are kept, most central first
+    #[test]
+    fn test_top_k_by_centrality() {
+        let centrality: HashMap<NodeIndex, f64> = [0.2, 0.9, 0.5, 0.65, 0.3, 0.8, 0.65].iter().enumerate()
+            .map(|(i, &value)| (NodeIndex::new(i), value))
+            .collect();
+        let nodes: Vec<NodeIndex> = (0..7).map(NodeIndex::new).collect();
+        assert_eq!(top_k_by_centrality(&nodes, &centrality, 3), [1, 5, 3].map(NodeIndex::new)); // 3 and 6 tie, 3 comes first
+        assert_eq!(top_k_by_centrality(&nodes[..2], &centrality, 3), [1, 0].map(NodeIndex::new));
+        assert!(top_k_by_centrality(&nodes, &centrality, 0).is_empty());
+    }
+
+    // test that exactly k nodes come back, most central first, with ties going to the lowest node index
+    #[test]
+    fn test_top_k_central_nodes() {
+        let centrality: HashMap<NodeIndex, f64> = [0.2, 0.9, 0.5, 0.65, 0.3, 0.8, 0.65].iter().enumerate()
+            .map(|(i, &value)| (NodeIndex::new(i), value))
+            .collect();
+        assert_eq!(top_k_central_nodes(&centrality, 4), [1, 5, 3, 6].map(NodeIndex::new)); // 3 and 6 tie, 3 comes first
+        for _ in 0..5 { // the same answer whatever the hash map's iteration order
+            let shuffled: HashMap<NodeIndex, f64> = centrality.iter().map(|(&node, &value)| (node, value)).collect();
+            assert_eq!(top_k_central_nodes(&shuffled, 3), [1, 5, 3].map(NodeIndex::new));
+        }
+        assert_eq!(top_k_central_nodes(&centrality, 10).len(), 7);
+        assert!(top_k_central_nodes(&centrality, 0).is_empty());
+    }
+
+    // test the share of edges carrying each attribute on a graph with three known edges
+    #[test]
+    fn test_edge_attribute_rates() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41); // shares everything but the age group with customer 1
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        assert_eq!(graph.edge_count(), 3); // 0-1, 0-2 and 1-2
+
+        let rates = edge_attribute_rates(&graph, &SimilarityConfig::default());
+        assert_eq!(rates.len(), ATTRIBUTE_NAMES.len());
+        assert_eq!(rates["Card Type"], 1.0);
+        assert!((rates["Mon W Bank"] - 1.0 / 3.0).abs() < 1e-12); // customer 2 is in a lower tenure band
+        assert_eq!(rates["Age"], 0.0);
+        assert!((rates["Number of Products Purchased"] - 1.0 / 3.0).abs() < 1e-12); // only customer 1 and its twin
+        assert!(edge_attribute_rates(&construct_graph(&customers[3..]), &SimilarityConfig::default()).is_empty());
+    }
+
+    // test that the rates use the given similarity config and the customers of the nodes, not the slice positions
+    #[test]
+    fn test_edge_attribute_rates_config_and_filtered_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer3(), create_sample_customer1(), customer1_twin];
+        let mut graph = construct_graph(&customers);
+        graph.remove_node(NodeIndex::new(0)); // customer 3 has no edges; the twin moves to index 0
+        assert_eq!(graph.edge_count(), 1);
+
+        // with wider age groups the twin (41) and customer 1 (25) share the age; Card Type isn't compared at all
+        let mut similarity = SimilarityConfig { age: crate::customer::BucketSpec::new(&[50.0]), ..SimilarityConfig::default() };
+        similarity.excluded_attributes.insert("Card Type".to_string());
+        let rates = edge_attribute_rates(&graph, &similarity);
+        assert_eq!(rates["Age"], 1.0);
+        assert_eq!(rates["Card Type"], 0.0);
+        assert_eq!(rates["Education Level"], 1.0);
+        assert_eq!(edge_attribute_rates(&graph, &SimilarityConfig::default())["Age"], 0.0);
+    }
+
+    // test the density of a graph with a known number of edges
+    #[test]
+    fn test_graph_stats() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        // a triangle and a single edge: 4 of the 10 possible edges
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer3_twin, customer1_twin];
+        let stats = graph_stats(&construct_graph(&customers));
+        assert_eq!(stats, GraphStats { node_count: 5, edge_count: 4, density: 0.4 });
+
+        assert_eq!(graph_stats(&construct_graph(&customers[..1])).density, 0.0);
+        assert_eq!(graph_stats(&Graph::<(), (), Undirected>::new_undirected()).density, 0.0);
+    }
+
+    // test the degree counts of a graph with two components
+    #[test]
+    fn test_degree_histogram() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        // customers 1, 2 and the twin of 1 form a triangle, customer 3 and its twin a single edge, and the lone customer has no neighbors
+        let loner = Customer {
+            churn_status: ChurnStatus::Existing,
+            age: Some(45), // only the age group is shared (with the twin of customer 1)
+            one_hot_encoding: crate::customer::OneHotEncoding {
+                education_level: "Uneducated".to_string(),
+                marital_status: "Divorced".to_string(),
+                income_range: "Less than $40K".to_string(),
+                card_type: "Platinum".to_string(),
+            },
+            mon_w_bank: Some(25),
+            num_product_purchased: Some(2),
+            mon_inactive: Some(4),
+            num_contact: Some(5),
+            transactions_amount: Some(1200),
+            num_transctions: Some(35),
+            avg_card_utilize: Some(0.15),
+        };
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer3_twin, customer1_twin, loner];
+        let graph = construct_graph(&customers);
+
+        let histogram = degree_histogram(&graph);
+        assert_eq!(histogram, BTreeMap::from([(0, 1), (1, 2), (2, 3)]));
+        assert_eq!(histogram.values().sum::<usize>(), graph.node_count());
+        assert!(degree_histogram(&Graph::<(), (), Undirected>::new_undirected()).is_empty());
+    }
+
+    // test that every node of a symmetric graph (a cycle) gets the same PageRank
+    #[test]
+    fn test_pagerank_symmetric_graph() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..4).map(|_| graph.add_node(())).collect();
+        for i in 0..4 {
+            graph.add_edge(nodes[i], nodes[(i + 1) % 4], ());
+        }
+        let pagerank = calculate_pagerank(&graph, 0.85, 50);
+        assert!((pagerank.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        for score in pagerank.values() {
+            assert!((score - 0.25).abs() < 1e-9);
+        }
+    }
+
+    // test that the hub of a star graph gets the highest PageRank
+    #[test]
+    fn test_pagerank_star_graph() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let hub = graph.add_node(());
+        for _ in 0..5 {
+            let leaf = graph.add_node(());
+            graph.add_edge(hub, leaf, ());
+        }
+        let pagerank = calculate_pagerank(&graph, 0.85, 50);
+        assert!((pagerank.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(graph.node_indices().filter(|&node| node != hub).all(|leaf| pagerank[&hub] > pagerank[&leaf]));
+        assert_eq!(identify_high_centrality_nodes(&pagerank, 1.1), vec![hub]);
+    }
+
+    // test harmonic centrality on a graph of two components and an isolated node
+    #[test]
+    fn test_harmonic_centrality_disconnected() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..6).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], ()); // path 0 - 1 - 2
+        graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[3], nodes[4], ()); // pair 3 - 4, and 5 is isolated
+
+        let harmonic = calculate_harmonic_centrality(&graph);
+        assert!(harmonic.values().all(|value| value.is_finite()));
+        assert_eq!(harmonic[&nodes[1]], 2.0); // two neighbors at distance 1
+        assert_eq!(harmonic[&nodes[0]], 1.5); // 1 + 1/2
+        assert_eq!(harmonic[&nodes[3]], 1.0);
+        assert_eq!(harmonic[&nodes[5]], 0.0);
+        assert_eq!(identify_high_centrality_nodes(&harmonic, 1.5), vec![nodes[1]]); // mean 7/6
+    }
+
+    // test that every node of a clique gets the same eigenvector centrality
+    #[test]
+    fn test_eigenvector_centrality_clique() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..4).map(|_| graph.add_node(())).collect();
+        for (i, &a) in nodes.iter().enumerate() {
+            for &b in nodes.iter().skip(i + 1) {
+                graph.add_edge(a, b, ());
+            }
+        }
+        let centrality = calculate_eigenvector_centrality(&graph, 100, 1e-9);
+        assert_eq!(centrality.len(), 4);
+        for score in centrality.values() {
+            assert!((score - 0.5).abs() < 1e-9); // L2-normalized: 4 equal scores of 1/sqrt(4)
+        }
+    }
+
+    // test that every node of a triangle has clustering coefficient 1
+    #[test]
+    fn test_clustering_coefficient_triangle() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..3).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[2], nodes[0], ());
+        for &node in &nodes {
+            assert_eq!(local_clustering_coefficient(&graph, node), 1.0);
+        }
+        assert_eq!(average_clustering_coefficient(&graph), 1.0);
+    }
+
+    // test that the center of a star has clustering coefficient 0
+    #[test]
+    fn test_clustering_coefficient_star() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let center = graph.add_node(());
+        let leaves: Vec<NodeIndex> = (0..4).map(|_| graph.add_node(())).collect();
+        for &leaf in &leaves {
+            graph.add_edge(center, leaf, ());
+        }
+        assert_eq!(local_clustering_coefficient(&graph, center), 0.0);
+        assert_eq!(local_clustering_coefficient(&graph, leaves[0]), 0.0); // a single neighbor
+        graph.add_edge(leaves[0], leaves[1], ()); // one of the 6 pairs of the center's neighbors is now connected
+        assert!((local_clustering_coefficient(&graph, center) - 1.0 / 6.0).abs() < 1e-12);
+        assert!((average_clustering_coefficient_of(&graph, &[leaves[0], leaves[1]]) - 1.0).abs() < 1e-12);
+    }
+
+    // test that Louvain recovers two cliques connected by a single edge
+    #[test]
+    fn test_detect_communities_two_cliques() {
+        let mut graph: Graph<(), u32, Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..8).map(|_| graph.add_node(())).collect();
+        for clique in [&nodes[0..4], &nodes[4..8]] {
+            for (i, &a) in clique.iter().enumerate() {
+                for &b in clique.iter().skip(i + 1) {
+                    graph.add_edge(a, b, 3);
+                }
+            }
+        }
+        graph.add_edge(nodes[3], nodes[4], 2); // the bridge between the cliques
+
+        let communities = detect_communities(&graph);
+        assert_eq!(communities.len(), 8);
+        assert!(nodes[0..4].iter().all(|node| communities[node] == 0));
+        assert!(nodes[4..8].iter().all(|node| communities[node] == 1));
+
+        // the same structure without weights gives the same communities
+        let unweighted = graph.map(|_, _| (), |_, _| ());
+        assert_eq!(detect_communities(&unweighted), communities);
+    }
+
+    // test that two cliques split along their bridge score a high modularity, and a random split about zero
+    #[test]
+    fn test_modularity_two_cliques() {
+        use rand::{Rng, SeedableRng};
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..20).map(|_| graph.add_node(())).collect();
+        for clique in [&nodes[0..10], &nodes[10..20]] {
+            for (i, &a) in clique.iter().enumerate() {
+                for &b in clique.iter().skip(i + 1) {
+                    graph.add_edge(a, b, ());
+                }
+            }
+        }
+        graph.add_edge(nodes[9], nodes[10], ()); // the bridge between the cliques
+
+        let natural: HashMap<NodeIndex, usize> = nodes.iter().map(|&node| (node, node.index() / 10)).collect();
+        assert!(modularity(&graph, &natural) > 0.45);
+        assert_eq!(detect_communities(&graph), natural);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let random: HashMap<NodeIndex, usize> = nodes.iter().map(|&node| (node, rng.gen_range(0..2))).collect();
+        assert!(modularity(&graph, &random).abs() < 0.1);
+
+        // a single group, or a graph without edges, has modularity 0
+        let single: HashMap<NodeIndex, usize> = nodes.iter().map(|&node| (node, 0)).collect();
+        assert!(modularity(&graph, &single).abs() < 1e-12);
+        let empty: Graph<(), (), Undirected> = Graph::new_undirected();
+        assert_eq!(modularity(&empty, &HashMap::new()), 0.0);
+    }
+
+    // test that each community is represented by its most central node
+    #[test]
+    fn test_community_representatives() {
+        let mut graph: Graph<(), u32, Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..8).map(|_| graph.add_node(())).collect();
+        for clique in [&nodes[0..4], &nodes[4..8]] {
+            for (i, &a) in clique.iter().enumerate() {
+                for &b in clique.iter().skip(i + 1) {
+                    graph.add_edge(a, b, 3);
+                }
+            }
+        }
+        graph.add_edge(nodes[3], nodes[4], 2); // the bridge ends are the most central node of each clique
+        let communities = detect_communities(&graph);
+        let centrality = calculate_pagerank(&graph, 0.85, 100);
+
+        let representatives = community_representatives(&graph, &communities, &centrality);
+        assert_eq!(representatives, HashMap::from([(0, nodes[3]), (1, nodes[4])]));
+        for (&community, representative) in &representatives {
+            assert_eq!(communities[representative], community);
+            let best = nodes.iter().filter(|node| communities[node] == community).map(|node| centrality[node]).fold(f64::MIN, f64::max);
+            assert_eq!(centrality[representative], best);
+        }
+
+        // ties go to the lowest index
+        let flat: HashMap<NodeIndex, f64> = nodes.iter().map(|&node| (node, 1.0)).collect();
+        assert_eq!(community_representatives(&graph, &communities, &flat), HashMap::from([(0, nodes[0]), (1, nodes[4])]));
+    }
+
+    // test that closeness from the Floyd-Warshall distance matrix matches the Dijkstra version
+    #[test]
+    fn test_floyd_warshall_centrality_matches_dijkstra() {
+        let mut customer1_twin = create_sample_customer1(); // connected to customer 1 only, so customer 2 is two steps away
+        customer1_twin.age = Some(41);
+        customer1_twin.one_hot_encoding.card_type = "Blue".to_string();
+        customer1_twin.one_hot_encoding.income_range = "Less than $40K".to_string();
+        customer1_twin.one_hot_encoding.marital_status = "Married".to_string();
+        customer1_twin.mon_w_bank = Some(30);
+        customer1_twin.transactions_amount = Some(1200);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        let customers = vec![create_sample_customer2(), create_sample_customer1(), customer1_twin, create_sample_customer3(), customer3_twin];
+        let graph = construct_graph(&customers);
+        assert!(graph.find_edge(NodeIndex::new(0), NodeIndex::new(2)).is_none());
+
+        let mut filtered = graph.clone();
+        filtered.remove_node(NodeIndex::new(0)); // the last node takes index 0, so the nodes no longer follow the customers
+        for graph in [&graph, &filtered] {
+            let floyd_warshall = calculate_centrality_floyd_warshall(graph);
+            let dijkstra = calculate_centrality_serial(graph);
+            assert_eq!(floyd_warshall.len(), graph.node_count());
+            assert_eq!(dijkstra.len(), graph.node_count());
+            for (node, value) in &dijkstra {
+                assert!((floyd_warshall[node] - value).abs() < 1e-12, "node {:?}: {} vs {}", node, floyd_warshall[node], value);
+            }
+        }
+        // the crossover only picks the method, not the result
+        assert_eq!(calculate_centrality_with(&graph, 0), calculate_centrality_serial(&graph));
+        assert_eq!(calculate_centrality_with(&graph, 5), calculate_centrality_floyd_warshall(&graph));
+    }
+
+    // test that a heavy edge brings its endpoints closer in weighted closeness only
+    #[test]
+    fn test_weighted_centrality() {
+        let customers = [create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let mut graph: Graph<&Customer, u32, Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = customers.iter().map(|customer| graph.add_node(customer)).collect();
+        graph.add_edge(nodes[0], nodes[1], 1); // path 0 - 1 - 2, where 1 and 2 share four times as many characteristics
+        graph.add_edge(nodes[1], nodes[2], 4);
+
+        let unweighted = calculate_centrality(&graph);
+        assert_eq!(unweighted[&nodes[0]], unweighted[&nodes[2]]); // the ends of the path look the same without weights
+
+        let weighted = calculate_weighted_centrality(&graph);
+        assert!((weighted[&nodes[2]] - 2.0 / 1.5).abs() < 1e-12); // distances 0.25 and 1.25
+        assert!((weighted[&nodes[0]] - 2.0 / 2.25).abs() < 1e-12); // distances 1 and 1.25
+        assert!(weighted[&nodes[2]] > weighted[&nodes[0]]);
+
+        // with every weight at 1 the two modes agree
+        let unit = graph.map(|_, &customer| customer, |_, _| ());
+        assert_eq!(calculate_weighted_centrality(&unit), calculate_centrality_serial(&unit));
+    }
+
+    // test that the parallel centrality matches the serial one
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_centrality_matches_serial() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let mut customer3_twin = create_sample_customer3();
+        customer3_twin.age = Some(58);
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer3_twin, customer1_twin];
+        let graph = construct_graph(&customers);
+        assert_eq!(calculate_centrality_parallel(&graph), calculate_centrality_serial(&graph));
+        let mut filtered = graph.clone();
+        filtered.remove_node(NodeIndex::new(1));
+        assert_eq!(calculate_centrality_parallel(&filtered), calculate_centrality_serial(&filtered));
+    }
+
+    // test that an isolated node gets a finite centrality and doesn't break the high centrality threshold
+    #[test]
+    fn test_centrality_with_isolated_node() {
+        // customer 3 shares nothing with the other two, so it is isolated
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let centrality = calculate_centrality(&graph);
+
+        assert!(centrality.values().all(|value| value.is_finite()));
+        assert_eq!(centrality[&NodeIndex::new(0)], 0.5); // reaches 1 of 2 other nodes at distance 1
+        assert_eq!(centrality[&NodeIndex::new(1)], 0.5);
+        assert_eq!(centrality[&NodeIndex::new(2)], 0.0); // isolated
+
+        let mut high_centrality_nodes = identify_high_centrality_nodes(&centrality, 1.1);
+        high_centrality_nodes.sort();
+        assert_eq!(high_centrality_nodes, vec![NodeIndex::new(0), NodeIndex::new(1)]);
+    }
+
+    // test each statistic of a known centrality distribution, and that an empty one gives NaN
+    #[test]
+    fn test_centrality_stats() {
+        let centrality: HashMap<NodeIndex, f64> = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].iter().enumerate()
+            .map(|(index, &value)| (NodeIndex::new(index), value))
+            .collect();
+        let stats = centrality_stats(&centrality);
+        assert_eq!((stats.min, stats.max, stats.mean, stats.median), (2.0, 9.0, 5.0, 4.5));
+        assert!((stats.std_dev - 2.0).abs() < 1e-12);
+        assert!(centrality_stats(&HashMap::new()).mean.is_nan());
+    }
+
+    // test a few known distances of a small graph, that they are symmetric and that unreachable pairs are left out
+    #[test]
+    fn test_distance_matrix() {
+        let mut graph: Graph<(), (), Undirected> = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..6).map(|_| graph.add_node(())).collect();
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0), (2, 4)] { // a square 0 - 1 - 2 - 3 with 4 hanging off 2, and 5 alone
+            graph.add_edge(nodes[a], nodes[b], ());
+        }
+        let distances = distance_matrix(&graph);
+        let distance = |a: usize, b: usize| distances[&nodes[a]].get(&nodes[b]).copied();
+        assert_eq!(distance(0, 0), Some(0.0));
+        assert_eq!(distance(0, 2), Some(2.0));
+        assert_eq!(distance(1, 3), Some(2.0));
+        assert_eq!(distance(0, 4), Some(3.0));
+        assert_eq!(distance(0, 5), None);
+        for a in 0..6 {
+            for b in 0..6 {
+                assert_eq!(distance(a, b), distance(b, a));
+            }
+        }
+        // the metrics built on the matrix agree with their standalone versions
+        assert_eq!(diameter_from(&graph, &distances), diameter(&graph));
+        assert_eq!(diameter(&graph), 3);
+        assert!((average_path_length_from(&graph, &distances) - average_path_length(&graph)).abs() < 1e-12);
+        let harmonic = calculate_harmonic_centrality(&graph);
+        for (node, value) in harmonic_centrality_from(&distances) { // the sums may add up in another order
+            assert!((value - harmonic[&node]).abs() < 1e-12);
+        }
+    }
+
+    // test that selecting nothing on a uniform distribution, or nearly everything past an outlier, is flagged
+    #[test]
+    fn test_threshold_warning() {
+        let centrality_map = |values: &[f64]| -> HashMap<NodeIndex, f64> {
+            values.iter().enumerate().map(|(index, &value)| (NodeIndex::new(index), value)).collect()
+        };
+        let uniform = centrality_map(&[0.98; 200]);
+        let selected = identify_high_centrality_nodes(&uniform, 1.1).len();
+        assert_eq!(threshold_warning(selected, uniform.len(), 1.1).unwrap(), "threshold factor 1.1 selected 0 of 200 nodes (0.0%); lower the threshold factor to select more");
+
+        let mut values = vec![1.0; 99];
+        values.push(0.01); // one outlier drags the mean down to 0.99, so half of it is below every other node
+        let skewed = centrality_map(&values);
+        let selected = identify_high_centrality_nodes(&skewed, 0.5).len();
+        assert!(threshold_warning(selected, skewed.len(), 0.5).unwrap().ends_with("selected 99 of 100 nodes (99.0%); raise the threshold factor to select fewer"));
+
+        assert_eq!(threshold_warning(10, 100, 1.1), None);
+        assert_eq!(threshold_warning(0, 0, 1.1), None);
+    }
+
+    // test that a saved and reloaded graph has the same nodes and edges as the original
+    #[test]
+    fn test_save_and_load_graph() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.json");
+        save_graph(&path, &SavedGraph::from_graph(&graph, 42)).unwrap();
+
+        let saved = load_graph(&path).unwrap();
+        assert_eq!(saved.fingerprint, 42);
+        let reloaded = saved.graph();
+        assert!(reloaded.node_weights().eq(graph.node_weights()));
+        let edges = |graph: &Graph<&Customer, u32, Undirected>| -> Vec<(usize, usize, u32)> {
+            graph.edge_references().map(|edge| (edge.source().index(), edge.target().index(), *edge.weight())).collect()
+        };
+        assert_eq!(edges(&reloaded), edges(&graph));
+        assert!(!edges(&graph).is_empty());
+
+        std::fs::write(&path, "not a graph").unwrap();
+        assert_eq!(load_graph(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,717 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use crate::customer::{ChurnStatus, Customer, OneHotEncoding, RecordError, EDUCATION_LEVELS, INCOME_RANGES, UNKNOWN_CATEGORY};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// struct mapping each Customer field to the index of the CSV column it is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub churn_status: usize, // Attrition_Flag
+    pub age: usize, // Customer_Age
+    pub education_level: usize, // Education_Level
+    pub marital_status: usize, // Marital_Status
+    pub income_range: usize, // Income_Category
+    pub card_type: usize, // Card_Category
+    pub mon_w_bank: usize, // Months_on_book
+    pub num_product_purchased: usize, // Total_Relationship_Count
+    pub mon_inactive: usize, // Months_Inactive_12_mon
+    pub num_contact: usize, // Contacts_Count_12_mon
+    pub transactions_amount: usize, // Total_Trans_Amt
+    pub num_transctions: usize, // Total_Trans_Ct
+    pub avg_card_utilize: usize, // Avg_Utilization_Ratio
+}
+
+impl Default for ColumnMap {
+    // column layout of the original BankChurners.csv
+    fn default() -> Self {
+        ColumnMap {
+            churn_status: 1,
+            age: 2,
+            education_level: 5,
+            marital_status: 6,
+            income_range: 7,
+            card_type: 8,
+            mon_w_bank: 9,
+            num_product_purchased: 10,
+            mon_inactive: 11,
+            num_contact: 12,
+            transactions_amount: 17,
+            num_transctions: 18,
+            avg_card_utilize: 20,
+        }
+    }
+}
+
+impl ColumnMap {
+    // Function to list the index of every column with its BankChurners header name
+    pub fn columns(&self) -> [(usize, &'static str); 13] {
+        [
+            (self.churn_status, "Attrition_Flag"),
+            (self.age, "Customer_Age"),
+            (self.education_level, "Education_Level"),
+            (self.marital_status, "Marital_Status"),
+            (self.income_range, "Income_Category"),
+            (self.card_type, "Card_Category"),
+            (self.mon_w_bank, "Months_on_book"),
+            (self.num_product_purchased, "Total_Relationship_Count"),
+            (self.mon_inactive, "Months_Inactive_12_mon"),
+            (self.num_contact, "Contacts_Count_12_mon"),
+            (self.transactions_amount, "Total_Trans_Amt"),
+            (self.num_transctions, "Total_Trans_Ct"),
+            (self.avg_card_utilize, "Avg_Utilization_Ratio"),
+        ]
+    }
+
+    // Function to build the column map by matching the BankChurners header names, so the columns can be in any order
+    // returns an error listing every expected column that is not in the header, along with the columns it does have,
+    // so a wrong file is rejected up front instead of being read as rows of zeros
+    pub fn from_headers(headers: &csv::StringRecord) -> Result<ColumnMap, String> {
+        let mut missing = Vec::new();
+        let mut find = |name: &'static str| {
+            headers.iter().position(|header| header.trim() == name).unwrap_or_else(|| {
+                missing.push(name);
+                0
+            })
+        };
+        let columns = ColumnMap {
+            churn_status: find("Attrition_Flag"),
+            age: find("Customer_Age"),
+            education_level: find("Education_Level"),
+            marital_status: find("Marital_Status"),
+            income_range: find("Income_Category"),
+            card_type: find("Card_Category"),
+            mon_w_bank: find("Months_on_book"),
+            num_product_purchased: find("Total_Relationship_Count"),
+            mon_inactive: find("Months_Inactive_12_mon"),
+            num_contact: find("Contacts_Count_12_mon"),
+            transactions_amount: find("Total_Trans_Amt"),
+            num_transctions: find("Total_Trans_Ct"),
+            avg_card_utilize: find("Avg_Utilization_Ratio"),
+        };
+        match missing.as_slice() {
+            [] => Ok(columns),
+            [name] => Err(format!("missing column {} (the header has {} columns: {})", name, headers.len(), headers.iter().collect::<Vec<_>>().join(", "))),
+            names => Err(format!("missing {} of the {} BankChurners columns: {} (the header has {} columns: {})", names.len(), columns.columns().len(),
+                names.join(", "), headers.len(), headers.iter().collect::<Vec<_>>().join(", "))),
+        }
+    }
+}
+
+// error describing why customers couldn't be read, so callers can tell a missing file from a bad row or an empty input
+#[derive(Debug)]
+pub enum ChurnAnalysisError {
+    Io(io::Error), // the input couldn't be opened or read
+    Csv(csv::Error), // the CSV is malformed (e.g. invalid UTF-8)
+    MissingColumns(String), // the header lacks columns that are needed, described by the message
+    ShortRecord { line: u64, fields: usize, expected: usize, column: &'static str }, // a row ends early (only with strict rows)
+    EmptyDataset, // the input has no header line
+    InFile { path: PathBuf, source: Box<ChurnAnalysisError> }, // the error of one of several input files
+}
+
+impl std::fmt::Display for ChurnAnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChurnAnalysisError::Io(error) => write!(f, "{}", error),
+            ChurnAnalysisError::Csv(error) => write!(f, "{}", error),
+            ChurnAnalysisError::MissingColumns(message) => write!(f, "{}", message),
+            ChurnAnalysisError::ShortRecord { line, fields, expected, column } =>
+                write!(f, "line {} has {} fields, expected at least {} (missing column {})", line, fields, expected, column),
+            ChurnAnalysisError::EmptyDataset => write!(f, "the file is empty (no header line)"),
+            ChurnAnalysisError::InFile { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for ChurnAnalysisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChurnAnalysisError::Io(error) => Some(error),
+            ChurnAnalysisError::Csv(error) => Some(error),
+            ChurnAnalysisError::InFile { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ChurnAnalysisError {
+    fn from(error: io::Error) -> Self {
+        ChurnAnalysisError::Io(error)
+    }
+}
+
+impl From<csv::Error> for ChurnAnalysisError {
+    fn from(error: csv::Error) -> Self {
+        ChurnAnalysisError::Csv(error)
+    }
+}
+
+// Function to open an input file for reading
+// a path ending in ".gz" is transparently decompressed (requires the `gzip` feature); anything else is read as is
+pub fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        return open_gzip(file);
+    }
+    Ok(Box::new(file))
+}
+
+#[cfg(feature = "gzip")]
+fn open_gzip(file: BufReader<File>) -> io::Result<Box<dyn Read>> {
+    Ok(Box::new(flate2::read::GzDecoder::new(file)))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn open_gzip(_file: BufReader<File>) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reading .gz input requires building with the `gzip` feature"))
+}
+
+// struct counting the rows that couldn't be parsed, by kind of problem and column
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    pub rows_read: usize, // number of rows read (valid or not)
+    pub skipped_rows: usize, // number of rows skipped because of a missing or unparseable field
+    pub missing_fields: BTreeMap<String, usize>, // column -> number of rows where it was missing or empty
+    pub unparseable_fields: BTreeMap<String, usize>, // column -> number of rows where its value wasn't a valid number
+    pub unknown_categories: BTreeMap<String, usize>, // column -> number of rows with an unrecognized category (strict categories only)
+    pub out_of_range_fields: BTreeMap<String, usize>, // column -> number of rows skipped for a value outside its valid range (strict ranges only)
+    pub clamped_fields: BTreeMap<String, usize>, // column -> number of rows kept with a value clamped into its valid range
+    pub missing_values: BTreeMap<String, usize>, // column -> number of rows kept with the (numeric) value missing
+    pub duplicate_rows: usize, // valid rows dropped because their key was already read (see load_customers_from_files)
+}
+
+impl ParseReport {
+    // Function to record a row that failed to parse
+    pub fn record_error(&mut self, error: &RecordError) {
+        self.skipped_rows += 1;
+        match error {
+            RecordError::MissingField(field) | RecordError::ShortRow { column: field, .. } => *self.missing_fields.entry(field.to_string()).or_insert(0) += 1,
+            RecordError::UnparseableField { field, .. } => *self.unparseable_fields.entry(field.to_string()).or_insert(0) += 1,
+            RecordError::UnknownCategory { field, .. } => *self.unknown_categories.entry(field.to_string()).or_insert(0) += 1,
+            RecordError::OutOfRange { field, .. } => *self.out_of_range_fields.entry(field.to_string()).or_insert(0) += 1,
+        }
+    }
+}
+
+// Function to read up to `limit` customers from a BankChurners-style CSV file (plain or gzip-compressed)
+// the columns are located by their header names, so their order doesn't matter; rows with problems are skipped
+pub fn load_customers(path: &Path, limit: usize) -> Result<Vec<Customer>, ChurnAnalysisError> {
+    Ok(load_customers_with_report(path, limit)?.0)
+}
+
+// Function to read up to `limit` rows like load_customers, also returning which rows were skipped and why
+// the delimiter is detected from the header line (see sniff_delimiter)
+pub fn load_customers_with_report(path: &Path, limit: usize) -> Result<(Vec<Customer>, ParseReport), ChurnAnalysisError> {
+    load_customers_delimited(path, limit, None)
+}
+
+// Function to read up to `limit` rows like load_customers_with_report, with the given field delimiter
+// (e.g. b';' or b'\t'), or the one detected from the header line if None
+pub fn load_customers_delimited(path: &Path, limit: usize, delimiter: Option<u8>) -> Result<(Vec<Customer>, ParseReport), ChurnAnalysisError> {
+    let (customers, report) = load_keyed_customers(path, limit, delimiter, None, false, false)?;
+    Ok((customers.into_iter().map(|(_, customer)| customer).collect(), report))
+}
+
+// parse report of each input file, in the order the files were read
+pub type FileReports = Vec<(PathBuf, ParseReport)>;
+
+// customers paired with the value of their key column
+type KeyedCustomers = Vec<(Option<String>, Customer)>;
+
+// Function to read up to `limit` customers from each of several files (e.g. one per month) and concatenate them in file order
+// every file gets its own parse report, so a bad file shows its own row problems; with a dedup key column (e.g. "CLIENTNUM")
+// only the first row of each key value is kept, and the later ones are counted as duplicates in the report of their file
+pub fn load_customers_from_files(
+    paths: &[PathBuf], // files to read, plain or gzip-compressed
+    limit: usize, // maximum number of rows read from each file
+    delimiter: Option<u8>, // field delimiter of all the files, detected per file if None
+    dedup_key: Option<&str>, // header name of the column identifying a customer, no deduplication if None
+    strict_categories: bool, // skip (and report) rows with an unrecognized category instead of mapping it to "Unknown"
+    strict_ranges: bool, // skip (and report) rows with an out of range value instead of clamping it
+) -> Result<(Vec<Customer>, FileReports), ChurnAnalysisError> {
+    let mut customers = Vec::new();
+    let mut reports = Vec::new();
+    let mut seen_keys: HashSet<String> = HashSet::new();
+    for path in paths {
+        let (keyed_customers, mut report) = load_keyed_customers(path, limit, delimiter, dedup_key, strict_categories, strict_ranges)
+            .map_err(|error| ChurnAnalysisError::InFile { path: path.clone(), source: Box::new(error) })?; // name the file that couldn't be read
+        for (key, customer) in keyed_customers {
+            if key.is_some_and(|key| !seen_keys.insert(key)) { // the key was already read
+                report.duplicate_rows += 1;
+            } else {
+                customers.push(customer);
+            }
+        }
+        reports.push((path.clone(), report));
+    }
+    Ok((customers, reports))
+}
+
+// Function to read up to `limit` rows, pairing every customer with the value of the key column (None without a key column)
+fn load_keyed_customers(path: &Path, limit: usize, delimiter: Option<u8>, key_column: Option<&str>, strict_categories: bool, strict_ranges: bool) -> Result<(KeyedCustomers, ParseReport), ChurnAnalysisError> {
+    read_keyed_customers(open_input(path)?, &ReadConfig { limit, delimiter, strict_categories, strict_ranges, strict_rows: false }, key_column)
+}
+
+// struct holding how customers are read from a CSV source (see read_customers)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadConfig {
+    pub limit: usize, // maximum number of rows read
+    pub delimiter: Option<u8>, // field delimiter (detected from the header line if None)
+    pub strict_categories: bool, // skip rows with an unrecognized category instead of mapping it to "Unknown"
+    pub strict_ranges: bool, // skip rows with an out of range value (see Customer::from_record_checked) instead of clamping it
+    pub strict_rows: bool, // stop with ChurnAnalysisError::ShortRecord at the first short row instead of skipping it
+}
+
+impl Default for ReadConfig {
+    fn default() -> Self {
+        ReadConfig { limit: usize::MAX, delimiter: None, strict_categories: false, strict_ranges: false, strict_rows: false }
+    }
+}
+
+// Function to read customers from any source of BankChurners-style CSV (a file, an in-memory buffer, stdin...)
+// like load_customers, but without opening anything, so the parsing can be tested on a string
+pub fn read_customers<R: Read>(reader: R, config: &ReadConfig) -> Result<Vec<Customer>, ChurnAnalysisError> {
+    Ok(read_customers_with_report(reader, config)?.0)
+}
+
+// Function to read customers like read_customers, also returning which rows were skipped and why
+pub fn read_customers_with_report<R: Read>(reader: R, config: &ReadConfig) -> Result<(Vec<Customer>, ParseReport), ChurnAnalysisError> {
+    let (customers, report) = read_keyed_customers(reader, config, None)?;
+    Ok((customers.into_iter().map(|(_, customer)| customer).collect(), report))
+}
+
+// Function to read customers from a CSV source, pairing every customer with the value of the key column (None without a key column)
+fn read_keyed_customers<R: Read>(reader: R, config: &ReadConfig, key_column: Option<&str>) -> Result<(KeyedCustomers, ParseReport), ChurnAnalysisError> {
+    let ReadConfig { limit, delimiter, strict_categories, strict_ranges, strict_rows } = *config;
+    let mut input = BufReader::new(reader);
+    let mut header_line = String::new();
+    input.read_line(&mut header_line)?; // read the header ourselves to sniff the delimiter, then hand it back to the CSV reader
+    if header_line.trim().is_empty() {
+        return Err(ChurnAnalysisError::EmptyDataset);
+    }
+    let delimiter = delimiter.unwrap_or_else(|| sniff_delimiter(&header_line));
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true) // a short row is skipped and reported by Customer::from_record instead of failing the whole file
+        .from_reader(io::Cursor::new(header_line).chain(input));
+    let columns = ColumnMap::from_headers(rdr.headers()?).map_err(ChurnAnalysisError::MissingColumns)?;
+    let key_index = match key_column {
+        Some(name) => Some(rdr.headers()?.iter().position(|header| header.trim() == name)
+            .ok_or_else(|| ChurnAnalysisError::MissingColumns(format!("missing column {}", name)))?),
+        None => None,
+    };
+    let mut customers = Vec::new();
+    let mut report = ParseReport::default();
+    for result in rdr.records().take(limit) {
+        let record = result?; // unwrap result to get the record
+        report.rows_read += 1;
+        match Customer::from_record_checked(&record, &columns, strict_categories, strict_ranges) {
+            Ok((customer, clamped_fields)) => {
+                for field in clamped_fields {
+                    *report.clamped_fields.entry(field.to_string()).or_insert(0) += 1;
+                }
+                for field in customer.missing_fields() {
+                    *report.missing_values.entry(field.to_string()).or_insert(0) += 1;
+                }
+                customers.push((key_index.map(|index| record.get(index).unwrap_or("").trim().to_string()), customer));
+            }
+            Err(RecordError::ShortRow { fields, expected, column }) if strict_rows => {
+                let line = record.position().map_or(0, |position| position.line());
+                return Err(ChurnAnalysisError::ShortRecord { line, fields, expected, column });
+            }
+            Err(error) => report.record_error(&error),
+        }
+    }
+    Ok((customers, report))
+}
+
+// Function to pick `size` of `total` row indices at random, reproducibly for a given seed
+// the indices are returned in ascending order (file order); all of them if size >= total.
+// StdRng is only guaranteed to give the same sequence within a version of the rand crate
+pub fn sample_indices(total: usize, size: usize, seed: u64) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut indices = rand::seq::index::sample(&mut rng, total, size.min(total)).into_vec();
+    indices.sort_unstable();
+    indices
+}
+
+// Function to keep a reproducible random sample of `size` customers (in their original order), instead of the first rows
+pub fn sample_customers(customers: Vec<Customer>, size: usize, seed: u64) -> Vec<Customer> {
+    let indices = sample_indices(customers.len(), size, seed);
+    let mut customers: Vec<Option<Customer>> = customers.into_iter().map(Some).collect();
+    indices.into_iter().filter_map(|i| customers[i].take()).collect()
+}
+
+// Function to generate `count` made-up customers with values in the ranges of BankChurners.csv (about 16% churned),
+// the same ones for a given seed; for benchmarks and tests that need a dataset of a given size without the file
+pub fn synthetic_customers(count: usize, seed: u64) -> Vec<Customer> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Closure to pick one of the labels, or "Unknown" about one time in ten like in the dataset
+    let pick = |rng: &mut StdRng, labels: &[&str]| -> String {
+        if rng.gen_bool(0.1) { UNKNOWN_CATEGORY.to_string() } else { labels[rng.gen_range(0..labels.len())].to_string() }
+    };
+    (0..count).map(|_| Customer {
+        churn_status: if rng.gen_bool(0.16) { ChurnStatus::Attrited } else { ChurnStatus::Existing },
+        age: Some(rng.gen_range(26..=73)),
+        one_hot_encoding: OneHotEncoding {
+            education_level: pick(&mut rng, &EDUCATION_LEVELS),
+            marital_status: pick(&mut rng, &["Married", "Single", "Divorced"]),
+            income_range: pick(&mut rng, &INCOME_RANGES),
+            card_type: ["Blue", "Blue", "Blue", "Blue", "Blue", "Blue", "Silver", "Gold", "Platinum"][rng.gen_range(0..9)].to_string(), // mostly Blue
+        },
+        mon_w_bank: Some(rng.gen_range(13..=56)),
+        num_product_purchased: Some(rng.gen_range(1..=6)),
+        mon_inactive: Some(rng.gen_range(0..=6)),
+        num_contact: Some(rng.gen_range(0..=6)),
+        transactions_amount: Some(rng.gen_range(510..=18484)),
+        num_transctions: Some(rng.gen_range(10..=139)),
+        avg_card_utilize: Some((rng.gen_range(0.0..1.0f64) * 1000.0).round() / 1000.0), // 3 decimals like the file
+    }).collect()
+}
+
+// Function to count the customers that are exact copies (equal on every field) of an earlier customer
+// such copies become separate nodes connected to each other on every attribute, which inflates their centrality
+pub fn count_duplicate_customers(customers: &[Customer]) -> usize {
+    let mut seen = HashSet::new();
+    customers.iter().filter(|customer| !seen.insert(duplicate_key(customer))).count()
+}
+
+// Function to drop the exact duplicate customers, keeping the first of each in the original order
+// returns the remaining customers and the number dropped (to key on a column instead, see load_customers_from_files)
+pub fn dedup_customers(customers: Vec<Customer>) -> (Vec<Customer>, usize) {
+    let total = customers.len();
+    let first_copies: Vec<bool> = {
+        let mut seen = HashSet::new();
+        customers.iter().map(|customer| seen.insert(duplicate_key(customer))).collect()
+    };
+    let unique: Vec<Customer> = customers.into_iter().zip(first_copies).filter_map(|(customer, first)| first.then_some(customer)).collect();
+    let dropped = total - unique.len();
+    (unique, dropped)
+}
+
+// Function to build a hashable key holding every field of a customer, so two customers have the same key exactly when
+// they are equal (the utilization ratio is compared by its bits, which only differs from == for 0.0 / -0.0 and NaN)
+fn duplicate_key(customer: &Customer) -> impl std::hash::Hash + Eq + '_ {
+    let categories = &customer.one_hot_encoding;
+    (
+        customer.churn_status, customer.age,
+        (&categories.education_level, &categories.marital_status, &categories.income_range, &categories.card_type),
+        (customer.mon_w_bank, customer.num_product_purchased, customer.mon_inactive, customer.num_contact),
+        (customer.transactions_amount, customer.num_transctions, customer.avg_card_utilize.map(f64::to_bits)),
+    )
+}
+
+// Function to guess the delimiter of a CSV file from its header line: the most frequent of ',', ';', tab and '|'
+// outside quoted fields (',' if none of them appears)
+pub fn sniff_delimiter(header_line: &str) -> u8 {
+    let candidates = [b',', b';', b'\t', b'|'];
+    let mut counts = [0usize; 4];
+    let mut in_quotes = false;
+    for byte in header_line.bytes() {
+        if byte == b'"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(i) = candidates.iter().position(|&candidate| candidate == byte) {
+                counts[i] += 1;
+            }
+        }
+    }
+    // the first candidate wins ties, so a header without any delimiter falls back to ','
+    let best = (0..candidates.len()).rev().max_by_key(|&i| counts[i]).unwrap_or(0);
+    candidates[best]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::customer::ChurnStatus;
+
+    // header and three rows (two existing customers, one churned) from BankChurners.csv
+    pub const SAMPLE_CSV: &str = r#""CLIENTNUM","Attrition_Flag","Customer_Age","Gender","Dependent_count","Education_Level","Marital_Status","Income_Category","Card_Category","Months_on_book","Total_Relationship_Count","Months_Inactive_12_mon","Contacts_Count_12_mon","Credit_Limit","Total_Revolving_Bal","Avg_Open_To_Buy","Total_Amt_Chng_Q4_Q1","Total_Trans_Amt","Total_Trans_Ct","Total_Ct_Chng_Q4_Q1","Avg_Utilization_Ratio","Naive_Bayes_Classifier_Attrition_Flag_Card_Category_Contacts_Count_12_mon_Dependent_count_Education_Level_Months_Inactive_12_mon_1","Naive_Bayes_Classifier_Attrition_Flag_Card_Category_Contacts_Count_12_mon_Dependent_count_Education_Level_Months_Inactive_12_mon_2"
+768805383,"Existing Customer",45,"M",3,"High School","Married","$60K - $80K","Blue",39,5,1,3,12691,777,11914,1.335,1144,42,1.625,0.061,9.3448e-05,0.99991
+818770008,"Existing Customer",49,"F",5,"Graduate","Single","Less than $40K","Blue",44,6,1,2,8256,864,7392,1.541,1291,33,3.714,0.105,5.6861e-05,0.99994
+708508758,"Attrited Customer",62,"F",0,"Graduate","Married","Less than $40K","Blue",49,2,3,3,1438.3,0,1438.3,1.047,692,16,0.6,0,0.99616,0.0038363
+"#;
+
+    // test that a plain CSV file is parsed into customers
+    #[test]
+    fn test_load_plain_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("customers.csv");
+        std::fs::write(&path, SAMPLE_CSV).unwrap();
+
+        let customers = load_customers(&path, 1000).unwrap();
+        assert_eq!(customers.len(), 3);
+        assert_eq!(customers[0].age, Some(45));
+        assert_eq!(customers[0].one_hot_encoding.income_range, "$60K - $80K");
+        assert_eq!(customers[2].churn_status, ChurnStatus::Attrited);
+        assert_eq!(customers[2].avg_card_utilize, Some(0.0));
+        assert_eq!(load_customers(&path, 2).unwrap().len(), 2); // the limit caps the number of rows read
+    }
+
+    // test that customers are parsed from an in-memory CSV string, with the same delimiter detection and limit as files
+    #[test]
+    fn test_read_customers_from_memory() {
+        let customers = read_customers(SAMPLE_CSV.as_bytes(), &ReadConfig::default()).unwrap();
+        assert_eq!(customers.len(), 3);
+        assert_eq!((customers[0].age, customers[0].one_hot_encoding.education_level.as_str()), (Some(45), "High School"));
+        assert_eq!((customers[1].age, customers[1].one_hot_encoding.marital_status.as_str()), (Some(49), "Single"));
+        assert_eq!((customers[2].churn_status, customers[2].transactions_amount), (ChurnStatus::Attrited, Some(692)));
+
+        let semicolons = SAMPLE_CSV.replace(',', ";");
+        let config = ReadConfig { limit: 2, ..ReadConfig::default() };
+        let (customers, report) = read_customers_with_report(io::Cursor::new(semicolons), &config).unwrap();
+        assert_eq!(customers.len(), 2);
+        assert_eq!(report.rows_read, 2);
+    }
+
+    // test that rows with missing and non-numeric fields, or cut short, are skipped and counted by kind,
+    // while a row with an empty number is kept and counted as a missing value
+    #[test]
+    fn test_load_report_malformed_rows() {
+        let mut csv = SAMPLE_CSV.to_string();
+        csv.push_str("1,\"Existing Customer\",,\"M\",3,\"Graduate\",\"Single\",\"Unknown\",\"Blue\",39,5,1,3,1,1,1,1,1000,42,1,0.1,0,1\n"); // no age
+        csv.push_str("2,\"Existing Customer\",40,\"M\",3,\"Graduate\",\"Single\",\"Unknown\",\"Blue\",39,5,1,3,1,1,1,1,n/a,42,1,0.1,0,1\n"); // text amount
+        csv.push_str("3,\"Existing Customer\",41,\"M\",3,\"Graduate\",\"Single\",\"Unknown\",\"Blue\",39,5,1,3,1,1,1,1,?,42,1,0.1,0,1\n");
+        csv.push_str("4,\"Existing Customer\",42,\"M\",3,\"Graduate\",\"Single\"\n"); // cut off after the marital status
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("customers.csv");
+        std::fs::write(&path, csv).unwrap();
+
+        let (customers, report) = load_customers_with_report(&path, 1000).unwrap();
+        assert_eq!(customers.len(), 4);
+        assert_eq!(customers[3].age, None);
+        assert_eq!(report.rows_read, 7);
+        assert_eq!(report.skipped_rows, 3);
+        assert_eq!(report.missing_fields, BTreeMap::from([("Income_Category".to_string(), 1)]));
+        assert_eq!(report.missing_values, BTreeMap::from([("Customer_Age".to_string(), 1)]));
+        assert_eq!(report.unparseable_fields, BTreeMap::from([("Total_Trans_Amt".to_string(), 2)]));
+    }
+
+    // test that out of range utilization ratios are clamped and counted by default, and skipped with strict ranges
+    #[test]
+    fn test_read_report_out_of_range() {
+        let mut csv = SAMPLE_CSV.to_string();
+        csv.push_str("1,\"Existing Customer\",40,\"M\",3,\"Graduate\",\"Single\",\"Unknown\",\"Blue\",39,5,1,3,1,1,1,1,1000,42,1,5000,0,1\n");
+        let (customers, report) = read_customers_with_report(csv.as_bytes(), &ReadConfig::default()).unwrap();
+        assert_eq!((customers.len(), customers[3].avg_card_utilize), (4, Some(1.0)));
+        assert_eq!(report.clamped_fields, BTreeMap::from([("Avg_Utilization_Ratio".to_string(), 1)]));
+
+        let strict = ReadConfig { strict_ranges: true, ..ReadConfig::default() };
+        let (customers, report) = read_customers_with_report(csv.as_bytes(), &strict).unwrap();
+        assert_eq!((customers.len(), report.skipped_rows), (3, 1));
+        assert_eq!(report.out_of_range_fields, BTreeMap::from([("Avg_Utilization_Ratio".to_string(), 1)]));
+        assert!(report.clamped_fields.is_empty());
+    }
+
+    // test that a truncated row is skipped by default and stops the read with ShortRecord in the strict rows mode
+    #[test]
+    fn test_short_record_error() {
+        let truncated: Vec<&str> = SAMPLE_CSV.lines().nth(1).unwrap().split(',').take(5).collect();
+        let csv = format!("{}{}\n", SAMPLE_CSV, truncated.join(","));
+        let (customers, report) = read_customers_with_report(csv.as_bytes(), &ReadConfig::default()).unwrap();
+        assert_eq!((customers.len(), report.skipped_rows), (3, 1));
+
+        let strict = ReadConfig { strict_rows: true, ..ReadConfig::default() };
+        match read_customers(csv.as_bytes(), &strict) {
+            Err(ChurnAnalysisError::ShortRecord { line, fields, column, .. }) => assert_eq!((line, fields, column), (5, 5, "Education_Level")),
+            other => panic!("expected a short record error, got {:?}", other),
+        }
+    }
+
+    // test that exact duplicate rows are counted, dropped keeping the first, and no longer become separate nodes
+    #[test]
+    fn test_dedup_customers() {
+        let mut csv = SAMPLE_CSV.to_string();
+        let rows: Vec<&str> = SAMPLE_CSV.lines().skip(1).collect();
+        csv.push_str(&format!("{}\n{}\n{}\n", rows[0], rows[2], rows[0])); // customer 1 three times, customer 3 twice
+        let customers = read_customers(csv.as_bytes(), &ReadConfig::default()).unwrap();
+        assert_eq!(customers.len(), 6);
+        assert_eq!(count_duplicate_customers(&customers), 3);
+
+        let (unique, dropped) = dedup_customers(customers.clone());
+        assert_eq!(dropped, 3);
+        assert_eq!(unique, customers[..3].to_vec());
+        assert_eq!(crate::graph_utils::construct_graph(&unique).node_count(), 3);
+
+        let mut older = customers[0].clone();
+        older.age = older.age.map(|age| age + 1); // differs in a single field, so it isn't a duplicate
+        assert_eq!(count_duplicate_customers(&[customers[0].clone(), older]), 0);
+    }
+
+    // test that an empty file is an error, and a header-only file has no customers
+    #[test]
+    fn test_load_empty_and_header_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.csv");
+        let header_only = dir.path().join("header.csv");
+        std::fs::write(&empty, "").unwrap();
+        std::fs::write(&header_only, SAMPLE_CSV.lines().next().unwrap()).unwrap();
+
+        assert!(matches!(load_customers(&empty, 1000), Err(ChurnAnalysisError::EmptyDataset)));
+        assert!(load_customers(&empty, 1000).unwrap_err().to_string().contains("empty"));
+        let (customers, report) = load_customers_with_report(&header_only, 1000).unwrap();
+        assert!(customers.is_empty());
+        assert_eq!(report.rows_read, 0);
+    }
+
+    // test that customers from several files are merged (each file with its own report), and deduplicated on a key column
+    #[test]
+    fn test_load_multiple_files() {
+        let mut lines = SAMPLE_CSV.lines();
+        let header = lines.next().unwrap();
+        let rows: Vec<&str> = lines.collect();
+        let dir = tempfile::tempdir().unwrap();
+        let january = dir.path().join("january.csv");
+        let february = dir.path().join("february.csv");
+        std::fs::write(&january, format!("{}\n{}\n{}\n", header, rows[0], rows[1])).unwrap();
+        // the second file repeats the first customer of january and has a row with an unparseable age
+        let bad_row = "1,\"Existing Customer\",n/a,\"M\",3,\"Graduate\",\"Single\",\"Unknown\",\"Blue\",39,5,1,3,1,1,1,1,1000,42,1,0.1,0,1";
+        std::fs::write(&february, format!("{}\n{}\n{}\n{}\n", header, rows[2], rows[0], bad_row)).unwrap();
+        let paths = vec![january.clone(), february.clone()];
+
+        let (customers, reports) = load_customers_from_files(&paths, 1000, None, None, false, false).unwrap();
+        assert_eq!(customers.len(), 4);
+        assert_eq!(reports[0], (january.clone(), ParseReport { rows_read: 2, ..ParseReport::default() }));
+        assert_eq!((reports[1].1.rows_read, reports[1].1.skipped_rows), (3, 1));
+
+        let (customers, reports) = load_customers_from_files(&paths, 1000, None, Some("CLIENTNUM"), false, false).unwrap();
+        assert_eq!(customers, load_customers(&january, 1000).unwrap().into_iter().chain(load_customers(&february, 1).unwrap()).collect::<Vec<_>>());
+        assert_eq!(reports[1].1.duplicate_rows, 1);
+        assert!(matches!(load_customers_from_files(&paths, 1000, None, Some("Customer_Id"), false, false),
+            Err(ChurnAnalysisError::InFile { source, .. }) if matches!(*source, ChurnAnalysisError::MissingColumns(_))));
+
+        // the second january customer and the february customer share education, income, card type and months with the bank
+        let graph = crate::graph_utils::construct_graph(&customers);
+        assert_eq!(graph.node_count(), 3);
+        assert!(graph.contains_edge(petgraph::graph::NodeIndex::new(1), petgraph::graph::NodeIndex::new(2)));
+    }
+
+    // test that the fields land correctly when the columns are in a different order
+    #[test]
+    fn test_reordered_columns() {
+        // write the sample with every row (and the header) reversed
+        let mut reader = csv::Reader::from_reader(SAMPLE_CSV.as_bytes());
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(reader.headers().unwrap().iter().rev()).unwrap();
+        for record in reader.records() {
+            writer.write_record(record.unwrap().iter().rev()).unwrap();
+        }
+        let reordered_csv = writer.into_inner().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().join("customers.csv");
+        let reordered_path = dir.path().join("reordered.csv");
+        std::fs::write(&plain_path, SAMPLE_CSV).unwrap();
+        std::fs::write(&reordered_path, reordered_csv).unwrap();
+
+        let mut reordered_reader = csv::Reader::from_path(&reordered_path).unwrap();
+        let columns = ColumnMap::from_headers(reordered_reader.headers().unwrap()).unwrap();
+        assert_eq!(columns.age, 20); // Customer_Age is now the third column from the end
+        assert_ne!(columns, ColumnMap::default());
+        assert_eq!(load_customers(&reordered_path, 1000).unwrap(), load_customers(&plain_path, 1000).unwrap());
+
+        let mut headers = reordered_reader.headers().unwrap().clone();
+        headers = headers.iter().map(|header| if header == "Card_Category" { "Card_Type" } else { header }).collect();
+        let error = ColumnMap::from_headers(&headers).unwrap_err(); // a single renamed column
+        assert!(error.starts_with(&format!("missing column Card_Category (the header has {} columns: ", headers.len())) && error.contains("Card_Type"));
+    }
+
+    // test that a header with the wrong columns is rejected with every missing column and the columns it has
+    #[test]
+    fn test_wrong_header() {
+        let wrong = csv::StringRecord::from(vec!["Attrition_Flag", "Customer_Age", "Name"]);
+        assert_eq!(ColumnMap::from_headers(&wrong), Err("missing 11 of the 13 BankChurners columns: Education_Level, Marital_Status, \
+            Income_Category, Card_Category, Months_on_book, Total_Relationship_Count, Months_Inactive_12_mon, Contacts_Count_12_mon, \
+            Total_Trans_Amt, Total_Trans_Ct, Avg_Utilization_Ratio (the header has 3 columns: Attrition_Flag, Customer_Age, Name)".to_string()));
+
+        let error = read_customers("Name,Balance\nAda,12\n".as_bytes(), &ReadConfig::default()).unwrap_err();
+        assert!(error.to_string().starts_with("missing 13 of the 13 BankChurners columns: Attrition_Flag, "));
+    }
+
+    // test that semicolon- and tab-delimited files are detected and parse like the comma version
+    #[test]
+    fn test_load_other_delimiters() {
+        let mut reader = csv::Reader::from_reader(SAMPLE_CSV.as_bytes());
+        let write_with = |delimiter: u8, reader: &mut csv::Reader<&[u8]>| {
+            let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(Vec::new());
+            writer.write_record(reader.headers().unwrap()).unwrap();
+            for record in reader.records() {
+                writer.write_record(&record.unwrap()).unwrap();
+            }
+            writer.into_inner().unwrap()
+        };
+        let semicolon_csv = write_with(b';', &mut reader);
+        let mut reader = csv::Reader::from_reader(SAMPLE_CSV.as_bytes());
+        let tab_csv = write_with(b'\t', &mut reader);
+
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().join("customers.csv");
+        let semicolon_path = dir.path().join("semicolon.csv");
+        let tab_path = dir.path().join("tab.tsv");
+        std::fs::write(&plain_path, SAMPLE_CSV).unwrap();
+        std::fs::write(&semicolon_path, &semicolon_csv).unwrap();
+        std::fs::write(&tab_path, &tab_csv).unwrap();
+
+        let plain = load_customers(&plain_path, 1000).unwrap();
+        assert_eq!(plain.len(), 3);
+        assert_eq!(load_customers(&semicolon_path, 1000).unwrap(), plain);
+        assert_eq!(load_customers(&tab_path, 1000).unwrap(), plain);
+        assert_eq!(load_customers_delimited(&semicolon_path, 1000, Some(b';')).unwrap().0, plain);
+        assert!(load_customers_delimited(&semicolon_path, 1000, Some(b',')).is_err()); // a forced wrong delimiter finds no columns
+
+        assert_eq!(sniff_delimiter("\"a;b\",c,d\n"), b','); // delimiters inside quotes don't count
+        assert_eq!(sniff_delimiter("single_column\n"), b',');
+    }
+
+    // test that the same seed samples the same rows, and a different seed other rows
+    #[test]
+    fn test_sample_indices_reproducible() {
+        let first = sample_indices(10000, 50, 7);
+        assert_eq!(first, sample_indices(10000, 50, 7));
+        assert_ne!(first, sample_indices(10000, 50, 8));
+        assert_eq!(first.len(), 50);
+        assert!(first.windows(2).all(|pair| pair[0] < pair[1])); // distinct, in file order
+        assert!(first.iter().all(|&i| i < 10000));
+        assert_eq!(sample_indices(3, 10, 7), vec![0, 1, 2]); // asking for more rows than there are keeps them all
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("customers.csv");
+        std::fs::write(&path, SAMPLE_CSV).unwrap();
+        let customers = load_customers(&path, 1000).unwrap();
+        let sample = sample_customers(customers.clone(), 2, 7);
+        let expected: Vec<Customer> = sample_indices(3, 2, 7).into_iter().map(|i| customers[i].clone()).collect();
+        assert_eq!(sample, expected);
+    }
+
+    // test that the synthetic customers are the same for a seed, differ for another seed, and stay in the dataset's ranges
+    #[test]
+    fn test_synthetic_customers() {
+        let customers = synthetic_customers(500, 3);
+        assert_eq!(customers.len(), 500);
+        assert_eq!(customers, synthetic_customers(500, 3));
+        assert_ne!(customers, synthetic_customers(500, 4));
+        assert!(customers.iter().all(|customer| customer.avg_card_utilize.is_some_and(|ratio| (0.0..=1.0).contains(&ratio))));
+        assert!(customers.iter().all(|customer| customer.missing_fields().is_empty()));
+        let churned = customers.iter().filter(|customer| customer.churn_status == ChurnStatus::Attrited).count();
+        assert!((40..=120).contains(&churned)); // about 16%
+    }
+
+    // test that a gzipped file produces the same customers as the uncompressed version
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_load_gzip_csv() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().join("customers.csv");
+        let gzip_path = dir.path().join("customers.csv.gz");
+        std::fs::write(&plain_path, SAMPLE_CSV).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gzip_path).unwrap(), flate2::Compression::default());
+        encoder.write_all(SAMPLE_CSV.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(load_customers(&gzip_path, 1000).unwrap(), load_customers(&plain_path, 1000).unwrap());
+    }
+}
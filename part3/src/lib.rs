@@ -0,0 +1,7 @@
+pub mod graph_utils; // Expose local modules so the analysis can be reused as a library (and by main)
+pub mod customer;
+pub mod cli;
+pub mod report;
+pub mod input;
+pub mod analysis;
+pub mod stats;
@@ -1,63 +1,432 @@
-mod graph_utils; // Import local modules 
-mod customer;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
-use crate::customer::{Customer, OneHotEncoding, print_top_shared_characteristics};
-use graph_utils::{construct_graph, calculate_centrality, identify_high_centrality_nodes};
-use crate::customer::map_category;
+use std::io::Write;
+use part3::customer::{filter_customers, ChurnStatus, ACTIVITY_BUCKET_EDGES, Customer, compute_distinguishing_characteristics, write_node_characteristics, format_percentage, write_summary_counts, write_summary_with}; // Import local modules from the library crate
+use part3::graph_utils::{average_path_length_from, bridging_nodes, centrality_stats, churn_assortativity, distance_matrix, isolated_nodes, connected_components, average_clustering_coefficient_of, diameter_from, degree_histogram, edge_attribute_rates, graph_stats, load_graph, modularity, save_graph, threshold_warning, write_adjacency_list, NodeOrder, SavedGraph};
+use petgraph::graph::NodeIndex;
+use std::path::PathBuf;
+use part3::input::{count_duplicate_customers, dedup_customers, load_customers_from_files, sample_customers, ParseReport};
+use part3::cli::{parse_args, OutputFormat};
+use part3::report::{analysis_report, dry_run_or_build, group_trees, open_output, run_interactive, to_json, write_profiles_jsonl, TreeReport};
+use part3::analysis::{AnalysisConfig, GroupAnalysis, build_graph, graph_fingerprint, run_analysis_on_graph};
+use part3::stats::{category_counts, chi_square_by_category, compare_prevalence, PrevalenceComparison};
 
 
 
+// largest number of node indices listed on one line of the text output
+const MAX_LISTED_NODES: usize = 20;
+
 pub fn main() -> Result<(), Box<dyn Error>> {
-    // Read the CSV file and create a vector of Customer structs
-    let mut rdr = csv::Reader::from_path("BankChurners.csv")?;
-    let customers: Vec<Customer> = rdr
-        .records()
-        .take(1000)
-        .map(|result| {
-            let record = result?; // unwrap result to get the record
-            Ok::<Customer, Box<dyn Error>>(Customer {
-                // extract values from record
-                churn_status: record.get(1).unwrap_or(&"Unknown".to_string()).to_string(),
-                age: record[1].parse().unwrap_or(2),
-                one_hot_encoding: OneHotEncoding {
-                    education_level: map_category(record.get(5).unwrap_or(&"Unknown".to_string())),
-                    marital_status: map_category(record.get(6).unwrap_or(&"Unknown".to_string())),
-                    income_range: map_category(record.get(7).unwrap_or(&"Unknown".to_string())),
-                    card_type: map_category(record.get(8).unwrap_or(&"Unknown".to_string())),
-                },
-                mon_w_bank: record[9].parse().unwrap_or(0),
-                num_product_purchased: record[10].parse().unwrap_or(0),
-                mon_inactive: record[11].parse().unwrap_or(0),
-                num_contact: record[12].parse().unwrap_or(0),
-                //card_credit_limit: record[13].parse().unwrap_or(0),
-                //evolving_bal: record[14].parse().unwrap_or(0),
-                transactions_amount: record[17].parse().unwrap_or(0),
-                num_transctions: record[18].parse().unwrap_or(0),
-                avg_card_utilize: record[20].parse().unwrap_or(0.0),
-            })
+    let options = parse_args(std::env::args().skip(1))?; // parse the command line options (e.g. --format json)
+    // parameters of the analysis (thresholds, centrality algorithm...), defaults overridden by the command line
+    let mut builder = AnalysisConfig::builder();
+    if let Some(top_n) = options.top_n {
+        builder = builder.top_n(top_n);
+    }
+    if let Some(min_component_size) = options.min_component_size {
+        builder = builder.min_component_size(min_component_size);
+    }
+    if let Some(max_neighbors) = options.max_neighbors {
+        builder = builder.max_neighbors(max_neighbors);
+    }
+    if let Some(k) = options.most_central {
+        builder = builder.most_central(k);
+    }
+    if let Some(top_nodes) = options.top_nodes {
+        builder = builder.top_nodes(top_nodes);
+    }
+    if options.weighted_traits {
+        builder = builder.weighted_ranking(); // rare shared characteristics before ubiquitous ones
+    }
+    if options.sort_by_centrality {
+        builder = builder.node_order(NodeOrder::Centrality); // the most important customers first
+    }
+    if options.bucket_activity {
+        builder = builder.mon_inactive_buckets(&ACTIVITY_BUCKET_EDGES).num_contact_buckets(&ACTIVITY_BUCKET_EDGES);
+    }
+    if options.combined_graph {
+        builder = builder.combined_graph(); // one centrality for everybody, no per-group subgraphs
+    }
+    if let Some(edges) = &options.tenure_bands {
+        builder = builder.tenure_bands(edges);
+    }
+    if options.within_groups {
+        builder = builder.within_churn_groups(); // no edges between churned and existing customers
+    }
+    if let Some(precision) = options.precision {
+        builder = builder.precision(precision);
+    }
+    if let Some(threshold_factor) = options.threshold_factor {
+        builder = builder.threshold_factor(threshold_factor);
+    }
+    if let Some(threshold_mode) = options.threshold_mode {
+        builder = builder.threshold_mode(threshold_mode);
+    }
+    if let Some(unknown_policy) = options.unknown_policy {
+        builder = builder.unknown_policy(unknown_policy);
+    }
+    if let Some(resamples) = options.bootstrap {
+        builder = builder.bootstrap(resamples, options.seed);
+    }
+    if let Some(sample) = options.sample {
+        builder = builder.record_limit(sample); // analyze the whole sample
+    }
+    let config = builder.show_progress(options.progress).build();
+
+    // Read the CSV file (optionally gzip-compressed) and create a vector of Customer structs
+    // rows with missing or unparseable fields are skipped and reported at the end; the delimiter is detected unless --delimiter is given
+    // by default the first rows are read; with --sample the whole file is read and a seeded random sample is kept
+    // several --input files are merged in order, keeping only the first row of each --dedup-key value
+    // the --filter conditions are applied before the sample or the record limit, so they pick among the matching customers
+    let dedup_key = options.dedup_key.as_deref();
+    let read_everything = options.sample.is_some() || !options.filters.is_empty();
+    let row_limit = if read_everything { usize::MAX } else { config.record_limit };
+    let (mut customers, parse_reports): (Vec<Customer>, _) =
+        load_customers_from_files(&options.inputs, row_limit, options.delimiter, dedup_key, options.strict_categories, options.strict_ranges)?;
+    if !options.filters.is_empty() {
+        let loaded = customers.len();
+        customers = filter_customers(customers, &options.filters);
+        let conditions: Vec<String> = options.filters.iter().map(|filter| format!("{}={}", filter.field, filter.value)).collect();
+        eprintln!("Filter {}: {} of {} customers remain", conditions.join(" and "), customers.len(), loaded); // stderr keeps JSON output valid
+    }
+    // exact duplicate rows would be separate, fully connected nodes: drop them with --drop-duplicates, or just report them
+    if options.drop_duplicates {
+        let dropped;
+        (customers, dropped) = dedup_customers(customers);
+        if dropped > 0 {
+            eprintln!("Dropped {} exact duplicate customers", dropped);
+        }
+    } else {
+        let duplicates = count_duplicate_customers(&customers);
+        if duplicates > 0 {
+            eprintln!("Found {} exact duplicate customers (use --drop-duplicates to drop them)", duplicates);
+        }
+    }
+    match options.sample {
+        Some(sample) => customers = sample_customers(customers, sample, options.seed),
+        None => customers.truncate(config.record_limit), // the limit applies to the merged customers
+    }
+
+    // the report goes to stdout, or to the --output file
+    let mut out = open_output(options.output.as_deref())?;
+
+    // with --graph-cache the graph saved by an earlier run on the same input and config is reloaded instead of rebuilt
+    // (its nodes point to the saved copy of the customers, which is then used in their place)
+    let fingerprint = graph_fingerprint(&customers, &config);
+    let cached = options.graph_cache.as_deref().and_then(|path| load_graph(path).ok()).filter(|saved| saved.fingerprint == fingerprint);
+    let customers: &[Customer] = match &cached {
+        Some(saved) => &saved.customers,
+        None => &customers,
+    };
+    // with --dry-run the input is only checked: the data-quality report is written and the graph is never built
+    let build = || -> Result<_, Box<dyn Error>> {
+        Ok(match (&cached, options.graph_cache.as_deref()) {
+            (Some(saved), Some(path)) => {
+                eprintln!("Reusing the graph saved in {}", path.display());
+                saved.graph()
+            }
+            (None, Some(path)) => {
+                let graph = build_graph(customers, &config);
+                save_graph(path, &SavedGraph::from_graph(&graph, fingerprint))?;
+                graph
+            }
+            _ => build_graph(customers, &config),
         })
-        .collect::<Result<_, _>>()?;
+    };
+    let graph = match dry_run_or_build(options.dry_run, customers, &parse_reports, &mut out, build)? {
+        Some(graph) => graph?,
+        None => {
+            out.flush()?;
+            print_parse_reports(&parse_reports);
+            return Ok(());
+        }
+    };
+
+    // Compute centrality and the shared characteristics of the high centrality nodes of each group
+    let result = run_analysis_on_graph(customers, graph, &config)?; // fails with a clear message on an empty or single-customer input
+    let graph = &result.graph;
+    // end of every output format: the parse problems, then with --interactive the queries about the graph (answered on stdout)
+    let finish = |out: &mut Box<dyn Write>| -> Result<(), Box<dyn Error>> {
+        out.flush()?;
+        print_parse_reports(&parse_reports);
+        if options.interactive {
+            let centrality: HashMap<NodeIndex, f64> = result.churn.centrality.iter().chain(&result.not_churn.centrality)
+                .map(|(&node, &value)| (node, value)).collect();
+            run_interactive(graph, customers, &centrality, &config.similarity, std::io::stdin().lock(), &mut std::io::stdout().lock())?;
+        }
+        Ok(())
+    };
+
+    // Report the size and density of the graph, then how fragmented it is, before interpreting centrality
+    if options.format == OutputFormat::Text {
+        let stats = graph_stats(graph);
+        writeln!(out, "Graph: {} customers, {} edges, density {:.3}", stats.node_count, stats.edge_count, stats.density)?;
+        let components = connected_components(graph);
+        let largest_component = components.first().map_or(0, |component| component.len());
+        writeln!(out, "Connected components: {} (largest: {} of {} customers)", components.len(), largest_component, customers.len())?;
+        if let Some(min_component_size) = config.min_component_size {
+            writeln!(out, "Dropped {} customers in components of fewer than {} customers", result.dropped_nodes.len(), min_component_size)?;
+        }
+        // how spread out the largest component is (a dense graph gives values close to 1)
+        let distances = distance_matrix(graph); // one traversal for both metrics
+        writeln!(out, "Largest component: average shortest path {:.3}, diameter {}", average_path_length_from(graph, &distances), diameter_from(graph, &distances))?;
+
+        let nodes_with = |status: ChurnStatus| graph.node_indices().filter(|&node| graph[node].churn_status == status).collect::<Vec<NodeIndex>>();
+        let (not_churn_nodes, churn_nodes) = (nodes_with(ChurnStatus::Existing), nodes_with(ChurnStatus::Attrited));
+        // Customers connected to no one are left out of the centrality report, show how many of each group that is
+        let isolated = isolated_nodes(graph);
+        for (label, group_nodes) in [("churn", &churn_nodes), ("not churn", &not_churn_nodes)] {
+            let group_isolated: Vec<String> = isolated.iter().filter(|node| group_nodes.contains(node)).map(|node| node.index().to_string()).collect();
+            write!(out, "Isolated customers - {}: {} of {}", label, group_isolated.len(), group_nodes.len())?;
+            if !group_isolated.is_empty() {
+                let shown = group_isolated.len().min(MAX_LISTED_NODES);
+                write!(out, " ({}{})", group_isolated[..shown].join(", "), if shown < group_isolated.len() { ", ..." } else { "" })?;
+            }
+            writeln!(out)?;
+        }
+
+        // Compare how tightly each churn group clusters in the graph
+        writeln!(out, "Average clustering coefficient - churn: {:.3}, not churn: {:.3}",
+            average_clustering_coefficient_of(graph, &churn_nodes), average_clustering_coefficient_of(graph, &not_churn_nodes))?;
+        // how well the churn / not churn split follows the structure of the graph (near 0: no better than a random split)
+        // customers with an unknown churn status form a third group
+        let churn_partition: HashMap<NodeIndex, usize> = graph.node_indices()
+            .map(|node| (node, graph[node].churn_status as usize)).collect();
+        writeln!(out, "Modularity of the churn partition: {:.3}", modularity(graph, &churn_partition))?;
+        // whether customers connect preferentially to customers of the same churn status (positive: churned customers cluster)
+        writeln!(out, "Churn assortativity: {:.3}", churn_assortativity(graph, customers))?;
+        // customers whose edges cross most to the other churn group: candidates for intervention
+        let known_partition: HashMap<NodeIndex, usize> = churn_partition.into_iter()
+            .filter(|&(node, _)| graph[node].churn_status != ChurnStatus::Unknown).collect();
+        let bridges: Vec<String> = bridging_nodes(graph, &known_partition).iter().take(5)
+            .map(|score| format!("{} ({}%, {} of {} edges)", score.node.index(), format_percentage(score.fraction * 100.0, config.precision), score.cross_edges, score.degree)).collect();
+        if !bridges.is_empty() {
+            writeln!(out, "Most bridging customers (share of edges to the other churn group): {}", bridges.join(", "))?;
+        }
+
+        // Show how connected the customers are, to help pick a sensible neighbor threshold
+        write_degree_histogram(&mut out, &degree_histogram(graph))?;
+        writeln!(out)?;
+
+        // Show which attributes the connected customers share most often, i.e. what drives the edges
+        let mut rates: Vec<(String, f64)> = edge_attribute_rates(graph, &config.similarity).into_iter().collect();
+        rates.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        writeln!(out, "Share of edges with each attribute in common:")?;
+        for (attribute, rate) in rates {
+            writeln!(out, "  {}: {}%", attribute, format_percentage(rate * 100.0, config.precision))?;
+        }
+        writeln!(out)?;
+
+        // Dump the raw neighbor lists to check determine_neighbor by hand (off by default, it's one line per customer)
+        if options.debug {
+            writeln!(out, "Adjacency list:")?;
+            write_adjacency_list(graph, &mut out)?;
+            writeln!(out)?;
+        }
+    }
+
+    // Emit both groups as a single JSON document instead of the text output
+    if options.format == OutputFormat::Json {
+        writeln!(out, "{}", to_json(&analysis_report(&result))?)?;
+        return finish(&mut out);
+    }
 
-    let graph = construct_graph(&customers);
+    // Emit every high centrality customer with its neighbors and the traits shared with each, for visualizations
+    if options.format == OutputFormat::Tree {
+        let trees = TreeReport {
+            churn: group_trees(&result.churn, customers, graph, config.node_order, &config.similarity),
+            not_churn: group_trees(&result.not_churn, customers, graph, config.node_order, &config.similarity),
+        };
+        writeln!(out, "{}", serde_json::to_string_pretty(&trees)?)?;
+        return finish(&mut out);
+    }
 
-    // Splitting customers into two groups: churned customers and customers who haven't churned (churn=stop using card)
-    let (not_churn_customers, churn_customers): (Vec<_>, Vec<_>) =
-        customers.iter().cloned().partition(|customer| customer.churn_status == "Existing Customer");
+    // Stream one JSON line per high centrality customer (churned customers first) instead of the text output
+    if options.format == OutputFormat::JsonLines {
+        for group in [&result.churn, &result.not_churn] {
+            write_profiles_jsonl(&mut out, group, customers, graph, config.top_n, &config.similarity, config.node_order)?;
+        }
+        return finish(&mut out);
+    }
 
-    let churn_centrality = calculate_centrality(&graph, &churn_customers); // Calculate centrality for churned customers
-    let not_churn_centrality = calculate_centrality(&graph, &not_churn_customers);  // Calculate centrality for not churned customers
+    // Describe each group's centrality distribution, to help pick the threshold factor
+    for (label, group) in [("churn", &result.churn), ("not churn", &result.not_churn)] {
+        let stats = centrality_stats(&group.centrality);
+        writeln!(out, "Centrality - {}: min {:.4}, max {:.4}, mean {:.4}, median {:.4}, std dev {:.4}", label, stats.min, stats.max, stats.mean, stats.median, stats.std_dev)?;
+    }
 
-    let churn_high_centrality_nodes = identify_high_centrality_nodes(&churn_centrality, 1.1); // Identify high centrality nodes for churned customers
-    let not_churn_high_centrality_nodes = identify_high_centrality_nodes(&not_churn_centrality, 1.1);// Identify high centrality nodes for churned customers
+    // Warn when the threshold factor selects (almost) none or nearly all of a group's nodes, which makes its profile meaningless
+    // (not with --top-nodes or --most-central, which cap the selection on purpose)
+    for (label, group) in [("churn", &result.churn), ("not churn", &result.not_churn)].into_iter().filter(|_| config.top_nodes.is_none() && config.most_central.is_none()) {
+        if let Some(warning) = threshold_warning(group.high_centrality_nodes.len(), group.centrality.len(), config.threshold_factor) {
+            eprintln!("Warning ({}): {}", label, warning); // stderr keeps the report itself clean
+        }
+    }
 
-    // Print high centrality nodes for churned customers and the top 4 shared characteristics between those nodes and their neighbors 
-    println!("Churn High Centrality Nodes");
-    print_top_shared_characteristics(&churn_high_centrality_nodes, &churn_customers, &graph);
-    // Print high centrality nodes for not churned customers and the top 4 shared characteristics between those nodes and their neighbors 
-    println!("Not Churn High Centrality Nodes:");
-    print_top_shared_characteristics(&not_churn_high_centrality_nodes, &not_churn_customers, &graph);
+    // Report how many nodes the threshold factor (or --most-central) selected in each group
+    let selection = match config.most_central {
+        Some(k) => format!("(the {} most central)", k),
+        None => format!("at threshold factor {} ({:?})", config.threshold_factor, config.threshold_mode),
+    };
+    writeln!(out, "High centrality nodes {}: churn {} of {}, not churn {} of {}", selection,
+        result.churn.high_centrality_nodes.len(), result.churn.customers.len(),
+        result.not_churn.high_centrality_nodes.len(), result.not_churn.customers.len())?;
 
+    // Headline insight of each group: the characteristic its high centrality customers share the most with their neighbors
+    for (label, group) in [("churned", &result.churn), ("not churned", &result.not_churn)] {
+        match group.summary.headline() {
+            Some((category, characteristic)) => writeln!(out, "The most prevalent shared characteristic among {} customers is {}: {} ({} times, {}% of all shared characteristics)",
+                label, category.name, characteristic.name, characteristic.count, format_percentage(characteristic.count as f64 / group.summary.total_count as f64 * 100.0, config.precision))?,
+            None => writeln!(out, "No shared characteristic among {} high centrality customers", label)?,
+        }
+        // and the characteristic the high centrality customers share the most with each other
+        if let Some((category, characteristic)) = group.mutual_summary.headline() {
+            writeln!(out, "  Among each other, {} high centrality customers most often share {}: {} ({} of {} pairs)",
+                label, category.name, characteristic.name, characteristic.count, group.high_centrality_nodes.len() * group.high_centrality_nodes.len().saturating_sub(1) / 2)?;
+        }
+    }
+    writeln!(out)?;
+
+    // Print high centrality nodes for churned customers and the top N shared characteristics between those nodes and their neighbors 
+    // with --quiet only the counts of each summary are printed
+    // with --sort-by-centrality each high centrality node is listed first, the most central first
+    let shown_summary = |group: &GroupAnalysis| match options.collapse_below { // long tails of rare characteristics shown as one "Other (rare)" entry
+        Some(threshold) => group.summary.collapse_rare(threshold),
+        None => group.summary.clone(),
+    };
+    let write_group_summary = |group: &GroupAnalysis, out: &mut Box<dyn Write>| {
+        if config.node_order == NodeOrder::Centrality {
+            write_node_characteristics(&group.high_centrality_nodes, &group.centrality, customers, graph, config.top_n, &config.similarity, out)?;
+        }
+        let summary = shown_summary(group);
+        if options.quiet { write_summary_counts(&summary, out) } else { write_summary_with(&summary, config.precision, out) }
+    };
+    if options.side_by_side { // both groups in one table, the most distinguishing characteristics first
+        let rows = compare_prevalence(&category_counts(&shown_summary(&result.churn)), &category_counts(&shown_summary(&result.not_churn)));
+        write_prevalence_table(&mut out, &rows, config.precision)?;
+    } else {
+        writeln!(out, "Churn High Centrality Nodes")?;
+        write_group_summary(&result.churn, &mut out)?;
+        // Print high centrality nodes for not churned customers and the top N shared characteristics between those nodes and their neighbors 
+        writeln!(out, "Not Churn High Centrality Nodes:")?;
+        write_group_summary(&result.not_churn, &mut out)?;
+    }
+
+    // with --distinguishing, the attributes on which the high centrality customers most consistently differ from their neighbors
+    if options.distinguishing {
+        for (label, group) in [("Churn", &result.churn), ("Not churn", &result.not_churn)] {
+            writeln!(out, "{} high centrality customers most often differ from their neighbors on:", label)?;
+            for characteristic in compute_distinguishing_characteristics(&group.high_centrality_nodes, customers, graph, &config.similarity) {
+                writeln!(out, "  {}: {} pairs ({}%)", characteristic.attribute, characteristic.count, format_percentage(characteristic.percentage, config.precision))?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    // with --bootstrap, how certain each characteristic's share of the shared characteristics is
+    if let Some(resamples) = config.bootstrap_resamples {
+        for (label, group) in [("Churn", &result.churn), ("Not churn", &result.not_churn)] {
+            writeln!(out, "{} prevalence with 95% bootstrap intervals ({} resamples):", label, resamples)?;
+            for interval in &group.prevalence_intervals {
+                let percentage = |value: f64| format_percentage(value, config.precision);
+                writeln!(out, "  {}: {}% ({}% - {}%)", interval.characteristic, percentage(interval.estimate), percentage(interval.lower), percentage(interval.upper))?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    // Compare the two groups: which categories are distributed differently among churned and not churned customers
+    if result.churn.summary.total_count == 0 && result.not_churn.summary.total_count == 0 { // no shared characteristics in either group
+        writeln!(out, "No high centrality nodes to compare.")?;
+    } else {
+        writeln!(out, "Categories ranked by how much they differ between churned and not churned customers (chi-square):")?;
+        for comparison in chi_square_by_category(&category_counts(&result.churn.summary), &category_counts(&result.not_churn.summary)) {
+            writeln!(out, "  {}: {:.2} (df {}){}", comparison.category, comparison.statistic, comparison.degrees_of_freedom,
+                if comparison.significant { " - significant at 5%" } else { "" })?;
+        }
+    }
+
+    finish(&mut out)
+}
+
+// Function to write the side-by-side table of the characteristics' share within their category among the churned
+// and not churned high centrality customers, with the difference in percentage points
+fn write_prevalence_table(out: &mut impl Write, rows: &[PrevalenceComparison], precision: usize) -> std::io::Result<()> {
+    let labels: Vec<String> = rows.iter().map(|row| format!("{}: {}", row.category, row.characteristic)).collect();
+    let width = labels.iter().map(String::len).chain(std::iter::once("Characteristic".len())).max().unwrap_or(0);
+    let number_width = precision + 10; // "-100." and the decimals, plus the "%" and some space
+    writeln!(out, "Shared characteristics of the high centrality customers, churned vs not churned (share within the category):")?;
+    writeln!(out, "  {:<width$} {:>number_width$} {:>number_width$} {:>number_width$}", "Characteristic", "Churn", "Not churn", "Difference")?;
+    for (label, row) in labels.iter().zip(rows) {
+        let difference = format!("{}{}", if row.difference >= 0.0 { "+" } else { "" }, format_percentage(row.difference, precision));
+        writeln!(out, "  {:<width$} {:>number_width$} {:>number_width$} {:>number_width$}", label,
+            format!("{}%", format_percentage(row.churn, precision)), format!("{}%", format_percentage(row.not_churn, precision)), difference)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Function to write the degree distribution as a text histogram
+// degrees are grouped into at most 20 ranges and the longest bar is 50 characters wide
+fn write_degree_histogram(out: &mut impl Write, histogram: &BTreeMap<usize, usize>) -> std::io::Result<()> {
+    let max_degree = match histogram.keys().next_back() {
+        Some(&degree) => degree,
+        None => return Ok(()), // empty graph
+    };
+    let bin_width = max_degree / 20 + 1;
+    let mut bins: BTreeMap<usize, usize> = BTreeMap::new(); // first degree of the range -> number of nodes
+    for (&degree, &count) in histogram {
+        *bins.entry(degree / bin_width * bin_width).or_insert(0) += count;
+    }
+    let largest_bin = bins.values().copied().max().unwrap_or(1);
+    writeln!(out, "Degree distribution (degree: number of customers):")?;
+    for (&start, &count) in &bins {
+        let label = if bin_width == 1 { start.to_string() } else { format!("{}-{}", start, start + bin_width - 1) };
+        let bar = "#".repeat((count * 50).div_ceil(largest_bin));
+        writeln!(out, "  {:>9}: {:<50} {}", label, bar, count)?;
+    }
     Ok(())
 }
 
+// Function to report the rows that were skipped while reading the CSV file (on stderr, so JSON output stays valid)
+fn print_parse_report(report: &ParseReport) {
+    if report.duplicate_rows > 0 {
+        eprintln!("Dropped {} duplicate rows", report.duplicate_rows);
+    }
+    for (field, count) in &report.clamped_fields {
+        eprintln!("Clamped {} out of range values of {} into their valid range", count, field);
+    }
+    for (field, count) in &report.missing_values {
+        eprintln!("Kept {} rows without a value of {} (treated as unknown)", count, field);
+    }
+    if report.skipped_rows == 0 {
+        return;
+    }
+    eprintln!("Skipped {} of {} rows with problems:", report.skipped_rows, report.rows_read);
+    for (field, count) in &report.missing_fields {
+        eprintln!("  missing {}: {} rows", field, count);
+    }
+    for (field, count) in &report.unparseable_fields {
+        eprintln!("  unparseable {}: {} rows", field, count);
+    }
+    for (field, count) in &report.unknown_categories {
+        eprintln!("  unknown category in {}: {} rows", field, count);
+    }
+    for (field, count) in &report.out_of_range_fields {
+        eprintln!("  out of range {}: {} rows", field, count);
+    }
+}
 
+// Function to print the parse report of each input file that had problems, under the file's name when there are several files
+fn print_parse_reports(reports: &[(PathBuf, ParseReport)]) {
+    for (path, report) in reports {
+        if report.skipped_rows == 0 && report.duplicate_rows == 0 && report.clamped_fields.is_empty() && report.missing_values.is_empty() {
+            continue;
+        }
+        eprintln!();
+        if reports.len() > 1 {
+            eprintln!("{}:", path.display());
+        }
+        print_parse_report(report);
+    }
+}
@@ -0,0 +1,655 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use crate::customer::{Customer, CharacteristicSummary, SimilarityConfig, compute_shared_characteristic_summary, find_top_shared_characteristics, neighbor_details};
+use crate::analysis::{AnalysisResult, GroupAnalysis};
+use crate::graph_utils::{customer_for_node, node_customer_indices, EdgeWeight, NodeOrder};
+use crate::input::ParseReport;
+
+// struct holding the analysis results of the churned and not churned customers, serialized for the JSON output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalysisReport {
+    pub churn: GroupReport,
+    pub not_churn: GroupReport,
+}
+
+// struct holding the results of one group of customers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupReport {
+    pub high_centrality_nodes: Vec<usize>, // indices of the high centrality nodes (by index, or by descending centrality with NodeOrder::Centrality)
+    pub summary: CharacteristicSummary, // ranked shared characteristic categories with their counts and percentages
+}
+
+// Function to build the report of one group from its high centrality nodes
+pub fn group_report<E>(
+    high_centrality_nodes: &[NodeIndex], // slice of NodeIndex representing the group's high centrality nodes
+    customers: &[Customer], // Slice of Customer representing the group's customers
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    top_n: usize, // number of shared characteristics kept per high centrality node
+    similarity: &SimilarityConfig, // the config the graph was built with, so the summary matches the edges
+) -> GroupReport {
+    let mut node_indices: Vec<usize> = high_centrality_nodes.iter().map(|node| node.index()).collect();
+    node_indices.sort();
+    GroupReport {
+        high_centrality_nodes: node_indices,
+        summary: compute_shared_characteristic_summary(high_centrality_nodes, customers, graph, top_n, similarity),
+    }
+}
+
+// Function to build the report from the results of run_analysis (reusing the computed summaries)
+pub fn analysis_report(result: &AnalysisResult) -> AnalysisReport {
+    AnalysisReport {
+        churn: group_report_from_analysis(&result.churn),
+        not_churn: group_report_from_analysis(&result.not_churn),
+    }
+}
+
+// Function to build the report of one analyzed group
+fn group_report_from_analysis(group: &GroupAnalysis) -> GroupReport {
+    let node_indices: Vec<usize> = group.high_centrality_nodes.iter().map(|node| node.index()).collect(); // already in the config's node order
+    GroupReport { high_centrality_nodes: node_indices, summary: group.summary.clone() }
+}
+
+// Function to serialize the report as a pretty-printed JSON document
+pub fn to_json(report: &AnalysisReport) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(report)
+}
+
+// struct for one high centrality customer, written as one line of the JSON-lines output (see write_profiles_jsonl)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CustomerProfile {
+    pub index: usize, // node index of the customer
+    pub churn_status: String,
+    pub centrality: f64,
+    pub shared_traits: Vec<SharedTraitCount>, // top shared characteristics with the customer's neighbors, most shared first
+}
+
+// struct for one shared characteristic of a profile, with the number of neighbors it is shared with
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SharedTraitCount {
+    pub characteristic: String,
+    pub count: usize,
+}
+
+// Function to stream the profile of every high centrality customer of a group as JSON lines (one object per line),
+// in the given order (node index or descending centrality), flushing after each line so a pipeline reading the output sees the profiles as they are computed
+pub fn write_profiles_jsonl<E>(
+    writer: &mut impl Write, // destination of the lines (stdout, file, buffer...)
+    group: &GroupAnalysis, // the analyzed group whose high centrality nodes are profiled
+    customers: &[Customer], // Slice of Customer the graph was built from
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    top_n: usize, // number of shared characteristics kept per customer
+    similarity: &SimilarityConfig, // bucket specs used to compare the numeric fields
+    order: NodeOrder, // order of the lines
+) -> std::io::Result<()> {
+    let node_customers = node_customer_indices(graph, customers);
+    for node in order.sort(&group.high_centrality_nodes, &group.centrality) {
+        let Some(customer) = customer_for_node(&node_customers, customers, node) else {
+            continue; // not one of the customers
+        };
+        let profile = CustomerProfile {
+            index: node.index(),
+            churn_status: customer.churn_status.to_string(),
+            centrality: group.centrality.get(&node).copied().unwrap_or(0.0),
+            shared_traits: find_top_shared_characteristics(graph, node, customers, top_n, similarity).into_iter()
+                .map(|(characteristic, count)| SharedTraitCount { characteristic, count })
+                .collect(),
+        };
+        serde_json::to_writer(&mut *writer, &profile)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// struct holding the neighbor trees of the high centrality customers of both groups, serialized for the tree output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeReport {
+    pub churn: Vec<NodeTree>,
+    pub not_churn: Vec<NodeTree>,
+}
+
+// struct for one high centrality customer with every neighbor and the traits shared with it (not aggregated like CustomerProfile)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeTree {
+    pub index: usize, // node index of the customer
+    pub centrality: f64,
+    pub profile: Customer,
+    pub neighbors: Vec<NeighborTree>, // by node index
+}
+
+// struct for one neighbor of a NodeTree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NeighborTree {
+    pub index: usize, // node index of the neighbor
+    pub profile: Customer,
+    pub shared_traits: Vec<String>, // every trait the neighbor shares with the high centrality customer
+}
+
+// Function to build the tree of every high centrality customer of a group from neighbor_details, in the given order
+// (node index or descending centrality); nodes outside the customers slice are skipped
+pub fn group_trees<E>(
+    group: &GroupAnalysis, // the analyzed group whose high centrality nodes are the roots
+    customers: &[Customer], // Slice of Customer the graph was built from
+    graph: &Graph<&Customer, E, Undirected>, // Reference to the undirected graph of customers
+    order: NodeOrder, // order of the trees
+    similarity: &SimilarityConfig, // the config the graph was built with, so the traits match the edges
+) -> Vec<NodeTree> {
+    let node_customers = node_customer_indices(graph, customers);
+    order.sort(&group.high_centrality_nodes, &group.centrality).into_iter()
+        .filter_map(|node| {
+            let customer = customer_for_node(&node_customers, customers, node)?;
+            let neighbors = neighbor_details(graph, node, customers, similarity).into_iter()
+                .filter_map(|(neighbor, shared_traits)| Some(NeighborTree {
+                    index: neighbor.index(),
+                    profile: customer_for_node(&node_customers, customers, neighbor)?.clone(),
+                    shared_traits,
+                }))
+                .collect();
+            Some(NodeTree { index: node.index(), centrality: group.centrality.get(&node).copied().unwrap_or(0.0), profile: customer.clone(), neighbors })
+        })
+        .collect()
+}
+
+// Function to write the per-customer centrality values to a CSV file (columns: index, churn_status, centrality)
+// rows are sorted by centrality (descending), ties by index; nodes outside the customers slice are skipped
+pub fn write_centrality_csv<E>(
+    path: &Path,
+    centrality: &HashMap<NodeIndex, f64>,
+    customers: &[Customer], // Slice of Customer the graph was built from
+    graph: &Graph<&Customer, E, Undirected>, // the graph the centrality was computed on, to find the customer of each node
+) -> Result<(), csv::Error> {
+    let node_customers = node_customer_indices(graph, customers);
+    let mut rows: Vec<(usize, &Customer, f64)> = centrality.iter()
+        .filter_map(|(&node, &value)| customer_for_node(&node_customers, customers, node).map(|customer| (node.index(), customer, value)))
+        .collect();
+    rows.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["index", "churn_status", "centrality"])?;
+    for (index, customer, value) in rows {
+        writer.write_record([index.to_string(), customer.churn_status.to_string(), value.to_string()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Function to write the adjacency matrix of the graph to a CSV file, for matrix-based analysis in other tools
+// the first row and column hold the node indices; a cell is 1 if the two customers are connected and 0 otherwise,
+// or with weighted the weight of the edge (the number of shared characteristics for the customer graph).
+// The file has N x N cells, so it grows quadratically: about 200 MB for the 10,127 customers of BankChurners.csv
+pub fn write_adjacency_matrix_csv<N, E: EdgeWeight>(path: &Path, graph: &Graph<N, E, Undirected>, weighted: bool) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    let indices: Vec<String> = graph.node_indices().map(|node| node.index().to_string()).collect();
+    writer.write_record(std::iter::once(String::new()).chain(indices.iter().cloned()))?;
+    for node in graph.node_indices() { // one row at a time, so only the output is quadratic, not the memory
+        let mut row = vec![0.0; graph.node_count()];
+        for edge in graph.edges(node) {
+            let neighbor = if edge.source() == node { edge.target() } else { edge.source() };
+            row[neighbor.index()] = if weighted { edge.weight().weight() } else { 1.0 };
+        }
+        writer.write_record(std::iter::once(indices[node.index()].clone()).chain(row.iter().map(|cell| cell.to_string())))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// struct holding what a dry run found out about the input, without building the graph (see data_quality_report)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataQualityReport {
+    pub rows_read: usize, // rows read from all the input files (valid or not)
+    pub skipped_rows: usize, // rows that couldn't be turned into a customer
+    pub duplicate_rows: usize, // valid rows dropped for a repeated dedup key
+    pub row_problems: BTreeMap<String, usize>, // column -> number of rows skipped because of it (missing, unparseable, unknown or out of range)
+    pub customers: usize, // customers that would be analyzed (after the filters and the sample)
+    pub churn_status: BTreeMap<String, usize>, // churn status -> number of customers
+    pub categories: BTreeMap<String, BTreeMap<String, usize>>, // categorical column -> value -> number of customers
+    pub numeric_fields: Vec<NumericFieldStats>, // in column order
+}
+
+// struct holding the distribution of one numeric column over the customers (min, mean and max are None if every value is missing)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumericFieldStats {
+    pub column: String,
+    pub missing: usize, // customers without a value
+    pub min: Option<f64>,
+    pub mean: Option<f64>,
+    pub max: Option<f64>,
+}
+
+// accessor of one numeric field of a customer, as f64
+type NumericField = fn(&Customer) -> Option<f64>;
+
+// Function to summarize the parse reports of the input files and the value distributions of the loaded customers
+pub fn data_quality_report(customers: &[Customer], reports: &[(PathBuf, ParseReport)]) -> DataQualityReport {
+    let mut row_problems = BTreeMap::new();
+    for (_, report) in reports {
+        for problems in [&report.missing_fields, &report.unparseable_fields, &report.unknown_categories, &report.out_of_range_fields] {
+            for (column, count) in problems {
+                *row_problems.entry(column.clone()).or_insert(0) += count;
+            }
+        }
+    }
+    let mut churn_status = BTreeMap::new();
+    let mut categories: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    for customer in customers {
+        *churn_status.entry(customer.churn_status.to_string()).or_insert(0) += 1;
+        let encoding = &customer.one_hot_encoding;
+        for (column, value) in [("Education_Level", &encoding.education_level), ("Marital_Status", &encoding.marital_status),
+            ("Income_Category", &encoding.income_range), ("Card_Category", &encoding.card_type)] {
+            *categories.entry(column.to_string()).or_default().entry(value.clone()).or_insert(0) += 1;
+        }
+    }
+    let numeric: [(&str, NumericField); 8] = [
+        ("Customer_Age", |customer| customer.age.map(f64::from)),
+        ("Months_on_book", |customer| customer.mon_w_bank.map(f64::from)),
+        ("Total_Relationship_Count", |customer| customer.num_product_purchased.map(f64::from)),
+        ("Months_Inactive_12_mon", |customer| customer.mon_inactive.map(f64::from)),
+        ("Contacts_Count_12_mon", |customer| customer.num_contact.map(f64::from)),
+        ("Total_Trans_Amt", |customer| customer.transactions_amount.map(f64::from)),
+        ("Total_Trans_Ct", |customer| customer.num_transctions.map(f64::from)),
+        ("Avg_Utilization_Ratio", |customer| customer.avg_card_utilize),
+    ];
+    let numeric_fields = numeric.iter().map(|(column, value)| {
+        let values: Vec<f64> = customers.iter().filter_map(value).collect();
+        let known = !values.is_empty();
+        NumericFieldStats {
+            column: column.to_string(),
+            missing: customers.len() - values.len(),
+            min: known.then(|| values.iter().copied().fold(f64::INFINITY, f64::min)),
+            mean: known.then(|| values.iter().sum::<f64>() / values.len() as f64),
+            max: known.then(|| values.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        }
+    }).collect();
+    DataQualityReport {
+        rows_read: reports.iter().map(|(_, report)| report.rows_read).sum(),
+        skipped_rows: reports.iter().map(|(_, report)| report.skipped_rows).sum(),
+        duplicate_rows: reports.iter().map(|(_, report)| report.duplicate_rows).sum(),
+        row_problems,
+        customers: customers.len(),
+        churn_status,
+        categories,
+        numeric_fields,
+    }
+}
+
+// Function to write the data-quality report as text
+pub fn write_data_quality_report(report: &DataQualityReport, writer: &mut impl Write) -> std::io::Result<()> {
+    let counts = |counts: &BTreeMap<String, usize>| counts.iter().map(|(name, count)| format!("{} {}", name, count)).collect::<Vec<_>>().join(", ");
+    writeln!(writer, "Data quality report (dry run, the graph wasn't built):")?;
+    writeln!(writer, "  Rows read: {}, skipped: {}, duplicates: {}", report.rows_read, report.skipped_rows, report.duplicate_rows)?;
+    if !report.row_problems.is_empty() {
+        writeln!(writer, "  Rows skipped by column: {}", counts(&report.row_problems))?;
+    }
+    writeln!(writer, "  Customers: {} ({})", report.customers, counts(&report.churn_status))?;
+    for (column, values) in &report.categories {
+        writeln!(writer, "  {}: {}", column, counts(values))?;
+    }
+    for field in &report.numeric_fields {
+        match (field.min, field.mean, field.max) {
+            (Some(min), Some(mean), Some(max)) => write!(writer, "  {}: min {}, mean {:.2}, max {}", field.column, min, mean, max)?,
+            _ => write!(writer, "  {}: no values", field.column)?,
+        }
+        if field.missing > 0 {
+            write!(writer, " ({} missing)", field.missing)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+// Function to stop before the expensive graph construction in a dry run: writes the data-quality report and returns None
+// without calling build; otherwise returns what build returns (the graph) and writes nothing
+pub fn dry_run_or_build<T>(
+    dry_run: bool,
+    customers: &[Customer], // the loaded customers
+    reports: &[(PathBuf, ParseReport)], // parse report of each input file
+    writer: &mut impl Write, // destination of the data-quality report
+    build: impl FnOnce() -> T, // builds the graph
+) -> std::io::Result<Option<T>> {
+    if dry_run {
+        write_data_quality_report(&data_quality_report(customers, reports), writer)?;
+        return Ok(None);
+    }
+    Ok(Some(build()))
+}
+
+// Function to answer queries about the built graph (--interactive): for every customer index read from input, write the customer,
+// its centrality (none for customers outside both churn groups) and its neighbors with the traits they share (see neighbor_details).
+// Stops at the end of the input or on "q" / "quit"; blank lines are ignored and anything else gets an error line
+pub fn run_interactive<E>(
+    graph: &Graph<&Customer, E, Undirected>,
+    customers: &[Customer], // Slice of Customer the graph was built from
+    centrality: &HashMap<NodeIndex, f64>, // centrality per node, e.g. of both churn groups
+    similarity: &SimilarityConfig, // the config the graph was built with, so the traits match the edges
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<()> {
+    let node_customers = node_customer_indices(graph, customers);
+    writeln!(output, "Enter a customer index (q to quit):")?;
+    for line in input.lines() {
+        let line = line?;
+        let query = line.trim();
+        match query {
+            "" => continue,
+            "q" | "quit" => break,
+            _ => {}
+        }
+        let customer = query.parse::<usize>().ok().map(NodeIndex::new)
+            .and_then(|node| customer_for_node(&node_customers, customers, node).map(|customer| (node, customer)));
+        let Some((node, customer)) = customer else {
+            writeln!(output, "Not a customer index: '{}' (0 to {})", query, customers.len().saturating_sub(1))?;
+            continue;
+        };
+        writeln!(output, "Customer {}: {}", node.index(), customer)?;
+        match centrality.get(&node) {
+            Some(value) => writeln!(output, "Centrality: {:.4}", value)?,
+            None => writeln!(output, "Centrality: none")?,
+        }
+        let neighbors = neighbor_details(graph, node, customers, similarity);
+        writeln!(output, "Neighbors: {}", neighbors.len())?;
+        for (neighbor, traits) in neighbors {
+            writeln!(output, "  {}: {}", neighbor.index(), traits.join(", "))?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+// Function to open the destination of the report (--output): the given file, creating its parent directories if needed, or stdout if None
+pub fn open_output(path: Option<&Path>) -> std::io::Result<Box<dyn Write>> {
+    let Some(path) = path else {
+        return Ok(Box::new(std::io::stdout()));
+    };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::tests::{create_sample_customer1, create_sample_customer2, create_sample_customer3};
+    use crate::customer::{compute_mutual_characteristic_summary, shared_traits, write_summary};
+    use crate::graph_utils::{construct_graph, calculate_centrality};
+
+    // test that a scripted session prints the customer, centrality and neighbors of a known index, and rejects bad input
+    #[test]
+    fn test_run_interactive() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), customer1_twin, create_sample_customer2()];
+        let graph = construct_graph(&customers);
+        let centrality = HashMap::from([(NodeIndex::new(0), 0.75)]);
+        let excluding_card = SimilarityConfig { excluded_attributes: ["Card Type".to_string()].into(), ..SimilarityConfig::default() };
+        let mut output = Vec::new();
+        run_interactive(&graph, &customers, &centrality, &excluding_card, "0\n\nabc\n9\nq\n2\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("Card Type")); // the traits follow the given config
+
+        let details = neighbor_details(&graph, NodeIndex::new(0), &customers, &excluding_card);
+        let mut expected = vec![
+            "Enter a customer index (q to quit):".to_string(),
+            format!("Customer 0: {}", customers[0]),
+            "Centrality: 0.7500".to_string(),
+            "Neighbors: 2".to_string(),
+        ];
+        expected.extend(details.iter().map(|(neighbor, traits)| format!("  {}: {}", neighbor.index(), traits.join(", "))));
+        expected.push("Not a customer index: 'abc' (0 to 3)".to_string());
+        expected.push("Not a customer index: '9' (0 to 3)".to_string());
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected); // nothing after "q"
+    }
+
+    // test that the JSON report holds the expected node indices, counts and percentages
+    #[test]
+    fn test_json_report() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let report = AnalysisReport {
+            churn: group_report(&[NodeIndex::new(1), NodeIndex::new(0)], &customers, &graph, 4, &SimilarityConfig::default()),
+            not_churn: group_report(&[], &customers, &graph, 4, &SimilarityConfig::default()),
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&report).unwrap()).unwrap();
+        assert_eq!(json["churn"]["high_centrality_nodes"], serde_json::json!([0, 1]));
+        assert_eq!(json["churn"]["summary"]["node_count"], 2);
+        assert_eq!(json["churn"]["summary"]["total_count"], 16); // both nodes share 4 categorical characteristics with 2 neighbors
+        let first_category = &json["churn"]["summary"]["categories"][0];
+        assert_eq!(first_category["name"], "Card Type");
+        assert_eq!(first_category["total_count"], 4);
+        assert_eq!(first_category["percentage"], 25.0);
+        assert_eq!(first_category["characteristics"][0]["name"], "Silver");
+        assert_eq!(first_category["characteristics"][0]["percentage"], 100.0);
+        assert_eq!(json["not_churn"]["high_centrality_nodes"], serde_json::json!([]));
+        assert_eq!(json["not_churn"]["summary"]["categories"], serde_json::json!([]));
+
+        // the summary follows the given config
+        let excluding_card = SimilarityConfig { excluded_attributes: ["Card Type".to_string()].into(), ..SimilarityConfig::default() };
+        let report = group_report(&[NodeIndex::new(0), NodeIndex::new(1)], &customers, &graph, 4, &excluding_card);
+        assert!(!report.summary.categories.is_empty());
+        assert!(report.summary.categories.iter().all(|category| category.name != "Card Type"));
+    }
+
+    // test that every JSON line parses on its own and has the profile keys
+    #[test]
+    fn test_write_profiles_jsonl() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin, create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let centrality = calculate_centrality(&graph);
+        let group = GroupAnalysis {
+            customers: customers.clone(),
+            centrality: centrality.clone(),
+            high_centrality_nodes: vec![NodeIndex::new(2), NodeIndex::new(0)],
+            summary: compute_shared_characteristic_summary(&[], &customers, &graph, 4, &SimilarityConfig::default()),
+            mutual_summary: compute_mutual_characteristic_summary(&[], &customers, &graph, &SimilarityConfig::default()),
+            prevalence_intervals: Vec::new(),
+        };
+
+        let mut output = Vec::new();
+        write_profiles_jsonl(&mut output, &group, &customers, &graph, 3, &SimilarityConfig::default(), NodeOrder::Index).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(output).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            let keys: Vec<&str> = line.as_object().unwrap().keys().map(String::as_str).collect();
+            assert_eq!(keys.len(), 4);
+            assert!(["index", "churn_status", "centrality", "shared_traits"].iter().all(|key| keys.contains(key)));
+            assert_eq!(line["shared_traits"].as_array().unwrap().len(), 3);
+        }
+        assert_eq!(lines[0]["index"], 0); // in node index order
+        assert_eq!(lines[0]["churn_status"], "Existing Customer");
+        assert_eq!(lines[0]["centrality"], centrality[&NodeIndex::new(0)]);
+        assert_eq!(lines[0]["shared_traits"][0]["count"], 2);
+    }
+
+    // test that the tree of a high centrality node nests its profile, its neighbors and the traits shared with each
+    #[test]
+    fn test_group_trees() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer1_twin];
+        let graph = construct_graph(&customers);
+        let group = GroupAnalysis {
+            customers: customers.clone(),
+            centrality: HashMap::from([(NodeIndex::new(0), 0.9)]),
+            high_centrality_nodes: vec![NodeIndex::new(0), NodeIndex::new(7)], // 7 isn't a node
+            summary: compute_shared_characteristic_summary(&[], &customers, &graph, 4, &SimilarityConfig::default()),
+            mutual_summary: compute_mutual_characteristic_summary(&[], &customers, &graph, &SimilarityConfig::default()),
+            prevalence_intervals: Vec::new(),
+        };
+
+        let report = TreeReport { churn: group_trees(&group, &customers, &graph, NodeOrder::Index, &SimilarityConfig::default()), not_churn: Vec::new() };
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string_pretty(&report).unwrap()).unwrap();
+        let trees = json["churn"].as_array().unwrap();
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0]["index"], 0);
+        assert_eq!(trees[0]["centrality"], 0.9);
+        assert_eq!(trees[0]["profile"], serde_json::to_value(&customers[0]).unwrap());
+        let neighbors = trees[0]["neighbors"].as_array().unwrap();
+        assert_eq!(neighbors.len(), 2);
+        for (neighbor, index) in neighbors.iter().zip([1, 2]) {
+            assert_eq!(neighbor["index"], index);
+            assert_eq!(neighbor["profile"]["age"], customers[index].age.unwrap());
+            assert_eq!(neighbor["shared_traits"], serde_json::json!(shared_traits(&customers[0], &customers[index])));
+        }
+        assert_eq!(neighbors[1]["shared_traits"].as_array().unwrap().len(), 11); // the twin differs only in the age group
+        assert_eq!(json["not_churn"], serde_json::json!([]));
+
+        // the shared traits follow the config the graph was built with
+        let excluding_card = SimilarityConfig { excluded_attributes: ["Card Type".to_string()].into(), ..SimilarityConfig::default() };
+        let trees = group_trees(&group, &customers, &graph, NodeOrder::Index, &excluding_card);
+        assert_eq!(trees[0].neighbors[1].shared_traits.len(), 10);
+        assert!(trees[0].neighbors.iter().all(|neighbor| !neighbor.shared_traits.iter().any(|characteristic| characteristic.starts_with("Card Type"))));
+    }
+
+    // test that a dry run writes the data-quality report without building the graph, and a normal run builds it silently
+    #[test]
+    fn test_dry_run_or_build() {
+        let mut customer3 = create_sample_customer3();
+        customer3.age = None;
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), customer3];
+        let report = ParseReport {
+            rows_read: 4,
+            skipped_rows: 1,
+            unparseable_fields: BTreeMap::from([("Total_Trans_Ct".to_string(), 1)]),
+            ..ParseReport::default()
+        };
+        let reports = vec![(PathBuf::from("customers.csv"), report)];
+
+        let mut built = false; // spy on the graph construction
+        let mut output = Vec::new();
+        let graph = dry_run_or_build(true, &customers, &reports, &mut output, || {
+            built = true;
+            construct_graph(&customers)
+        }).unwrap();
+        assert!(graph.is_none() && !built);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Data quality report (dry run, the graph wasn't built):\n  Rows read: 4, skipped: 1, duplicates: 0\n"));
+        assert!(output.contains("  Rows skipped by column: Total_Trans_Ct 1\n"));
+        assert!(output.contains("  Customers: 3 (Attrited Customer 2, Existing Customer 1)\n"));
+        assert!(output.contains("  Customer_Age: min 25, mean 27.50, max 30 (1 missing)\n"));
+
+        let quality = data_quality_report(&customers, &reports);
+        assert_eq!(quality.categories["Card_Category"]["Silver"], 2);
+        assert_eq!(quality.numeric_fields.len(), 8);
+
+        let mut output = Vec::new();
+        let graph = dry_run_or_build(false, &customers, &reports, &mut output, || {
+            built = true;
+            construct_graph(&customers)
+        }).unwrap();
+        assert!(built);
+        assert_eq!(graph.map(|graph| graph.node_count()), Some(3));
+        assert!(output.is_empty());
+    }
+
+    // test that the centrality CSV is written sorted by centrality and can be read back
+    #[test]
+    fn test_write_centrality_csv() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        customer1_twin.one_hot_encoding.card_type = "Blue".to_string(); // still connected to customer 1, but not to customer 2
+        customer1_twin.one_hot_encoding.income_range = "Less than $40K".to_string();
+        customer1_twin.one_hot_encoding.marital_status = "Married".to_string();
+        customer1_twin.mon_w_bank = Some(30);
+        customer1_twin.transactions_amount = Some(1200);
+        let customers = vec![create_sample_customer2(), create_sample_customer1(), customer1_twin, create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let centrality = calculate_centrality(&graph);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("centrality.csv");
+        write_centrality_csv(&path, &centrality, &customers, &graph).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["index", "churn_status", "centrality"]);
+        let rows: Vec<(usize, String, f64)> = reader.records().map(|record| {
+            let record = record.unwrap();
+            (record[0].parse().unwrap(), record[1].to_string(), record[2].parse().unwrap())
+        }).collect();
+        let indices: Vec<usize> = rows.iter().map(|row| row.0).collect();
+        assert_eq!(indices, vec![1, 0, 2, 3]); // customer 1 is in the middle of a path, the ends tie, the isolated customer is last
+        assert_eq!(rows[0].1, "Existing Customer");
+        assert_eq!(rows[1].1, "Attrited Customer");
+        for (index, _, value) in &rows {
+            assert_eq!(*value, centrality[&NodeIndex::new(*index)]);
+        }
+        assert_eq!(rows[3].2, 0.0);
+
+        // the churn status comes from the customer of each node, also once a removed node has shifted the indices
+        let mut filtered = graph.clone();
+        filtered.remove_node(NodeIndex::new(1)); // customer 3 takes index 1
+        write_centrality_csv(&path, &calculate_centrality(&filtered), &customers, &filtered).unwrap();
+        let statuses: HashMap<usize, String> = csv::Reader::from_path(&path).unwrap().records()
+            .map(|record| { let record = record.unwrap(); (record[0].parse().unwrap(), record[1].to_string()) })
+            .collect();
+        assert_eq!(statuses.len(), 3);
+        assert_eq!(statuses[&1], customers[3].churn_status.to_string());
+    }
+
+    // test that the adjacency matrix is symmetric and has a non-zero cell exactly for each edge
+    #[test]
+    fn test_write_adjacency_matrix_csv() {
+        let mut customer1_twin = create_sample_customer1();
+        customer1_twin.age = Some(41);
+        let customers = vec![create_sample_customer1(), create_sample_customer3(), create_sample_customer2(), customer1_twin];
+        let graph = construct_graph(&customers);
+        let dir = tempfile::tempdir().unwrap();
+        let read_matrix = |weighted: bool| -> Vec<Vec<String>> {
+            let path = dir.path().join("adjacency.csv");
+            write_adjacency_matrix_csv(&path, &graph, weighted).unwrap();
+            csv::ReaderBuilder::new().has_headers(false).from_path(&path).unwrap()
+                .records().map(|record| record.unwrap().iter().map(str::to_string).collect()).collect()
+        };
+
+        let matrix = read_matrix(false);
+        assert_eq!(matrix[0], ["", "0", "1", "2", "3"]);
+        assert_eq!(matrix.len(), 5);
+        for i in 0..4 {
+            assert_eq!(matrix[i + 1][0], i.to_string());
+            for j in 0..4 {
+                assert_eq!(matrix[i + 1][j + 1], matrix[j + 1][i + 1]); // symmetric
+                let connected = graph.contains_edge(NodeIndex::new(i), NodeIndex::new(j));
+                assert_eq!(matrix[i + 1][j + 1], if connected { "1" } else { "0" });
+            }
+        }
+        assert_eq!(matrix.iter().skip(1).flat_map(|row| &row[1..]).filter(|cell| *cell == "1").count(), 2 * graph.edge_count());
+
+        let weights = read_matrix(true);
+        let edge = graph.find_edge(NodeIndex::new(0), NodeIndex::new(2)).unwrap();
+        assert_eq!(weights[1][3], graph[edge].to_string());
+    }
+
+    // test that a report written through open_output to a file in missing directories is what would go to stdout
+    #[test]
+    fn test_open_output_file_matches_stdout() {
+        let customers = vec![create_sample_customer1(), create_sample_customer2(), create_sample_customer3()];
+        let graph = construct_graph(&customers);
+        let report = group_report(&[NodeIndex::new(0), NodeIndex::new(1)], &customers, &graph, 4, &SimilarityConfig::default());
+        let write_report = |mut writer: &mut dyn Write| -> std::io::Result<()> {
+            writeln!(writer, "Churn High Centrality Nodes")?; // the text output
+            write_summary(&report.summary, &mut writer)?;
+            writeln!(writer, "{}", serde_json::to_string_pretty(&report).unwrap()) // and the JSON output
+        };
+        let mut stdout_output = Vec::new(); // what the report writes to stdout without --output
+        write_report(&mut stdout_output).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports").join("march").join("report.txt");
+        let mut output = open_output(Some(&path)).unwrap();
+        write_report(&mut output).unwrap();
+        drop(output); // flushes the file
+        assert_eq!(std::fs::read(&path).unwrap(), stdout_output);
+        assert!(!stdout_output.is_empty());
+    }
+}
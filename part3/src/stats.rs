@@ -0,0 +1,188 @@
+use std::collections::{BTreeSet, HashMap};
+use serde::Serialize;
+use crate::customer::CharacteristicSummary;
+
+// struct holding the chi-square comparison of one category between the churned and not churned customers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryComparison {
+    pub category: String,
+    pub statistic: f64, // chi-square statistic of the 2 x k contingency table (groups x characteristics)
+    pub degrees_of_freedom: usize, // k - 1
+    pub significant: bool, // whether the statistic exceeds the critical value at the 5% level
+}
+
+// Function to get the characteristic counts of a summary as category -> characteristic -> count
+pub fn category_counts(summary: &CharacteristicSummary) -> HashMap<String, HashMap<String, usize>> {
+    summary.categories.iter().map(|category| {
+        let counts = category.characteristics.iter().map(|characteristic| (characteristic.name.clone(), characteristic.count)).collect();
+        (category.name.clone(), counts)
+    }).collect()
+}
+
+// Function to compare the characteristic distributions of two groups with a chi-square test per category
+// each category gives a 2 x k table of counts (churned / not churned x the k characteristics seen in either group);
+// categories missing from a group count as all zeros. Returns the categories ranked by statistic (descending),
+// so the categories that distinguish churned from not churned customers the most come first
+pub fn chi_square_by_category(
+    churn_counts: &HashMap<String, HashMap<String, usize>>, // category -> characteristic -> count for churned customers
+    not_churn_counts: &HashMap<String, HashMap<String, usize>>, // same for customers who haven't churned
+) -> Vec<CategoryComparison> {
+    let empty = HashMap::new();
+    let categories: BTreeSet<&String> = churn_counts.keys().chain(not_churn_counts.keys()).collect();
+    let mut comparisons: Vec<CategoryComparison> = categories.into_iter().map(|category| {
+        let churn = churn_counts.get(category).unwrap_or(&empty);
+        let not_churn = not_churn_counts.get(category).unwrap_or(&empty);
+        let characteristics: BTreeSet<&String> = churn.keys().chain(not_churn.keys()).collect();
+        let rows: [Vec<f64>; 2] = [churn, not_churn].map(|counts| {
+            characteristics.iter().map(|&characteristic| counts.get(characteristic).copied().unwrap_or(0) as f64).collect()
+        });
+        let (statistic, degrees_of_freedom) = chi_square_statistic(&rows);
+        CategoryComparison {
+            category: category.clone(),
+            statistic,
+            degrees_of_freedom,
+            significant: degrees_of_freedom > 0 && statistic > chi_square_critical_value(degrees_of_freedom),
+        }
+    }).collect();
+    comparisons.sort_by(|a, b| b.statistic.total_cmp(&a.statistic).then_with(|| a.category.cmp(&b.category)));
+    comparisons
+}
+
+// struct holding one row of the side-by-side comparison of the churned and not churned customers (see compare_prevalence)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrevalenceComparison {
+    pub category: String,
+    pub characteristic: String,
+    pub churn: f64, // share of the characteristic within its category among the churned customers (0-100, 0 if not seen)
+    pub not_churn: f64, // same among the customers who haven't churned
+    pub difference: f64, // churn - not_churn, in percentage points
+}
+
+// Function to build the side-by-side table of every characteristic seen in either group: its share within its category
+// in each group and the difference. Rows are sorted by the size of the difference (descending), then by category and
+// characteristic, so the characteristics that distinguish churned from not churned customers come first
+pub fn compare_prevalence(
+    churn_counts: &HashMap<String, HashMap<String, usize>>, // category -> characteristic -> count for churned customers
+    not_churn_counts: &HashMap<String, HashMap<String, usize>>, // same for customers who haven't churned
+) -> Vec<PrevalenceComparison> {
+    let empty = HashMap::new();
+    // Closure to get the share of a characteristic within its category (0 for an empty or missing category)
+    let share = |counts: &HashMap<String, usize>, characteristic: &String| {
+        let total: usize = counts.values().sum();
+        if total == 0 { 0.0 } else { counts.get(characteristic).copied().unwrap_or(0) as f64 / total as f64 * 100.0 }
+    };
+    let categories: BTreeSet<&String> = churn_counts.keys().chain(not_churn_counts.keys()).collect();
+    let mut rows: Vec<PrevalenceComparison> = categories.into_iter().flat_map(|category| {
+        let churn = churn_counts.get(category).unwrap_or(&empty);
+        let not_churn = not_churn_counts.get(category).unwrap_or(&empty);
+        let characteristics: BTreeSet<&String> = churn.keys().chain(not_churn.keys()).collect();
+        characteristics.into_iter().map(move |characteristic| {
+            let (churn, not_churn) = (share(churn, characteristic), share(not_churn, characteristic));
+            PrevalenceComparison { category: category.clone(), characteristic: characteristic.clone(), churn, not_churn, difference: churn - not_churn }
+        }).collect::<Vec<_>>()
+    }).collect();
+    rows.sort_by(|a, b| b.difference.abs().total_cmp(&a.difference.abs())
+        .then_with(|| a.category.cmp(&b.category))
+        .then_with(|| a.characteristic.cmp(&b.characteristic)));
+    rows
+}
+
+// Function to calculate the chi-square statistic and degrees of freedom of a 2 x k contingency table
+// an empty row or a single column can't show any difference, so it gives (0, 0)
+fn chi_square_statistic(rows: &[Vec<f64>; 2]) -> (f64, usize) {
+    let k = rows[0].len();
+    let row_totals: Vec<f64> = rows.iter().map(|row| row.iter().sum()).collect();
+    let total: f64 = row_totals.iter().sum();
+    if k < 2 || row_totals.contains(&0.0) {
+        return (0.0, 0);
+    }
+    let mut statistic = 0.0;
+    for j in 0..k {
+        let column_total = rows[0][j] + rows[1][j];
+        for (row, row_total) in rows.iter().zip(&row_totals) {
+            let expected = row_total * column_total / total;
+            if expected > 0.0 {
+                statistic += (row[j] - expected).powi(2) / expected;
+            }
+        }
+    }
+    (statistic, k - 1)
+}
+
+// Function to approximate the chi-square critical value at the 5% significance level (Wilson-Hilferty approximation)
+fn chi_square_critical_value(degrees_of_freedom: usize) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let z = 1.644854; // 95th percentile of the standard normal distribution
+    k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(entries: &[(&str, &[(&str, usize)])]) -> HashMap<String, HashMap<String, usize>> {
+        entries.iter().map(|(category, characteristics)| {
+            (category.to_string(), characteristics.iter().map(|(name, count)| (name.to_string(), *count)).collect())
+        }).collect()
+    }
+
+    // test that the category whose distribution differs most between the groups ranks first
+    #[test]
+    fn test_chi_square_ranks_divergent_category_first() {
+        let churn = counts(&[
+            ("Card Type", &[("Blue", 10), ("Silver", 10)]),
+            ("Income Range", &[("Less than $40K", 45), ("$120K +", 5)]),
+            ("Marital Status", &[("Married", 12), ("Single", 8)]),
+        ]);
+        let not_churn = counts(&[
+            ("Card Type", &[("Blue", 20), ("Silver", 20)]), // same distribution as the churned customers
+            ("Income Range", &[("Less than $40K", 5), ("$120K +", 45)]), // opposite distribution
+            ("Marital Status", &[("Married", 8), ("Single", 12)]),
+        ]);
+
+        let comparisons = chi_square_by_category(&churn, &not_churn);
+        let ranking: Vec<&str> = comparisons.iter().map(|comparison| comparison.category.as_str()).collect();
+        assert_eq!(ranking, ["Income Range", "Marital Status", "Card Type"]);
+        assert!((comparisons[0].statistic - 64.0).abs() < 1e-9);
+        assert_eq!(comparisons[0].degrees_of_freedom, 1);
+        assert!(comparisons[0].significant);
+        assert!(!comparisons[1].significant); // 1.6 is below the critical value of about 3.84
+        assert_eq!(comparisons[2].statistic, 0.0);
+        assert!(!comparisons[2].significant);
+    }
+
+    // test the rows and differences of the side-by-side table, largest difference first
+    #[test]
+    fn test_compare_prevalence() {
+        let churn = counts(&[
+            ("Card Type", &[("Blue", 9), ("Silver", 1)]),
+            ("Income Range", &[("Less than $40K", 3), ("$120K +", 1)]),
+        ]);
+        let not_churn = counts(&[
+            ("Card Type", &[("Blue", 18), ("Silver", 2)]), // the same distribution as the churned customers
+            ("Income Range", &[("$120K +", 4)]),
+            ("Marital Status", &[("Married", 5)]), // only in one group
+        ]);
+        let rows = compare_prevalence(&churn, &not_churn);
+        let table: Vec<(&str, &str, f64, f64, f64)> = rows.iter()
+            .map(|row| (row.category.as_str(), row.characteristic.as_str(), row.churn, row.not_churn, row.difference)).collect();
+        assert_eq!(table, vec![
+            ("Marital Status", "Married", 0.0, 100.0, -100.0),
+            ("Income Range", "$120K +", 25.0, 100.0, -75.0), // ties on the size of the difference go to the category, then the characteristic
+            ("Income Range", "Less than $40K", 75.0, 0.0, 75.0),
+            ("Card Type", "Blue", 90.0, 90.0, 0.0),
+            ("Card Type", "Silver", 10.0, 10.0, 0.0),
+        ]);
+        assert!(compare_prevalence(&HashMap::new(), &HashMap::new()).is_empty());
+    }
+
+    // test that a category present in only one group can't be compared
+    #[test]
+    fn test_chi_square_missing_category() {
+        let churn = counts(&[("Card Type", &[("Blue", 3), ("Gold", 1)])]);
+        let comparisons = chi_square_by_category(&churn, &HashMap::new());
+        assert_eq!(comparisons[0].statistic, 0.0);
+        assert_eq!(comparisons[0].degrees_of_freedom, 0);
+        assert!(!comparisons[0].significant);
+    }
+}